@@ -0,0 +1,2 @@
+-- Set when a post is edited, NULL for posts that were never updated
+ALTER TABLE posts ADD COLUMN IF NOT EXISTS updated_at TIMESTAMPTZ;
@@ -0,0 +1,12 @@
+-- Follows table
+CREATE TABLE IF NOT EXISTS follows (
+    follower_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    followee_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (follower_id, followee_id),
+    CHECK (follower_id <> followee_id)
+);
+
+-- Listing a user's followers (the primary key covers the following side)
+CREATE INDEX IF NOT EXISTS idx_follows_followee_created_at
+  ON follows(followee_id, created_at DESC);
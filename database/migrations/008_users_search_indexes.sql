@@ -0,0 +1,6 @@
+-- Case-insensitive prefix search on username/email (LIKE 'abc%')
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_users_username_prefix
+  ON users(lower(username) text_pattern_ops);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_users_email_prefix
+  ON users(lower(email) text_pattern_ops);
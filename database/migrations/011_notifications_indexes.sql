@@ -0,0 +1,3 @@
+-- Unread counts and per-user listing
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_notifications_user_read_created_at
+  ON notifications(user_id, read, created_at DESC);
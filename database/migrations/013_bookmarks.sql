@@ -0,0 +1,11 @@
+-- Bookmarks table
+CREATE TABLE IF NOT EXISTS bookmarks (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    post_id UUID NOT NULL REFERENCES posts(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, post_id)
+);
+
+-- List a user's bookmarks, most recent first
+CREATE INDEX IF NOT EXISTS idx_bookmarks_user_created_at
+  ON bookmarks(user_id, created_at DESC);
@@ -0,0 +1,29 @@
+ALTER TABLE posts ADD COLUMN IF NOT EXISTS reposted_post_id UUID REFERENCES posts(id) ON DELETE CASCADE;
+ALTER TABLE posts ADD COLUMN IF NOT EXISTS reposts_count integer NOT NULL DEFAULT 0;
+
+-- One repost per user and post
+CREATE UNIQUE INDEX IF NOT EXISTS idx_posts_author_reposted_post
+  ON posts(author_id, reposted_post_id)
+  WHERE reposted_post_id IS NOT NULL;
+
+CREATE OR REPLACE FUNCTION increment_reposts_count() RETURNS trigger AS $$
+BEGIN
+  UPDATE posts SET reposts_count = reposts_count + 1 WHERE id = NEW.reposted_post_id;
+  RETURN NEW;
+END $$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION decrement_reposts_count() RETURNS trigger AS $$
+BEGIN
+  UPDATE posts SET reposts_count = reposts_count - 1 WHERE id = OLD.reposted_post_id;
+  RETURN OLD;
+END $$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS posts_reposts_inc ON posts;
+CREATE TRIGGER posts_reposts_inc AFTER INSERT ON posts
+  FOR EACH ROW WHEN (NEW.reposted_post_id IS NOT NULL)
+  EXECUTE FUNCTION increment_reposts_count();
+
+DROP TRIGGER IF EXISTS posts_reposts_dec ON posts;
+CREATE TRIGGER posts_reposts_dec AFTER DELETE ON posts
+  FOR EACH ROW WHEN (OLD.reposted_post_id IS NOT NULL)
+  EXECUTE FUNCTION decrement_reposts_count();
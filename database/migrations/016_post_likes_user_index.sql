@@ -0,0 +1,2 @@
+-- Backs GET /users/{userId}/likes (a user's likes, newest first)
+CREATE INDEX IF NOT EXISTS idx_post_likes_user_created_at ON post_likes(user_id, created_at DESC);
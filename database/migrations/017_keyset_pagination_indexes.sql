@@ -0,0 +1,9 @@
+-- Keyset pagination compares (created_at, id) pairs, index both so pages are a single range scan
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_posts_created_at_id
+  ON posts(created_at DESC, id DESC);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_users_created_at_id
+  ON users(created_at DESC, id DESC);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_comments_post_created_at_id
+  ON comments(post_id, created_at, id);
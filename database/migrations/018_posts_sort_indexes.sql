@@ -0,0 +1,5 @@
+-- Indexes behind GET /posts?sort=...&order=...
+-- createdAt (asc or desc) is served by idx_posts_created_at_id (017), read backwards for asc.
+-- likeCount ties are broken by id so pages stay stable, the index is read backwards for desc.
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_posts_likes_count_id
+  ON posts(likes_count, id);
@@ -0,0 +1,31 @@
+ALTER TABLE posts ADD COLUMN IF NOT EXISTS comments_count integer NOT NULL DEFAULT 0;
+
+-- Backfill existing counts (safe to skip in empty/dev DB)
+UPDATE posts p
+SET comments_count = c.cnt
+FROM (
+  SELECT post_id, COUNT(*)::int AS cnt
+  FROM comments
+  GROUP BY post_id
+) c
+WHERE p.id = c.post_id;
+
+CREATE OR REPLACE FUNCTION increment_comments_count() RETURNS trigger AS $$
+BEGIN
+  UPDATE posts SET comments_count = comments_count + 1 WHERE id = NEW.post_id;
+  RETURN NEW;
+END $$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION decrement_comments_count() RETURNS trigger AS $$
+BEGIN
+  UPDATE posts SET comments_count = comments_count - 1 WHERE id = OLD.post_id;
+  RETURN OLD;
+END $$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS comments_inc ON comments;
+CREATE TRIGGER comments_inc AFTER INSERT ON comments
+  FOR EACH ROW EXECUTE FUNCTION increment_comments_count();
+
+DROP TRIGGER IF EXISTS comments_dec ON comments;
+CREATE TRIGGER comments_dec AFTER DELETE ON comments
+  FOR EACH ROW EXECUTE FUNCTION decrement_comments_count();
@@ -0,0 +1,49 @@
+ALTER TABLE posts ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
+
+-- Soft-deleted posts and users can't be referenced by new likes, comments, bookmarks,
+-- follows or conversations. Raised as foreign_key_violation so callers treat them as missing.
+-- One function per referencing column: these run on the hottest inserts, NEW is read directly.
+CREATE OR REPLACE FUNCTION reject_deleted_post() RETURNS trigger AS $$
+BEGIN
+  IF EXISTS (SELECT 1 FROM posts WHERE id = NEW.post_id AND deleted_at IS NOT NULL) THEN
+    RAISE foreign_key_violation USING MESSAGE = 'referenced post is deleted';
+  END IF;
+  RETURN NEW;
+END $$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION reject_deleted_user() RETURNS trigger AS $$
+BEGIN
+  IF EXISTS (SELECT 1 FROM users WHERE id = NEW.user_id AND deleted_at IS NOT NULL) THEN
+    RAISE foreign_key_violation USING MESSAGE = 'referenced user is deleted';
+  END IF;
+  RETURN NEW;
+END $$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION reject_deleted_followee() RETURNS trigger AS $$
+BEGIN
+  IF EXISTS (SELECT 1 FROM users WHERE id = NEW.followee_id AND deleted_at IS NOT NULL) THEN
+    RAISE foreign_key_violation USING MESSAGE = 'referenced user is deleted';
+  END IF;
+  RETURN NEW;
+END $$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS post_likes_reject_deleted ON post_likes;
+CREATE TRIGGER post_likes_reject_deleted BEFORE INSERT ON post_likes
+  FOR EACH ROW EXECUTE FUNCTION reject_deleted_post();
+
+DROP TRIGGER IF EXISTS comments_reject_deleted ON comments;
+CREATE TRIGGER comments_reject_deleted BEFORE INSERT ON comments
+  FOR EACH ROW EXECUTE FUNCTION reject_deleted_post();
+
+DROP TRIGGER IF EXISTS bookmarks_reject_deleted ON bookmarks;
+CREATE TRIGGER bookmarks_reject_deleted BEFORE INSERT ON bookmarks
+  FOR EACH ROW EXECUTE FUNCTION reject_deleted_post();
+
+DROP TRIGGER IF EXISTS follows_reject_deleted ON follows;
+CREATE TRIGGER follows_reject_deleted BEFORE INSERT ON follows
+  FOR EACH ROW EXECUTE FUNCTION reject_deleted_followee();
+
+DROP TRIGGER IF EXISTS conversation_participants_reject_deleted ON conversation_participants;
+CREATE TRIGGER conversation_participants_reject_deleted BEFORE INSERT ON conversation_participants
+  FOR EACH ROW EXECUTE FUNCTION reject_deleted_user();
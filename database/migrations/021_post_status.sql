@@ -0,0 +1,16 @@
+ALTER TABLE posts ADD COLUMN IF NOT EXISTS status TEXT NOT NULL DEFAULT 'published'
+  CHECK (status IN ('draft', 'published'));
+
+-- Drafts can't be liked, commented or bookmarked until they're published,
+-- same as soft-deleted posts (020_soft_delete.sql)
+CREATE OR REPLACE FUNCTION reject_deleted_post() RETURNS trigger AS $$
+BEGIN
+  IF EXISTS (
+    SELECT 1 FROM posts
+    WHERE id = NEW.post_id
+      AND (deleted_at IS NOT NULL OR status <> 'published')
+  ) THEN
+    RAISE foreign_key_violation USING MESSAGE = 'referenced post is deleted or unpublished';
+  END IF;
+  RETURN NEW;
+END $$ LANGUAGE plpgsql;
@@ -0,0 +1,6 @@
+-- Drafts with a publish_at are published by the scheduled publishing job once it's due
+ALTER TABLE posts ADD COLUMN IF NOT EXISTS publish_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_posts_scheduled
+  ON posts(publish_at)
+  WHERE status = 'draft' AND publish_at IS NOT NULL AND deleted_at IS NULL;
@@ -0,0 +1,6 @@
+-- Index-only scans for GET /posts/count and GET /users/count
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_posts_visible
+  ON posts(id) WHERE deleted_at IS NULL AND status = 'published';
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_users_active
+  ON users(id) WHERE deleted_at IS NULL;
@@ -0,0 +1,3 @@
+-- Row versions for optimistic concurrency, bumped by every API update of the row
+ALTER TABLE users ADD COLUMN IF NOT EXISTS version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE posts ADD COLUMN IF NOT EXISTS version INTEGER NOT NULL DEFAULT 1;
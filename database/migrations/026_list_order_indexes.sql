@@ -0,0 +1,24 @@
+-- List queries break created_at ties on the row's key, index the full sort so pages stay a single range scan
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_posts_author_created_at_id
+  ON posts(author_id, created_at DESC, id DESC);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_follows_followee_created_at_follower
+  ON follows(followee_id, created_at DESC, follower_id DESC);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_follows_follower_created_at_followee
+  ON follows(follower_id, created_at DESC, followee_id DESC);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_post_hashtags_hashtag_created_at_post
+  ON post_hashtags(hashtag_id, created_at DESC, post_id DESC);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_post_likes_user_created_at_post
+  ON post_likes(user_id, created_at DESC, post_id DESC);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_bookmarks_user_created_at_post
+  ON bookmarks(user_id, created_at DESC, post_id DESC);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_notifications_user_created_at_id
+  ON notifications(user_id, created_at DESC, id DESC);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_messages_conversation_created_at_id
+  ON messages(conversation_id, created_at DESC, id DESC);
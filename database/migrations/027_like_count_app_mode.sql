@@ -0,0 +1,20 @@
+-- posts.likes_count is maintained by the post_likes triggers (004), unless the statement
+-- maintains it itself: those set apibench.like_count_by_app to 'on' for their transaction
+-- (rust-axum LIKE_COUNT_MODE=app) and the triggers skip. Other statements are unaffected.
+CREATE OR REPLACE FUNCTION increment_likes_count() RETURNS trigger AS $$
+BEGIN
+  IF current_setting('apibench.like_count_by_app', true) = 'on' THEN
+    RETURN NEW;
+  END IF;
+  UPDATE posts SET likes_count = likes_count + 1 WHERE id = NEW.post_id;
+  RETURN NEW;
+END $$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION decrement_likes_count() RETURNS trigger AS $$
+BEGIN
+  IF current_setting('apibench.like_count_by_app', true) = 'on' THEN
+    RETURN OLD;
+  END IF;
+  UPDATE posts SET likes_count = likes_count - 1 WHERE id = OLD.post_id;
+  RETURN OLD;
+END $$ LANGUAGE plpgsql;
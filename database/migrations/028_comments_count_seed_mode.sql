@@ -0,0 +1,11 @@
+-- posts.comments_count is maintained by the comments trigger (019), unless the statement
+-- maintains it itself: bulk seeding (rust-axum POST /admin/seed) writes the counts with the
+-- posts and sets apibench.comments_count_by_app to 'on' for its transaction, the trigger skips.
+CREATE OR REPLACE FUNCTION increment_comments_count() RETURNS trigger AS $$
+BEGIN
+  IF current_setting('apibench.comments_count_by_app', true) = 'on' THEN
+    RETURN NEW;
+  END IF;
+  UPDATE posts SET comments_count = comments_count + 1 WHERE id = NEW.post_id;
+  RETURN NEW;
+END $$ LANGUAGE plpgsql;
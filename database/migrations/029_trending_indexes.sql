@@ -0,0 +1,7 @@
+-- Trending only counts the likes and comments of the last hours: range scans on created_at,
+-- carrying post_id so they don't visit the tables
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_post_likes_created_at_post
+  ON post_likes(created_at, post_id);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_comments_created_at_post
+  ON comments(created_at, post_id);
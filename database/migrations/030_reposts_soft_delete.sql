@@ -0,0 +1,42 @@
+-- Reposts are soft-deleted (020_soft_delete.sql): only live reposts count towards the
+-- original's reposts_count and hold the one-repost-per-user slot.
+CREATE UNIQUE INDEX IF NOT EXISTS idx_posts_author_live_reposted_post
+  ON posts(author_id, reposted_post_id)
+  WHERE reposted_post_id IS NOT NULL AND deleted_at IS NULL;
+DROP INDEX IF EXISTS idx_posts_author_reposted_post;
+
+-- Recount, dropping the reposts soft-deleted so far
+UPDATE posts p
+SET reposts_count = COALESCE(r.cnt, 0)
+FROM posts o
+LEFT JOIN (
+  SELECT reposted_post_id, COUNT(*)::int AS cnt
+  FROM posts
+  WHERE reposted_post_id IS NOT NULL AND deleted_at IS NULL
+  GROUP BY reposted_post_id
+) r ON r.reposted_post_id = o.id
+WHERE p.id = o.id
+  AND p.reposts_count <> COALESCE(r.cnt, 0);
+
+-- Deleting a repost decrements the count, restoring it increments it again
+CREATE OR REPLACE FUNCTION update_reposts_count_on_soft_delete() RETURNS trigger AS $$
+BEGIN
+  UPDATE posts
+  SET reposts_count = reposts_count + CASE WHEN NEW.deleted_at IS NULL THEN 1 ELSE -1 END
+  WHERE id = NEW.reposted_post_id;
+  RETURN NEW;
+END $$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS posts_reposts_soft_delete ON posts;
+CREATE TRIGGER posts_reposts_soft_delete AFTER UPDATE OF deleted_at ON posts
+  FOR EACH ROW WHEN (
+    NEW.reposted_post_id IS NOT NULL
+    AND (OLD.deleted_at IS NULL) <> (NEW.deleted_at IS NULL)
+  )
+  EXECUTE FUNCTION update_reposts_count_on_soft_delete();
+
+-- A soft-deleted repost was already uncounted
+DROP TRIGGER IF EXISTS posts_reposts_dec ON posts;
+CREATE TRIGGER posts_reposts_dec AFTER DELETE ON posts
+  FOR EACH ROW WHEN (OLD.reposted_post_id IS NOT NULL AND OLD.deleted_at IS NULL)
+  EXECUTE FUNCTION decrement_reposts_count();
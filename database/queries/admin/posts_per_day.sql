@@ -0,0 +1,19 @@
+-- Posts created on each of the last $1 UTC days, days without posts included
+WITH days AS (
+    SELECT generate_series(
+               (now() AT TIME ZONE 'UTC')::date - ($1::int - 1),
+               (now() AT TIME ZONE 'UTC')::date,
+               interval '1 day'
+           )::date AS day
+),
+counts AS (
+    SELECT (created_at AT TIME ZONE 'UTC')::date AS day, count(*) AS post_count
+    FROM posts
+    WHERE deleted_at IS NULL
+      AND created_at >= ((now() AT TIME ZONE 'UTC')::date - ($1::int - 1)) AT TIME ZONE 'UTC'
+    GROUP BY 1
+)
+SELECT d.day, COALESCE(c.post_count, 0) AS post_count
+FROM days d
+LEFT JOIN counts c ON c.day = d.day
+ORDER BY d.day;
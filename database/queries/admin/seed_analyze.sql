@@ -0,0 +1,2 @@
+-- Fresh statistics once seeded rows are committed, for the plans of the run that follows
+ANALYZE users, posts, comments;
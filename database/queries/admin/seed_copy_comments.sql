@@ -0,0 +1 @@
+COPY comments (id, author_id, post_id, content, created_at) FROM STDIN
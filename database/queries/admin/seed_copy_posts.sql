@@ -0,0 +1 @@
+COPY posts (id, author_id, content, created_at, comments_count) FROM STDIN
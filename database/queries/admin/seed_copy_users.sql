@@ -0,0 +1 @@
+COPY users (id, username, email, password_hash, created_at) FROM STDIN
@@ -0,0 +1,4 @@
+-- Settings of the seeding transaction: no statement timeout for its COPYs, and the
+-- comments_count trigger skipped where it can be (migration 028), counts being copied with the posts
+SELECT set_config('statement_timeout', '0', true),
+       set_config('apibench.comments_count_by_app', 'on', true);
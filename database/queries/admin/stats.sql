@@ -0,0 +1,7 @@
+SELECT (SELECT count(*) FROM users WHERE deleted_at IS NULL) AS total_users,
+       (SELECT count(*) FROM posts WHERE deleted_at IS NULL) AS total_posts,
+       (SELECT count(*) FROM comments) AS total_comments,
+       (SELECT count(*) FROM post_likes) AS total_likes,
+       (SELECT count(*) FROM follows) AS total_follows,
+       (SELECT count(*) FROM bookmarks) AS total_bookmarks,
+       (SELECT count(*) FROM messages) AS total_messages;
@@ -0,0 +1 @@
+SELECT id, username, email, bio, avatar_url, created_at, version FROM users WHERE id = $1 AND deleted_at IS NULL;
\ No newline at end of file
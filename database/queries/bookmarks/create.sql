@@ -0,0 +1 @@
+INSERT INTO bookmarks (user_id, post_id) VALUES ($1, $2);
@@ -0,0 +1 @@
+DELETE FROM bookmarks WHERE user_id = $1 AND post_id = $2;
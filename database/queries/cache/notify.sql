@@ -0,0 +1,2 @@
+-- Publishes a cache invalidation to the other instances (CACHE_NOTIFY)
+SELECT pg_notify($1, $2);
@@ -0,0 +1,4 @@
+-- Whether the comments_count trigger skips sessions maintaining the counter themselves (migration 028)
+SELECT COALESCE(bool_and(prosrc LIKE '%apibench.comments_count_by_app%'), false)
+FROM pg_proc
+WHERE proname = 'increment_comments_count';
@@ -0,0 +1,13 @@
+-- Create the comment and notify the post author in one statement
+WITH created AS (
+    INSERT INTO comments (author_id, post_id, content)
+    VALUES ($1, $2, $3)
+    RETURNING id, author_id, post_id, content, created_at
+), notified AS (
+    INSERT INTO notifications (user_id, actor_id, kind, post_id, comment_id)
+    SELECT p.author_id, created.author_id, 'comment', created.post_id, created.id
+    FROM created
+    JOIN posts p ON p.id = created.post_id
+    WHERE p.author_id <> created.author_id
+)
+SELECT id, author_id, post_id, content, created_at FROM created;
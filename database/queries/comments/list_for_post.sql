@@ -0,0 +1,10 @@
+-- Comment listing of an active post in a single round trip: no row at all when the post
+-- is missing or deleted, one row of NULLs when it has no comment on the requested page.
+-- $1 is the post. The caller appends the page's filters, ORDER BY and LIMIT to the
+-- lateral subquery, closes it with `) c ON true` and repeats the ORDER BY.
+SELECT c.id, c.author_id, c.post_id, c.content, c.created_at
+FROM (SELECT id FROM posts WHERE id = $1 AND deleted_at IS NULL) p
+LEFT JOIN LATERAL (
+    SELECT c.id, c.author_id, c.post_id, c.content, c.created_at
+    FROM comments c
+    WHERE c.post_id = p.id
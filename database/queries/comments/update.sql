@@ -0,0 +1,4 @@
+UPDATE comments
+SET content = $3
+WHERE id = $1 AND post_id = $2
+RETURNING id, author_id, post_id, content, created_at;
@@ -0,0 +1,2 @@
+INSERT INTO conversation_participants (conversation_id, user_id)
+SELECT $1, unnest($2::uuid[]);
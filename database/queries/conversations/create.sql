@@ -0,0 +1 @@
+INSERT INTO conversations DEFAULT VALUES RETURNING id, created_at;
@@ -0,0 +1,16 @@
+-- Only inserts when the sender takes part in the conversation, and bumps last_message_at
+WITH created AS (
+    INSERT INTO messages (conversation_id, sender_id, content)
+    SELECT $1, $2, $3
+    WHERE EXISTS (
+        SELECT 1 FROM conversation_participants
+        WHERE conversation_id = $1 AND user_id = $2
+    )
+    RETURNING id, conversation_id, sender_id, content, created_at
+), bumped AS (
+    UPDATE conversations c
+    SET last_message_at = created.created_at
+    FROM created
+    WHERE c.id = created.conversation_id
+)
+SELECT id, conversation_id, sender_id, content, created_at FROM created;
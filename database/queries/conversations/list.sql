@@ -0,0 +1,14 @@
+SELECT c.id,
+       c.created_at,
+       c.last_message_at,
+       ARRAY(
+           SELECT cp.user_id
+           FROM conversation_participants cp
+           WHERE cp.conversation_id = c.id
+           ORDER BY cp.joined_at, cp.user_id
+       ) AS participant_ids
+FROM conversation_participants me
+JOIN conversations c ON c.id = me.conversation_id
+WHERE me.user_id = $1
+ORDER BY COALESCE(c.last_message_at, c.created_at) DESC, c.id DESC
+LIMIT $2 OFFSET $3;
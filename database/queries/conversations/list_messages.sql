@@ -0,0 +1,5 @@
+SELECT id, conversation_id, sender_id, content, created_at
+FROM messages
+WHERE conversation_id = $1
+ORDER BY created_at DESC, id DESC
+LIMIT $2 OFFSET $3;
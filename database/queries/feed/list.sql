@@ -0,0 +1,31 @@
+-- Posts from followed users, keyset paginated on (created_at, id)
+SELECT p.id,
+       p.author_id,
+       p.content,
+       p.created_at,
+       p.updated_at,
+       p.status,
+       p.publish_at,
+       p.version,
+       p.likes_count::bigint AS like_count,
+       p.comments_count::bigint AS comment_count,
+       p.reposts_count::bigint AS repost_count,
+       p.reposted_post_id,
+       o.author_id AS original_author_id,
+       o.content AS original_content,
+       o.created_at AS original_created_at,
+       o.updated_at AS original_updated_at,
+       o.version AS original_version,
+       o.likes_count::bigint AS original_like_count,
+       o.comments_count::bigint AS original_comment_count,
+       o.reposts_count::bigint AS original_repost_count,
+       EXISTS (SELECT 1 FROM post_likes pl WHERE pl.post_id = p.id AND pl.user_id = $1) AS liked_by_me
+FROM follows f
+JOIN posts p ON p.author_id = f.followee_id
+LEFT JOIN posts o ON o.id = p.reposted_post_id AND o.deleted_at IS NULL
+WHERE f.follower_id = $1
+  AND p.deleted_at IS NULL
+  AND p.status = 'published'
+  AND ($2::timestamptz IS NULL OR (p.created_at, p.id) < ($2, $3::uuid))
+ORDER BY p.created_at DESC, p.id DESC
+LIMIT $4;
@@ -0,0 +1 @@
+INSERT INTO follows (follower_id, followee_id) VALUES ($1, $2);
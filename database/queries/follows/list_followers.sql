@@ -0,0 +1,6 @@
+SELECT u.id, u.username, u.email, u.bio, u.avatar_url, u.created_at, u.version
+FROM follows f
+JOIN users u ON u.id = f.follower_id AND u.deleted_at IS NULL
+WHERE f.followee_id = $1
+ORDER BY f.created_at DESC, f.follower_id DESC
+LIMIT $2 OFFSET $3;
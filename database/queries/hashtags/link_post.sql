@@ -0,0 +1,10 @@
+-- DO UPDATE (instead of DO NOTHING) so existing tags are returned and locked too.
+-- Tags are inserted sorted so concurrent posts lock them in the same order.
+WITH tags AS (
+    INSERT INTO hashtags (tag)
+    SELECT tag FROM unnest($2::text[]) AS tag ORDER BY 1
+    ON CONFLICT (tag) DO UPDATE SET tag = EXCLUDED.tag
+    RETURNING id
+)
+INSERT INTO post_hashtags (post_id, hashtag_id, created_at)
+SELECT $1, id, $3 FROM tags;
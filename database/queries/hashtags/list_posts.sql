@@ -0,0 +1,30 @@
+SELECT p.id,
+       p.author_id,
+       p.content,
+       p.created_at,
+       p.updated_at,
+       p.status,
+       p.publish_at,
+       p.version,
+       p.likes_count::bigint AS like_count,
+       p.comments_count::bigint AS comment_count,
+       p.reposts_count::bigint AS repost_count,
+       p.reposted_post_id,
+       o.author_id AS original_author_id,
+       o.content AS original_content,
+       o.created_at AS original_created_at,
+       o.updated_at AS original_updated_at,
+       o.version AS original_version,
+       o.likes_count::bigint AS original_like_count,
+       o.comments_count::bigint AS original_comment_count,
+       o.reposts_count::bigint AS original_repost_count,
+       CASE WHEN $4::uuid IS NOT NULL THEN EXISTS (SELECT 1 FROM post_likes pl WHERE pl.post_id = p.id AND pl.user_id = $4) END AS liked_by_me
+FROM hashtags h
+JOIN post_hashtags ph ON ph.hashtag_id = h.id
+JOIN posts p ON p.id = ph.post_id
+LEFT JOIN posts o ON o.id = p.reposted_post_id AND o.deleted_at IS NULL
+WHERE h.tag = $1
+  AND p.deleted_at IS NULL
+  AND p.status = 'published'
+ORDER BY ph.created_at DESC, ph.post_id DESC
+LIMIT $2 OFFSET $3;
@@ -0,0 +1,2 @@
+-- Before linking the tags of an edited post again
+DELETE FROM post_hashtags WHERE post_id = $1;
@@ -0,0 +1,2 @@
+-- Database round trip of GET /health/ready
+SELECT 1;
@@ -0,0 +1,5 @@
+UPDATE idempotency_keys
+SET status_code = $3,
+    content_type = $4,
+    response_body = $5
+WHERE user_id = $1 AND idempotency_key = $2;
@@ -0,0 +1,3 @@
+SELECT request_hash, status_code, content_type, response_body
+FROM idempotency_keys
+WHERE user_id = $1 AND idempotency_key = $2;
@@ -0,0 +1 @@
+DELETE FROM idempotency_keys WHERE user_id = $1 AND idempotency_key = $2;
@@ -0,0 +1,12 @@
+-- Claims (user, key) for a new request, taking over an expired entry.
+-- No row comes back when a live entry already exists.
+INSERT INTO idempotency_keys (user_id, idempotency_key, request_hash)
+VALUES ($1, $2, $3)
+ON CONFLICT (user_id, idempotency_key) DO UPDATE
+SET request_hash = EXCLUDED.request_hash,
+    status_code = NULL,
+    content_type = NULL,
+    response_body = NULL,
+    created_at = NOW()
+WHERE idempotency_keys.created_at < NOW() - make_interval(secs => $4)
+RETURNING user_id;
@@ -0,0 +1,4 @@
+-- Whether the likes_count triggers skip sessions maintaining the counter themselves (migration 027)
+SELECT COALESCE(bool_and(prosrc LIKE '%apibench.like_count_by_app%'), false)
+FROM pg_proc
+WHERE proname IN ('increment_likes_count', 'decrement_likes_count');
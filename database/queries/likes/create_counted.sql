@@ -0,0 +1,24 @@
+-- Like the post, bump its likes_count and notify its author in one statement
+-- (LIKE_COUNT_MODE=app; nothing is written when already liked)
+WITH app_counted AS (
+    -- Transaction-local, makes the post_likes triggers skip this statement
+    SELECT set_config('apibench.like_count_by_app', 'on', true)
+),
+liked AS (
+    INSERT INTO post_likes (user_id, post_id)
+    SELECT $1, $2
+    FROM app_counted
+    ON CONFLICT DO NOTHING
+    RETURNING post_id
+),
+counted AS (
+    UPDATE posts p
+    SET likes_count = p.likes_count + 1
+    FROM liked
+    WHERE p.id = liked.post_id
+    RETURNING p.id, p.author_id
+)
+INSERT INTO notifications (user_id, actor_id, kind, post_id)
+SELECT author_id, $1, 'like', id
+FROM counted
+WHERE author_id <> $1;
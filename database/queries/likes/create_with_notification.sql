@@ -0,0 +1,12 @@
+-- Like the post and notify its author in one statement (nothing is written when already liked)
+WITH liked AS (
+    INSERT INTO post_likes (user_id, post_id)
+    VALUES ($1, $2)
+    ON CONFLICT DO NOTHING
+    RETURNING post_id
+)
+INSERT INTO notifications (user_id, actor_id, kind, post_id)
+SELECT p.author_id, $1, 'like', p.id
+FROM liked
+JOIN posts p ON p.id = liked.post_id
+WHERE p.author_id <> $1;
@@ -0,0 +1,16 @@
+-- Unlike the post and decrement its likes_count in one statement (LIKE_COUNT_MODE=app).
+-- Affects no row when the post wasn't liked.
+WITH app_counted AS (
+    -- Transaction-local, makes the post_likes triggers skip this statement
+    SELECT set_config('apibench.like_count_by_app', 'on', true)
+),
+unliked AS (
+    DELETE FROM post_likes
+    USING app_counted
+    WHERE user_id = $1 AND post_id = $2
+    RETURNING post_id
+)
+UPDATE posts p
+SET likes_count = p.likes_count - 1
+FROM unliked
+WHERE p.id = unliked.post_id;
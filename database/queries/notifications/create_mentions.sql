@@ -0,0 +1,7 @@
+-- $4 holds lowercased usernames, self-mentions are skipped
+INSERT INTO notifications (user_id, actor_id, kind, post_id, comment_id)
+SELECT u.id, $1, 'mention', $2, $3
+FROM users u
+WHERE lower(u.username) = ANY($4::text[])
+  AND u.id <> $1
+  AND u.deleted_at IS NULL;
@@ -0,0 +1,5 @@
+SELECT id, kind, actor_id, post_id, comment_id, read, created_at
+FROM notifications
+WHERE user_id = $1
+ORDER BY created_at DESC, id DESC
+LIMIT $2 OFFSET $3;
@@ -0,0 +1,6 @@
+-- comments_count is the counter cache kept by 019_posts_comments_counter.sql
+SELECT comments_count::bigint
+FROM posts
+WHERE id = $1
+  AND deleted_at IS NULL
+  AND (status = 'published' OR author_id = $2);
@@ -0,0 +1,3 @@
+-- Base count for post listings, filters are appended by the caller
+SELECT count(*)
+FROM posts p
@@ -0,0 +1 @@
+SELECT count(*) FROM posts WHERE deleted_at IS NULL AND status = 'published';
@@ -0,0 +1,15 @@
+-- Single multi-row insert, ids are generated by the caller so rows come back in input order
+WITH input AS (
+    SELECT id, content, status, publish_at, ord
+    FROM unnest($2::uuid[], $3::text[], $4::text[], $5::timestamptz[])
+         WITH ORDINALITY AS t(id, content, status, publish_at, ord)
+),
+created AS (
+    INSERT INTO posts (id, author_id, content, status, publish_at)
+    SELECT id, $1, content, status, publish_at FROM input
+    RETURNING id, author_id, content, status, publish_at, created_at, version
+)
+SELECT c.id, c.author_id, c.content, c.status, c.publish_at, c.created_at, c.version
+FROM created c
+JOIN input i ON i.id = c.id
+ORDER BY i.ord;
@@ -0,0 +1,3 @@
+INSERT INTO posts (author_id, content, status, publish_at)
+VALUES ($1, $2, $3, $4)
+RETURNING id, author_id, content, status, publish_at, created_at, version;
@@ -0,0 +1 @@
+SELECT author_id FROM posts WHERE id = $1 AND deleted_at IS NULL;
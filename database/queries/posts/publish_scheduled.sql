@@ -0,0 +1,27 @@
+-- Publishes up to $1 due scheduled drafts, same as posts/publish.sql.
+-- SKIP LOCKED lets several API instances run the job without publishing a post twice.
+WITH due AS (
+    SELECT id
+    FROM posts
+    WHERE status = 'draft'
+      AND publish_at <= NOW()
+      AND deleted_at IS NULL
+    ORDER BY publish_at
+    LIMIT $1
+    FOR UPDATE SKIP LOCKED
+), published AS (
+    UPDATE posts p
+    SET status = 'published',
+        created_at = NOW(),
+        publish_at = NULL,
+        version = p.version + 1
+    FROM due
+    WHERE p.id = due.id
+    RETURNING p.id, p.author_id, p.content, p.created_at
+), retagged AS (
+    UPDATE post_hashtags ph
+    SET created_at = pb.created_at
+    FROM published pb
+    WHERE ph.post_id = pb.id
+)
+SELECT id, author_id, content FROM published;
@@ -0,0 +1,36 @@
+-- Reposting a repost points at the original post.
+-- The CTE snapshot predates the count trigger, so the new repost is added here.
+WITH created AS (
+    INSERT INTO posts (author_id, content, reposted_post_id)
+    SELECT $1, '', COALESCE(src.reposted_post_id, src.id)
+    FROM posts src
+    LEFT JOIN posts orig ON orig.id = src.reposted_post_id
+    WHERE src.id = $2
+      AND src.deleted_at IS NULL
+      AND src.status = 'published'
+      AND orig.deleted_at IS NULL
+    RETURNING *
+)
+SELECT p.id,
+       p.author_id,
+       p.content,
+       p.created_at,
+       p.updated_at,
+       p.status,
+       p.publish_at,
+       p.version,
+       p.likes_count::bigint AS like_count,
+       p.comments_count::bigint AS comment_count,
+       p.reposts_count::bigint AS repost_count,
+       p.reposted_post_id,
+       o.author_id AS original_author_id,
+       o.content AS original_content,
+       o.created_at AS original_created_at,
+       o.updated_at AS original_updated_at,
+       o.version AS original_version,
+       o.likes_count::bigint AS original_like_count,
+       o.comments_count::bigint AS original_comment_count,
+       o.reposts_count::bigint + 1 AS original_repost_count,
+       EXISTS (SELECT 1 FROM post_likes pl WHERE pl.post_id = p.id AND pl.user_id = $1) AS liked_by_me
+FROM created p
+LEFT JOIN posts o ON o.id = p.reposted_post_id AND o.deleted_at IS NULL;
@@ -0,0 +1,39 @@
+-- Posts of a deleted user stay deleted, and so does a repost once its author reposted
+-- the same post again (one live repost per user and post)
+WITH restored AS (
+    UPDATE posts
+    SET deleted_at = NULL
+    WHERE id = $1
+      AND deleted_at IS NOT NULL
+      AND NOT EXISTS (SELECT 1 FROM users u WHERE u.id = posts.author_id AND u.deleted_at IS NOT NULL)
+      AND NOT EXISTS (
+          SELECT 1 FROM posts r
+          WHERE r.author_id = posts.author_id
+            AND r.reposted_post_id = posts.reposted_post_id
+            AND r.deleted_at IS NULL
+      )
+    RETURNING *
+)
+SELECT p.id,
+       p.author_id,
+       p.content,
+       p.created_at,
+       p.updated_at,
+       p.status,
+       p.publish_at,
+       p.version,
+       p.likes_count::bigint AS like_count,
+       p.comments_count::bigint AS comment_count,
+       p.reposts_count::bigint AS repost_count,
+       p.reposted_post_id,
+       o.author_id AS original_author_id,
+       o.content AS original_content,
+       o.created_at AS original_created_at,
+       o.updated_at AS original_updated_at,
+       o.version AS original_version,
+       o.likes_count::bigint AS original_like_count,
+       o.comments_count::bigint AS original_comment_count,
+       o.reposts_count::bigint AS original_repost_count,
+       EXISTS (SELECT 1 FROM post_likes pl WHERE pl.post_id = p.id AND pl.user_id = $2) AS liked_by_me
+FROM restored p
+LEFT JOIN posts o ON o.id = p.reposted_post_id AND o.deleted_at IS NULL;
@@ -0,0 +1,18 @@
+-- Soft deletes the post if $2 wrote it or $3 (admin) is set, in one statement.
+-- No row: the post doesn't exist; deleted = false: it isn't the caller's.
+-- The row lock makes a concurrent delete of the same post see it as gone.
+WITH target AS (
+    SELECT id, author_id
+    FROM posts
+    WHERE id = $1 AND deleted_at IS NULL
+    FOR UPDATE
+), deleted AS (
+    UPDATE posts p
+    SET deleted_at = NOW()
+    FROM target t
+    WHERE p.id = t.id
+      AND (t.author_id = $2 OR $3)
+    RETURNING p.id
+)
+SELECT EXISTS (SELECT 1 FROM deleted) AS deleted
+FROM target;
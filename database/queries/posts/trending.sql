@@ -0,0 +1,42 @@
+-- Likes and comments (weighted x2) received within the last $1 hours,
+-- divided by a gravity factor on the post age so older posts decay
+SELECT p.id,
+       p.author_id,
+       p.content,
+       p.created_at,
+       p.updated_at,
+       p.status,
+       p.publish_at,
+       p.version,
+       p.likes_count::bigint AS like_count,
+       p.comments_count::bigint AS comment_count,
+       p.reposts_count::bigint AS repost_count,
+       p.reposted_post_id,
+       o.author_id AS original_author_id,
+       o.content AS original_content,
+       o.created_at AS original_created_at,
+       o.updated_at AS original_updated_at,
+       o.version AS original_version,
+       o.likes_count::bigint AS original_like_count,
+       o.comments_count::bigint AS original_comment_count,
+       o.reposts_count::bigint AS original_repost_count,
+       CASE WHEN $3::uuid IS NOT NULL THEN EXISTS (SELECT 1 FROM post_likes pl WHERE pl.post_id = p.id AND pl.user_id = $3) END AS liked_by_me
+FROM (
+    SELECT post_id, SUM(weight) AS interactions
+    FROM (
+        SELECT post_id, 1.0 AS weight
+        FROM post_likes
+        WHERE created_at >= NOW() - make_interval(hours => $1)
+        UNION ALL
+        SELECT post_id, 2.0 AS weight
+        FROM comments
+        WHERE created_at >= NOW() - make_interval(hours => $1)
+    ) i
+    GROUP BY post_id
+) s
+JOIN posts p ON p.id = s.post_id AND p.deleted_at IS NULL AND p.status = 'published'
+LEFT JOIN posts o ON o.id = p.reposted_post_id AND o.deleted_at IS NULL
+ORDER BY s.interactions / power(EXTRACT(EPOCH FROM NOW() - p.created_at) / 3600 + 2, 1.5) DESC,
+         p.created_at DESC,
+         p.id DESC
+LIMIT $2;
@@ -0,0 +1,35 @@
+-- Updates the post if $3 wrote it or $5 (admin) is set, in one statement.
+-- $4 is the version the caller last saw, NULL skips the check
+WITH updated AS (
+    UPDATE posts
+    SET content = $2,
+        updated_at = NOW(),
+        version = version + 1
+    WHERE id = $1 AND deleted_at IS NULL
+      AND (author_id = $3 OR $5)
+      AND ($4::int IS NULL OR version = $4)
+    RETURNING *
+)
+SELECT p.id,
+       p.author_id,
+       p.content,
+       p.created_at,
+       p.updated_at,
+       p.status,
+       p.publish_at,
+       p.version,
+       p.likes_count::bigint AS like_count,
+       p.comments_count::bigint AS comment_count,
+       p.reposts_count::bigint AS repost_count,
+       p.reposted_post_id,
+       o.author_id AS original_author_id,
+       o.content AS original_content,
+       o.created_at AS original_created_at,
+       o.updated_at AS original_updated_at,
+       o.version AS original_version,
+       o.likes_count::bigint AS original_like_count,
+       o.comments_count::bigint AS original_comment_count,
+       o.reposts_count::bigint AS original_repost_count,
+       EXISTS (SELECT 1 FROM post_likes pl WHERE pl.post_id = p.id AND pl.user_id = $3) AS liked_by_me
+FROM updated p
+LEFT JOIN posts o ON o.id = p.reposted_post_id AND o.deleted_at IS NULL;
@@ -0,0 +1,2 @@
+-- No row: the user was hard deleted
+SELECT is_admin, deleted_at IS NOT NULL AS deleted FROM users WHERE id = $1;
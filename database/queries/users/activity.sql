@@ -0,0 +1,39 @@
+-- Posts, reposts, comments and (when $5) likes of user $1, newest first.
+-- Each branch is keyset paginated on (created_at, id) and limited on its own index before the merge.
+(
+    SELECT CASE WHEN p.reposted_post_id IS NULL THEN 'post' ELSE 'repost' END AS kind,
+           p.id,
+           COALESCE(p.reposted_post_id, p.id) AS post_id,
+           p.content,
+           p.created_at
+    FROM posts p
+    WHERE p.author_id = $1
+      AND p.deleted_at IS NULL
+      AND p.status = 'published'
+      AND ($2::timestamptz IS NULL OR (p.created_at, p.id) < ($2, $3::uuid))
+    ORDER BY p.created_at DESC, p.id DESC
+    LIMIT $4
+)
+UNION ALL
+(
+    SELECT 'comment' AS kind, c.id, c.post_id, c.content, c.created_at
+    FROM comments c
+    JOIN posts p ON p.id = c.post_id AND p.deleted_at IS NULL AND p.status = 'published'
+    WHERE c.author_id = $1
+      AND ($2::timestamptz IS NULL OR (c.created_at, c.id) < ($2, $3::uuid))
+    ORDER BY c.created_at DESC, c.id DESC
+    LIMIT $4
+)
+UNION ALL
+(
+    SELECT 'like' AS kind, l.post_id AS id, l.post_id, NULL AS content, l.created_at
+    FROM post_likes l
+    JOIN posts p ON p.id = l.post_id AND p.deleted_at IS NULL
+    WHERE $5
+      AND l.user_id = $1
+      AND ($2::timestamptz IS NULL OR (l.created_at, l.post_id) < ($2, $3::uuid))
+    ORDER BY l.created_at DESC, l.post_id DESC
+    LIMIT $4
+)
+ORDER BY created_at DESC, id DESC
+LIMIT $4;
@@ -0,0 +1 @@
+SELECT count(*) FROM users WHERE deleted_at IS NULL;
@@ -0,0 +1,6 @@
+-- Rows clashing with an existing username or email are skipped, the caller reports them as failed
+INSERT INTO users (username, email, password_hash)
+SELECT username, email, password_hash
+FROM unnest($1::text[], $2::text[], $3::text[]) AS t(username, email, password_hash)
+ON CONFLICT DO NOTHING
+RETURNING id, username, email, bio, avatar_url, created_at, version;
@@ -0,0 +1,5 @@
+SELECT id, username, email, bio, avatar_url, created_at, version
+FROM users
+WHERE deleted_at IS NULL
+ORDER BY created_at DESC, id DESC
+LIMIT $1 OFFSET $2;
@@ -0,0 +1,7 @@
+-- Keyset paginated on (created_at, id), starts from the top when $1 is NULL
+SELECT id, username, email, bio, avatar_url, created_at, version
+FROM users
+WHERE deleted_at IS NULL
+  AND ($1::timestamptz IS NULL OR (created_at, id) < ($1, $2::uuid))
+ORDER BY created_at DESC, id DESC
+LIMIT $3;
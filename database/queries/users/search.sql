@@ -0,0 +1,7 @@
+-- $1 is a lowercased, LIKE-escaped prefix followed by '%'
+SELECT id, username, email, bio, avatar_url, created_at, version
+FROM users
+WHERE deleted_at IS NULL
+  AND (lower(username) LIKE $1 OR lower(email) LIKE $1)
+ORDER BY username ASC
+LIMIT $2 OFFSET $3;
@@ -0,0 +1,15 @@
+-- Soft-deletes the user and hides all of their posts with the same timestamp
+WITH deleted AS (
+    UPDATE users
+    SET deleted_at = NOW()
+    WHERE id = $1
+      AND deleted_at IS NULL
+    RETURNING id, deleted_at
+), hidden_posts AS (
+    UPDATE posts p
+    SET deleted_at = d.deleted_at
+    FROM deleted d
+    WHERE p.author_id = d.id
+      AND p.deleted_at IS NULL
+)
+SELECT id FROM deleted;
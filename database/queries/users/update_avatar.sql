@@ -0,0 +1,6 @@
+UPDATE users
+SET avatar_url = $2,
+    version = version + 1
+WHERE id = $1
+  AND deleted_at IS NULL
+RETURNING id, username, email, bio, avatar_url, created_at, version;
@@ -0,0 +1,8 @@
+-- $3 is the version the caller last saw, NULL skips the check
+UPDATE users
+SET bio = $2,
+    version = version + 1
+WHERE id = $1
+  AND deleted_at IS NULL
+  AND ($3::int IS NULL OR version = $3)
+RETURNING id, username, email, bio, avatar_url, created_at, version;
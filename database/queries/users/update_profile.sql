@@ -0,0 +1,9 @@
+-- Omitted (NULL) fields are left unchanged, as is the version check when $4 is NULL
+UPDATE users
+SET username = COALESCE($2, username),
+    bio = COALESCE($3, bio),
+    version = version + 1
+WHERE id = $1
+  AND deleted_at IS NULL
+  AND ($4::int IS NULL OR version = $4)
+RETURNING id, username, email, bio, avatar_url, created_at, version;
@@ -0,0 +1,106 @@
+// serde_json vs sonic-rs on the payloads the API spends most of its JSON time on:
+// serializing a full page of posts (GET /posts?limit=100) and parsing post bodies.
+// Run with `cargo bench --features sonic-json`.
+
+use chrono::{DateTime, Duration, Utc};
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use serde::{Deserialize, Serialize};
+use std::hint::black_box;
+use uuid::Uuid;
+
+// The API's timestamp serializer
+#[path = "../src/timestamp.rs"]
+mod timestamp;
+
+// Same shape as models::Post
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Post {
+    id: Uuid,
+    author_id: Uuid,
+    content: String,
+    status: String,
+    #[serde(serialize_with = "timestamp::serialize_option")]
+    publish_at: Option<DateTime<Utc>>,
+    like_count: i64,
+    comment_count: i64,
+    #[serde(serialize_with = "timestamp::serialize")]
+    created_at: DateTime<Utc>,
+    #[serde(serialize_with = "timestamp::serialize_option")]
+    updated_at: Option<DateTime<Utc>>,
+    version: i32,
+    repost_count: i64,
+    repost_of: Option<Box<Post>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    liked_by_me: Option<bool>,
+}
+
+// Same shape as models::PostCreate
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[allow(dead_code)]
+struct PostCreate {
+    content: String,
+    status: Option<String>,
+    publish_at: Option<DateTime<Utc>>,
+}
+
+fn post(index: i64, repost_of: Option<Box<Post>>) -> Post {
+    let created_at = Utc::now() - Duration::minutes(index);
+    Post {
+        id: Uuid::new_v4(),
+        author_id: Uuid::new_v4(),
+        content: format!(
+            "Post number {} about #benchmarks and #rust, mentioning @someone with a few more words to reach a typical length",
+            index
+        ),
+        status: "published".to_string(),
+        publish_at: None,
+        like_count: index * 7 % 113,
+        comment_count: index * 3 % 29,
+        created_at,
+        updated_at: (index % 4 == 0).then_some(created_at + Duration::seconds(30)),
+        version: 1 + (index % 3) as i32,
+        repost_count: index % 5,
+        repost_of,
+        liked_by_me: Some(index % 2 == 0),
+    }
+}
+
+// A page of 100 posts, one in ten being a repost embedding its original
+fn page() -> Vec<Post> {
+    (0..100)
+        .map(|index| {
+            let repost_of = (index % 10 == 0).then(|| Box::new(post(index + 1000, None)));
+            post(index, repost_of)
+        })
+        .collect()
+}
+
+fn serialize_page(c: &mut Criterion) {
+    let page = page();
+    let size = serde_json::to_vec(&page).unwrap().len() as u64;
+
+    let mut group = c.benchmark_group("serialize 100 posts");
+    group.throughput(Throughput::Bytes(size));
+    group.bench_function("serde_json", |b| b.iter(|| serde_json::to_vec(black_box(&page)).unwrap()));
+    group.bench_function("sonic_rs", |b| b.iter(|| sonic_rs::to_vec(black_box(&page)).unwrap()));
+    group.finish();
+}
+
+fn parse_post_body(c: &mut Criterion) {
+    let body = br#"{"content":"Hello #rust, this is @someone posting a scheduled draft","status":"draft","publishAt":"2030-01-01T12:00:00Z"}"#;
+
+    let mut group = c.benchmark_group("parse post body");
+    group.throughput(Throughput::Bytes(body.len() as u64));
+    group.bench_function("serde_json", |b| {
+        b.iter(|| serde_json::from_slice::<PostCreate>(black_box(body)).unwrap())
+    });
+    group.bench_function("sonic_rs", |b| {
+        b.iter(|| sonic_rs::from_slice::<PostCreate>(black_box(body)).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, serialize_page, parse_post_body);
+criterion_main!(benches);
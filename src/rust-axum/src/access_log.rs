@@ -0,0 +1,18 @@
+use axum::{body::HttpBody, extract::Request, middleware::Next, response::Response};
+use std::time::Instant;
+
+// Access log, ACCESS_LOG: a `request completed` line per request with its status, latency
+// (until the response headers) and body size, logged in the request span, which carries the
+// method and route pattern. Bodies streamed without a known length (streamed listings) have
+// no `bytes`; the size is the one before compression.
+pub async fn access_log_middleware(request: Request, next: Next) -> Response {
+    let started = Instant::now();
+    let response = next.run(request).await;
+    tracing::info!(
+        status = response.status().as_u16(),
+        latency_ms = (started.elapsed().as_secs_f64() * 100_000.0).round() / 100.0,
+        bytes = response.body().size_hint().exact(),
+        "request completed"
+    );
+    response
+}
@@ -1,46 +1,335 @@
 use axum::{
-    extract::{Request, State},
-    http::HeaderMap,
+    extract::{FromRef, FromRequestParts, Request, State},
+    http::{header, request::Parts, HeaderMap, HeaderValue, Method},
     middleware::Next,
     response::Response,
 };
+use chrono::{DateTime, Utc};
+use lru::LruCache;
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use pasetors::{
+    keys::SymmetricKey,
+    token::UntrustedToken,
+    version4::{LocalToken, V4},
+    Local,
+};
+use sha2::{Digest, Sha256};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use std::env;
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
 
-use crate::error::AppError;
+use crate::{
+    debug_stats::BlockingJob,
+    error::{AppError, ErrorCode},
+    sql::SQL_USER_STATUS,
+    AppState,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: String, // user id
     pub exp: usize,  // expiration time
+    #[serde(default)]
     pub is_admin: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aud: Option<Audience>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<TokenProfile>, // lets /auth/me answer without a DB round-trip
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub impersonated_by: Option<String>, // real admin id when acting as another user
+}
+
+// `aud` may be a single string or a list in tokens minted by an external gateway
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Audience {
+    One(String),
+    Many(Vec<String>),
+}
+
+// Token format used for issuing and verifying access tokens
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenBackend {
+    Jwt,
+    Paseto, // v4.local, symmetric key derived from the JWT secrets
+}
+
+impl TokenBackend {
+    fn from_env() -> Self {
+        match env::var("TOKEN_BACKEND").as_deref() {
+            Ok("paseto") => Self::Paseto,
+            Ok("jwt") | Err(_) => Self::Jwt,
+            Ok(other) => {
+                tracing::warn!("Unknown TOKEN_BACKEND {:?}, using jwt", other);
+                Self::Jwt
+            }
+        }
+    }
+}
+
+// Cost 8 matches the Python implementation and the seeded admin hash
+const DEFAULT_BCRYPT_COST: u32 = 8;
+// Below 4 is rejected by bcrypt, above 16 takes seconds per hash and stalls the blocking pool
+const MIN_BCRYPT_COST: u32 = 4;
+const MAX_BCRYPT_COST: u32 = 16;
+
+// Number of secrets kept for verification after a rotation (including the signing one)
+const MAX_JWT_SECRETS: usize = 5;
+
+// User profile snapshot taken at login time. The bio, up to 500 characters, only goes in
+// as a hash to keep tokens small: /auth/me loads it from the database when there is one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenProfile {
+    pub username: String,
+    pub email: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bio_hash: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avatar_url: Option<String>,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub version: i32,
 }
 
 #[derive(Debug, Clone)]
 pub struct AuthConfig {
-    pub jwt_secret: String,
+    // First secret signs new tokens, all of them are accepted for verification.
+    // Shared behind an Arc so a rotation is visible to every clone (state + middleware),
+    // replaced as a whole on rotation so requests only clone the inner Arc.
+    jwt_secrets: Arc<RwLock<Arc<[String]>>>,
     pub jwt_expire_minutes: i64,
+    pub me_from_db: bool,
+    pub bcrypt_cost: u32,
+    pub jwt_issuer: Option<String>,
+    pub jwt_audience: Option<String>,
+    pub token_backend: TokenBackend,
+    // Decoded claims keyed by raw token, skips signature verification on repeated tokens
+    token_cache: Option<Arc<Mutex<LruCache<String, Claims>>>>,
+    // Deleted users are rejected on protected routes, and when admin_from_db is set
+    // is_admin is re-checked against the database instead of trusting the token
+    pub user_status: Arc<UserStatusCache>,
+    pub admin_from_db: bool,
+    // Deliver the token as an HttpOnly cookie on login and accept it on requests
+    pub cookie_auth: bool,
+    pub cookie_secure: bool,
+}
+
+// Short-lived cache of users.is_admin and deleted_at, so deleting a user or revoking admin
+// rights applies within `ttl` to tokens already issued
+#[derive(Debug)]
+pub struct UserStatusCache {
+    ttl: Duration,
+    entries: Mutex<LruCache<Uuid, (UserStatus, Instant)>>,
+}
+
+#[derive(Debug, Clone, Copy, sqlx::FromRow)]
+pub struct UserStatus {
+    pub is_admin: bool,
+    pub deleted: bool,
+}
+
+impl UserStatusCache {
+    fn from_env() -> Self {
+        let ttl_ms = env::var("USER_STATUS_CACHE_TTL_MS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(1000);
+        let size = env::var("USER_STATUS_CACHE_SIZE")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .and_then(NonZeroUsize::new)
+            .unwrap_or(NonZeroUsize::new(10_000).expect("non-zero"));
+
+        Self {
+            ttl: Duration::from_millis(ttl_ms),
+            entries: Mutex::new(LruCache::new(size)),
+        }
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    pub async fn get(&self, db: &sqlx::PgPool, user_id: Uuid) -> Result<UserStatus, AppError> {
+        {
+            let mut entries = self.entries.lock().expect("user status cache lock poisoned");
+            if let Some(&(status, fetched_at)) = entries.get(&user_id) {
+                if fetched_at.elapsed() < self.ttl {
+                    return Ok(status);
+                }
+            }
+        }
+
+        let status: Option<UserStatus> = sqlx::query_as(SQL_USER_STATUS)
+            .bind(user_id)
+            .fetch_optional(db)
+            .await?;
+        let status = status.unwrap_or(UserStatus {
+            is_admin: false,
+            deleted: true,
+        });
+
+        self.entries
+            .lock()
+            .expect("user status cache lock poisoned")
+            .put(user_id, (status, Instant::now()));
+
+        Ok(status)
+    }
+
+    // The user was deleted here, other instances see it once their entry expires
+    pub fn invalidate(&self, user_id: Uuid) {
+        self.entries
+            .lock()
+            .expect("user status cache lock poisoned")
+            .pop(&user_id);
+    }
+}
+
+// First 8 bytes of the bio's SHA-256, hex encoded
+pub fn bio_hash(bio: &str) -> String {
+    Sha256::digest(bio.as_bytes())[..8]
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+impl AuthConfig {
+    pub fn signing_secret(&self) -> String {
+        self.jwt_secrets.read().expect("jwt secrets lock poisoned")[0].clone()
+    }
+
+    pub fn verification_secrets(&self) -> Arc<[String]> {
+        self.jwt_secrets.read().expect("jwt secrets lock poisoned").clone()
+    }
+
+    // Make `secret` the new signing key while still accepting tokens signed with previous ones
+    pub fn rotate_secret(&self, secret: String) {
+        let mut secrets = self.jwt_secrets.write().expect("jwt secrets lock poisoned");
+        let rotated: Arc<[String]> = std::iter::once(secret.clone())
+            .chain(secrets.iter().filter(|s| **s != secret).cloned())
+            .take(MAX_JWT_SECRETS)
+            .collect();
+        *secrets = rotated;
+        drop(secrets);
+
+        // Cached tokens may have been signed with a secret that was just dropped
+        if let Some(cache) = &self.token_cache {
+            cache.lock().expect("token cache lock poisoned").clear();
+        }
+    }
+
+    pub fn token_cache_size(&self) -> Option<usize> {
+        self.token_cache
+            .as_ref()
+            .map(|cache| cache.lock().expect("token cache lock poisoned").cap().get())
+    }
+
+    fn cached_claims(&self, token: &str) -> Option<Claims> {
+        let mut cache = self.token_cache.as_ref()?.lock().expect("token cache lock poisoned");
+        let claims = cache.get(token)?;
+        if claims.exp < chrono::Utc::now().timestamp() as usize {
+            // Expired since it was cached, evict so it goes through full validation again
+            cache.pop(token);
+            return None;
+        }
+        Some(claims.clone())
+    }
+
+    fn cache_claims(&self, token: &str, claims: &Claims) {
+        if let Some(cache) = &self.token_cache {
+            cache
+                .lock()
+                .expect("token cache lock poisoned")
+                .put(token.to_string(), claims.clone());
+        }
+    }
+}
+
+fn parse_bcrypt_cost() -> u32 {
+    let Ok(raw) = env::var("BCRYPT_COST") else {
+        return DEFAULT_BCRYPT_COST;
+    };
+
+    match raw.parse::<u32>() {
+        Ok(cost) if (MIN_BCRYPT_COST..=MAX_BCRYPT_COST).contains(&cost) => cost,
+        _ => {
+            tracing::warn!(
+                "Invalid BCRYPT_COST {:?} (expected {}-{}), using {}",
+                raw,
+                MIN_BCRYPT_COST,
+                MAX_BCRYPT_COST,
+                DEFAULT_BCRYPT_COST
+            );
+            DEFAULT_BCRYPT_COST
+        }
+    }
+}
+
+fn parse_jwt_secrets(raw: &str) -> Vec<String> {
+    let secrets: Vec<String> = raw
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect();
+
+    if secrets.is_empty() {
+        vec!["dev-secret".to_string()]
+    } else {
+        secrets
+    }
 }
 
 impl Default for AuthConfig {
     fn default() -> Self {
         Self {
-            jwt_secret: env::var("JWT_SECRET").unwrap_or_else(|_| "dev-secret".to_string()),
+            jwt_secrets: Arc::new(RwLock::new(
+                parse_jwt_secrets(&env::var("JWT_SECRET").unwrap_or_else(|_| "dev-secret".to_string())).into(),
+            )),
             jwt_expire_minutes: env::var("JWT_EXPIRE_MINUTES")
                 .unwrap_or_else(|_| "60".to_string())
                 .parse()
                 .unwrap_or(60),
+            me_from_db: env::var("AUTH_ME_FROM_DB")
+                .map(|v| v == "true" || v == "1")
+                .unwrap_or(false),
+            bcrypt_cost: parse_bcrypt_cost(),
+            jwt_issuer: env::var("JWT_ISSUER").ok().filter(|v| !v.is_empty()),
+            jwt_audience: env::var("JWT_AUDIENCE").ok().filter(|v| !v.is_empty()),
+            token_backend: TokenBackend::from_env(),
+            token_cache: env::var("TOKEN_CACHE_SIZE")
+                .ok()
+                .and_then(|v| v.parse::<usize>().ok())
+                .and_then(NonZeroUsize::new)
+                .map(|size| Arc::new(Mutex::new(LruCache::new(size)))),
+            user_status: Arc::new(UserStatusCache::from_env()),
+            admin_from_db: env::var("ADMIN_CHECK_FROM_DB")
+                .map(|v| v == "true" || v == "1")
+                .unwrap_or(false),
+            cookie_auth: env::var("AUTH_COOKIE_MODE")
+                .map(|v| v == "true" || v == "1")
+                .unwrap_or(false),
+            cookie_secure: env::var("AUTH_COOKIE_SECURE")
+                .map(|v| v == "true" || v == "1")
+                .unwrap_or(false),
         }
     }
 }
 
-pub async fn hash_password(password: &str) -> Result<String, AppError> {
+pub async fn hash_password(password: &str, cost: u32) -> Result<String, AppError> {
     let password = password.to_string();
-    // Using bcrypt with cost 8 for consistency with Python implementation
     // Offload CPU-intensive bcrypt to a blocking thread to avoid blocking the async runtime
-    tokio::task::spawn_blocking(move || bcrypt::hash(&password, 8))
+    let job = BlockingJob::start();
+    tokio::task::spawn_blocking(move || {
+        let _job = job;
+        bcrypt::hash(&password, cost)
+    })
         .await
         .map_err(|_| AppError::InternalServerError("Task join error".to_string()))?
         .map_err(|_| AppError::InternalServerError("Failed to hash password".to_string()))
@@ -50,75 +339,329 @@ pub async fn verify_password(password: &str, hash: &str) -> Result<bool, AppErro
     let password = password.to_string();
     let hash = hash.to_string();
     // Offload CPU-intensive bcrypt to a blocking thread to avoid blocking the async runtime
-    tokio::task::spawn_blocking(move || bcrypt::verify(&password, &hash))
+    let job = BlockingJob::start();
+    tokio::task::spawn_blocking(move || {
+        let _job = job;
+        bcrypt::verify(&password, &hash)
+    })
         .await
         .map_err(|_| AppError::InternalServerError("Task join error".to_string()))?
         .map_err(|_| AppError::InternalServerError("Failed to verify password".to_string()))
 }
 
-pub fn create_token(user_id: &Uuid, is_admin: bool, config: &AuthConfig) -> Result<String, AppError> {
-    let expiration = chrono::Utc::now()
+fn token_expiration(config: &AuthConfig) -> usize {
+    chrono::Utc::now()
         .checked_add_signed(chrono::Duration::minutes(config.jwt_expire_minutes))
         .expect("valid timestamp")
-        .timestamp() as usize;
+        .timestamp() as usize
+}
 
+pub fn create_token(
+    user_id: &Uuid,
+    is_admin: bool,
+    profile: TokenProfile,
+    config: &AuthConfig,
+) -> Result<String, AppError> {
     let claims = Claims {
         sub: user_id.to_string(),
-        exp: expiration,
+        exp: token_expiration(config),
         is_admin,
+        iss: config.jwt_issuer.clone(),
+        aud: config.jwt_audience.clone().map(Audience::One),
+        profile: Some(profile),
+        impersonated_by: None,
     };
 
-    encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(config.jwt_secret.as_ref()),
-    )
-    .map_err(|_| AppError::InternalServerError("Failed to create token".to_string()))
+    sign_claims(&claims, config)
 }
 
-pub fn decode_token(token: &str, secret: &str) -> Result<Claims, AppError> {
+// Token acting as `user_id` while keeping track of the admin who requested it
+pub fn create_impersonation_token(
+    user_id: &Uuid,
+    is_admin: bool,
+    profile: TokenProfile,
+    admin_id: &Uuid,
+    config: &AuthConfig,
+) -> Result<String, AppError> {
+    let claims = Claims {
+        sub: user_id.to_string(),
+        exp: token_expiration(config),
+        is_admin,
+        iss: config.jwt_issuer.clone(),
+        aud: config.jwt_audience.clone().map(Audience::One),
+        profile: Some(profile),
+        impersonated_by: Some(admin_id.to_string()),
+    };
+
+    sign_claims(&claims, config)
+}
+
+fn sign_claims(claims: &Claims, config: &AuthConfig) -> Result<String, AppError> {
+    match config.token_backend {
+        TokenBackend::Jwt => encode(
+            &Header::default(),
+            claims,
+            &EncodingKey::from_secret(config.signing_secret().as_ref()),
+        )
+        .map_err(|_| AppError::InternalServerError("Failed to create token".to_string())),
+        TokenBackend::Paseto => {
+            let payload = serde_json::to_vec(claims)
+                .map_err(|_| AppError::InternalServerError("Failed to create token".to_string()))?;
+            LocalToken::encrypt(&paseto_key(&config.signing_secret())?, &payload, None, None)
+                .map_err(|_| AppError::InternalServerError("Failed to create token".to_string()))
+        }
+    }
+}
+
+// PASETO v4.local needs a 32-byte key, derive it from the configured secret
+fn paseto_key(secret: &str) -> Result<SymmetricKey<V4>, AppError> {
+    SymmetricKey::<V4>::from(Sha256::digest(secret.as_bytes()).as_slice())
+        .map_err(|_| AppError::InternalServerError("Invalid PASETO key".to_string()))
+}
+
+pub fn decode_token(token: &str, config: &AuthConfig) -> Result<Claims, AppError> {
+    if let Some(claims) = config.cached_claims(token) {
+        return Ok(claims);
+    }
+
+    let claims = match config.token_backend {
+        TokenBackend::Jwt => decode_jwt(token, config),
+        TokenBackend::Paseto => decode_paseto(token, config),
+    }?;
+
+    config.cache_claims(token, &claims);
+    Ok(claims)
+}
+
+fn decode_jwt(token: &str, config: &AuthConfig) -> Result<Claims, AppError> {
     let mut validation = Validation::default();
     validation.validate_exp = true;  // Still validate expiration
     validation.validate_nbf = false; // Skip not-before validation for speed
-    validation.validate_aud = false; // Skip audience validation for speed
-    
-    decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(secret.as_ref()),
-        &validation,
-    )
-    .map(|data| data.claims)
-    .map_err(|e| {
+    // Issuer and audience are only checked when configured
+    if let Some(issuer) = &config.jwt_issuer {
+        validation.set_issuer(&[issuer]);
+        validation.required_spec_claims.insert("iss".to_string());
+    }
+    match &config.jwt_audience {
+        Some(audience) => {
+            validation.set_audience(&[audience]);
+            validation.required_spec_claims.insert("aud".to_string());
+        }
+        None => validation.validate_aud = false, // Skip audience validation for speed
+    }
+
+    // Try every known secret so tokens signed before a rotation stay valid
+    let mut last_error = None;
+    for secret in config.verification_secrets().iter() {
+        match decode::<Claims>(token, &DecodingKey::from_secret(secret.as_ref()), &validation) {
+            Ok(data) => return Ok(data.claims),
+            Err(e) if *e.kind() == jsonwebtoken::errors::ErrorKind::InvalidSignature => {
+                last_error = Some(e);
+            }
+            Err(e) => {
+                last_error = Some(e);
+                break;
+            }
+        }
+    }
+
+    tracing::debug!("Token decode error: {:?}", last_error);
+    Err(AppError::from(ErrorCode::InvalidToken))
+}
+
+fn decode_paseto(token: &str, config: &AuthConfig) -> Result<Claims, AppError> {
+    let untrusted = UntrustedToken::<Local, V4>::try_from(token).map_err(|e| {
         tracing::debug!("Token decode error: {:?}", e);
-        AppError::Unauthorized("Invalid token".to_string())
-    })
+        AppError::from(ErrorCode::InvalidToken)
+    })?;
+
+    // Try every known secret so tokens encrypted before a rotation stay valid
+    let trusted = config
+        .verification_secrets()
+        .iter()
+        .find_map(|secret| {
+            let key = paseto_key(secret).ok()?;
+            LocalToken::decrypt(&key, &untrusted, None, None).ok()
+        })
+        .ok_or_else(|| AppError::from(ErrorCode::InvalidToken))?;
+
+    let claims: Claims = serde_json::from_str(trusted.payload()).map_err(|e| {
+        tracing::debug!("Token payload error: {:?}", e);
+        AppError::from(ErrorCode::InvalidToken)
+    })?;
+
+    // PASETO has no built-in registered claim checks, mirror what the JWT validation does
+    if claims.exp < chrono::Utc::now().timestamp() as usize {
+        return Err(AppError::from(ErrorCode::InvalidToken));
+    }
+    if let Some(issuer) = &config.jwt_issuer {
+        if claims.iss.as_ref() != Some(issuer) {
+            return Err(AppError::from(ErrorCode::InvalidToken));
+        }
+    }
+    if let Some(audience) = &config.jwt_audience {
+        let matches = match &claims.aud {
+            Some(Audience::One(aud)) => aud == audience,
+            Some(Audience::Many(auds)) => auds.contains(audience),
+            None => false,
+        };
+        if !matches {
+            return Err(AppError::from(ErrorCode::InvalidToken));
+        }
+    }
+
+    Ok(claims)
 }
 
 pub fn extract_token_from_headers(headers: &HeaderMap) -> Result<String, AppError> {
     let auth_header = headers
         .get("authorization")
-        .ok_or_else(|| AppError::Unauthorized("Missing authorization header".to_string()))?
+        .ok_or_else(|| AppError::from(ErrorCode::MissingToken))?
         .to_str()
-        .map_err(|_| AppError::Unauthorized("Invalid authorization header".to_string()))?;
+        .map_err(|_| AppError::Coded(ErrorCode::InvalidToken, "Invalid authorization header".to_string()))?;
 
     if !auth_header.starts_with("Bearer ") {
-        return Err(AppError::Unauthorized("Invalid authorization format".to_string()));
+        return Err(AppError::Coded(ErrorCode::InvalidToken, "Invalid authorization format".to_string()));
     }
 
     Ok(auth_header[7..].to_string())
 }
 
+pub const ACCESS_TOKEN_COOKIE: &str = "access_token";
+pub const CSRF_COOKIE: &str = "csrf_token";
+pub const CSRF_HEADER: &str = "x-csrf-token";
+
+fn cookie_value<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
+}
+
+// Bearer header first, then (in cookie mode) the access token cookie.
+// Cookie-authenticated mutating requests must echo the CSRF cookie in the X-CSRF-Token header.
+pub fn extract_token(
+    method: &Method,
+    headers: &HeaderMap,
+    config: &AuthConfig,
+) -> Result<String, AppError> {
+    if !config.cookie_auth || headers.contains_key(header::AUTHORIZATION) {
+        return extract_token_from_headers(headers);
+    }
+
+    let token = cookie_value(headers, ACCESS_TOKEN_COOKIE)
+        .ok_or_else(|| AppError::from(ErrorCode::MissingToken))?;
+
+    if !method.is_safe() {
+        let csrf_cookie = cookie_value(headers, CSRF_COOKIE);
+        let csrf_header = headers.get(CSRF_HEADER).and_then(|v| v.to_str().ok());
+        match (csrf_cookie, csrf_header) {
+            (Some(cookie), Some(header)) if !cookie.is_empty() && cookie == header => {}
+            _ => return Err(AppError::from(ErrorCode::InvalidCsrfToken)),
+        }
+    }
+
+    Ok(token.to_string())
+}
+
+fn has_token(headers: &HeaderMap, config: &AuthConfig) -> bool {
+    headers.contains_key(header::AUTHORIZATION)
+        || (config.cookie_auth && cookie_value(headers, ACCESS_TOKEN_COOKIE).is_some())
+}
+
+// Set-Cookie headers for the access token (HttpOnly) and its CSRF companion (readable by scripts)
+pub fn auth_cookies(token: &str, config: &AuthConfig) -> Result<HeaderMap, AppError> {
+    let max_age = config.jwt_expire_minutes * 60;
+    let secure = if config.cookie_secure { "; Secure" } else { "" };
+    let csrf_token = Uuid::new_v4().simple().to_string();
+
+    let cookies = [
+        format!(
+            "{}={}; Path=/; Max-Age={}; HttpOnly; SameSite=Lax{}",
+            ACCESS_TOKEN_COOKIE, token, max_age, secure
+        ),
+        format!(
+            "{}={}; Path=/; Max-Age={}; SameSite=Lax{}",
+            CSRF_COOKIE, csrf_token, max_age, secure
+        ),
+    ];
+
+    let mut headers = HeaderMap::new();
+    for cookie in cookies {
+        let value = HeaderValue::from_str(&cookie)
+            .map_err(|_| AppError::InternalServerError("Failed to build cookie".to_string()))?;
+        headers.append(header::SET_COOKIE, value);
+    }
+
+    Ok(headers)
+}
+
+// Tokens outlive a user's deletion, and the admin status baked into them can't be revoked:
+// both are checked against the database for every decoded token, protected route or not
+async fn check_user_status(
+    auth_config: &AuthConfig,
+    db: &sqlx::PgPool,
+    claims: &mut Claims,
+) -> Result<(), AppError> {
+    let user_uuid = Uuid::parse_str(&claims.sub)
+        .map_err(|_| AppError::from(ErrorCode::InvalidToken))?;
+    let status = auth_config.user_status.get(db, user_uuid).await?;
+    if status.deleted {
+        return Err(AppError::Unauthorized("User not found".to_string()));
+    }
+    if auth_config.admin_from_db {
+        claims.is_admin = status.is_admin;
+    }
+    Ok(())
+}
+
 // Middleware for extracting user ID from JWT token
 pub async fn auth_middleware(
-    State(auth_config): State<AuthConfig>,
+    State(app_state): State<AppState>,
     mut request: Request,
     next: Next,
 ) -> Result<Response, AppError> {
-    let token = extract_token_from_headers(request.headers())?;
-    let claims = decode_token(&token, &auth_config.jwt_secret)?;
+    let token = extract_token(request.method(), request.headers(), &app_state.auth_config)?;
+    let mut claims = decode_token(&token, &app_state.auth_config)?;
+    check_user_status(&app_state.auth_config, &app_state.db, &mut claims).await?;
     
     // Add claims to request extensions for use in handlers
     request.extensions_mut().insert(claims);
     
     Ok(next.run(request).await)
 }
+
+// Extractor for public routes: decodes the bearer token when one is sent, so handlers
+// can add viewer-specific data without requiring authentication.
+#[derive(Debug, Clone)]
+pub struct OptionalClaims(pub Option<Claims>);
+
+impl<S> FromRequestParts<S> for OptionalClaims
+where
+    AuthConfig: FromRef<S>,
+    sqlx::PgPool: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        if let Some(claims) = parts.extensions.get::<Claims>() {
+            return Ok(Self(Some(claims.clone())));
+        }
+
+        let auth_config = AuthConfig::from_ref(state);
+        if !has_token(&parts.headers, &auth_config) {
+            return Ok(Self(None));
+        }
+
+        let token = extract_token(&parts.method, &parts.headers, &auth_config)?;
+        let mut claims = decode_token(&token, &auth_config)?;
+        check_user_status(&auth_config, &sqlx::PgPool::from_ref(state), &mut claims).await?;
+
+        Ok(Self(Some(claims)))
+    }
+}
@@ -0,0 +1,48 @@
+use axum::{
+    body::{to_bytes, Body, HttpBody},
+    extract::{Request, State},
+    http::{header, Method},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::error::AppError;
+
+// Largest request body accepted by the routes this middleware is applied to, in bytes
+#[derive(Debug, Clone, Copy)]
+pub struct BodyLimit(pub usize);
+
+fn too_large(limit: BodyLimit) -> AppError {
+    AppError::PayloadTooLarge(format!("Request body exceeds the {} byte limit", limit.0))
+}
+
+// Rejects oversized bodies with the usual JSON 413 before any extractor sees them
+pub async fn body_limit_middleware(
+    State(limit): State<BodyLimit>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let content_length = request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok());
+
+    match content_length {
+        Some(length) if length > limit.0 as u64 => Err(too_large(limit)),
+        // hyper enforces the declared length, the body can't grow past it
+        Some(_) => Ok(next.run(request).await),
+        // Nothing to buffer: bodyless GETs, and HTTP/2 requests ended with their headers
+        None if matches!(*request.method(), Method::GET | Method::HEAD)
+            || request.body().size_hint().exact() == Some(0) =>
+        {
+            Ok(next.run(request).await)
+        }
+        // Chunked bodies have no declared length, buffer them up to the limit
+        None => {
+            let (parts, body) = request.into_parts();
+            let body = to_bytes(body, limit.0).await.map_err(|_| too_large(limit))?;
+            Ok(next.run(Request::from_parts(parts, Body::from(body))).await)
+        }
+    }
+}
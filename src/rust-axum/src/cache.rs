@@ -0,0 +1,357 @@
+use axum::{
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use bytes::Bytes;
+use redis::{aio::ConnectionManager, AsyncCommands};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+#[cfg(feature = "post-cache")]
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+use std::{env, future::Future, time::Duration};
+use uuid::Uuid;
+
+use crate::{
+    cache_notify::{CacheNotifier, Invalidation},
+    casing::to_json_bytes,
+    error::AppError,
+    models::Post,
+};
+
+// Bumped by every change to a post, published post listings are keyed by its value
+const POSTS_GENERATION_KEY: &str = "posts:gen";
+// Bumped when a user is deleted, every single post is keyed by its value (and its own generation)
+const SINGLE_POSTS_GENERATION_KEY: &str = "post:gen";
+
+// Optional Redis cache of the anonymous GET /posts/{post_id}, GET /posts and
+// GET /posts/{post_id}/comments responses, enabled by CACHE_URL.
+// Entries hold the serialized response and are invalidated by the handlers changing them:
+// every key includes generation counters that are bumped, the post's own and the
+// single posts' one for single posts, the listings' one for listings. A fill racing an
+// invalidation writes under the previous generation, which is never read again.
+// Redis errors are logged and treated as a miss, the cache never fails a request.
+// Built with the `post-cache` feature, single posts are also kept in process, in front of Redis;
+// CACHE_NOTIFY extends their invalidation to the other instances (cache_notify.rs).
+#[derive(Clone)]
+pub struct Cache {
+    redis: Option<ConnectionManager>,
+    #[cfg(feature = "post-cache")]
+    posts: Option<moka::future::Cache<Uuid, Bytes>>,
+    // Bumped by every local invalidation, see `get_or_load_post`
+    #[cfg(feature = "post-cache")]
+    local_generation: Arc<AtomicU64>,
+    ttl: Duration,
+    // CACHE_NOTIFY: local invalidations are published to the other instances
+    notifier: Option<CacheNotifier>,
+}
+
+pub async fn cache_from_env() -> Result<Cache, redis::RedisError> {
+    let ttl = Duration::from_secs(
+        env::var("CACHE_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|&secs| secs > 0)
+            .unwrap_or(60),
+    );
+    let redis = match env::var("CACHE_URL") {
+        Ok(url) if !url.is_empty() => {
+            let manager = ConnectionManager::new(redis::Client::open(url)?).await?;
+            tracing::info!("Response cache enabled (Redis, TTL {:?})", ttl);
+            Some(manager)
+        }
+        _ => None,
+    };
+    Ok(Cache {
+        redis,
+        #[cfg(feature = "post-cache")]
+        posts: local_post_cache(ttl),
+        #[cfg(feature = "post-cache")]
+        local_generation: Arc::new(AtomicU64::new(0)),
+        ttl,
+        notifier: None,
+    })
+}
+
+// Bounded by POST_CACHE_SIZE entries, 0 disables it. Each instance has its own:
+// only changes made through this instance invalidate it, others are seen after the TTL.
+#[cfg(feature = "post-cache")]
+fn local_post_cache(ttl: Duration) -> Option<moka::future::Cache<Uuid, Bytes>> {
+    let size = env::var("POST_CACHE_SIZE")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(10_000);
+    if size == 0 {
+        return None;
+    }
+    tracing::info!("In-process post cache enabled ({} entries, TTL {:?})", size, ttl);
+    Some(
+        moka::future::Cache::builder()
+            .max_capacity(size)
+            .time_to_live(ttl)
+            .build(),
+    )
+}
+
+fn post_generation_key(post_id: Uuid) -> String {
+    format!("post:{}:gen", post_id)
+}
+
+fn comments_generation_key(post_id: Uuid) -> String {
+    format!("comments:{}:gen", post_id)
+}
+
+fn cache_error(e: redis::RedisError) {
+    tracing::warn!("Cache error: {}", e);
+}
+
+impl Cache {
+    pub fn with_notifier(self, notifier: Option<CacheNotifier>) -> Self {
+        Self { notifier, ..self }
+    }
+
+    // Anonymous GET /posts/{post_id}: the in-process cache, then Redis, then `load`
+    pub async fn get_or_load_post<F>(&self, post_id: Uuid, load: F) -> Result<CachedJson, AppError>
+    where
+        F: Future<Output = Result<Post, AppError>>,
+    {
+        #[cfg(feature = "post-cache")]
+        if let Some(posts) = &self.posts {
+            if let Some(body) = posts.get(&post_id).await {
+                return Ok(CachedJson(body));
+            }
+            // Taken back out when an invalidation ran meanwhile, it may predate it.
+            // Invalidations bump the generation before dropping the entry: either this
+            // sees the bump, or the invalidation drops what was inserted.
+            let generation = self.local_generation.load(Ordering::SeqCst);
+            let response = self.get_or_load(self.post_key(post_id).await, load).await?;
+            posts.insert(post_id, response.0.clone()).await;
+            if self.local_generation.load(Ordering::SeqCst) != generation {
+                posts.invalidate(&post_id).await;
+            }
+            return Ok(response);
+        }
+
+        self.get_or_load(self.post_key(post_id).await, load).await
+    }
+
+    async fn post_key(&self, post_id: Uuid) -> Option<String> {
+        let mut redis = self.redis.clone()?;
+        let keys = [post_generation_key(post_id), SINGLE_POSTS_GENERATION_KEY.to_string()];
+        let (post_generation, generation) = redis
+            .mget::<_, (Option<i64>, Option<i64>)>(&keys)
+            .await
+            .map_err(cache_error)
+            .ok()?;
+        Some(format!(
+            "post:{}:{}:{}",
+            post_id,
+            post_generation.unwrap_or_default(),
+            generation.unwrap_or_default()
+        ))
+    }
+
+    // `query` is the raw query string, identical listings requested differently are cached twice
+    pub async fn posts_list_key(&self, query: &str) -> Option<String> {
+        let generation = self.generation(POSTS_GENERATION_KEY).await?;
+        Some(format!("posts:list:{}:{}", generation, query))
+    }
+
+    pub async fn comments_list_key(&self, post_id: Uuid, query: &str) -> Option<String> {
+        let generation = self.generation(&comments_generation_key(post_id)).await?;
+        Some(format!("comments:{}:{}:{}", post_id, generation, query))
+    }
+
+    async fn generation(&self, key: &str) -> Option<i64> {
+        let mut redis = self.redis.clone()?;
+        redis
+            .get::<_, Option<i64>>(key)
+            .await
+            .map(Option::unwrap_or_default)
+            .map_err(cache_error)
+            .ok()
+    }
+
+    // Serves the cached response under `key`, otherwise runs `load` and caches its result.
+    // Without a key (cache disabled, per-user response) `load` is simply run.
+    pub async fn get_or_load<T, F>(&self, key: Option<String>, load: F) -> Result<CachedJson, AppError>
+    where
+        T: Serialize,
+        F: Future<Output = Result<T, AppError>>,
+    {
+        self.get_or_load_body(key, async { CachedJson::serialize(&load.await?).map(|json| json.0) })
+            .await
+    }
+
+    // Same, for a `load` returning a body already serialized (by Postgres)
+    pub async fn get_or_load_body<F>(&self, key: Option<String>, load: F) -> Result<CachedJson, AppError>
+    where
+        F: Future<Output = Result<Bytes, AppError>>,
+    {
+        let (Some(key), Some(mut redis)) = (key, self.redis.clone()) else {
+            return Ok(CachedJson(load.await?));
+        };
+
+        match redis.get::<_, Option<Vec<u8>>>(&key).await {
+            Ok(Some(body)) => return Ok(CachedJson(Bytes::from(body))),
+            Ok(None) => {}
+            Err(e) => cache_error(e),
+        }
+
+        let response = CachedJson(load.await?);
+        if let Err(e) = redis
+            .set_ex::<_, _, ()>(&key, response.0.as_ref(), self.ttl.as_secs())
+            .await
+        {
+            cache_error(e);
+        }
+        Ok(response)
+    }
+
+    // A post changed (content, status, counters): its entry and every post listing
+    pub async fn invalidate_post(&self, post_id: Uuid) {
+        self.invalidate_local_post(post_id).await;
+        self.notify(Invalidation::Post(post_id)).await;
+        self.invalidate(redis::pipe().incr(post_generation_key(post_id), 1).ignore()).await;
+    }
+
+    // A post's comments changed, which also changes the post's commentCount
+    pub async fn invalidate_comments(&self, post_id: Uuid) {
+        self.invalidate_local_post(post_id).await;
+        self.notify(Invalidation::Post(post_id)).await;
+        self.invalidate(
+            redis::pipe()
+                .incr(post_generation_key(post_id), 1)
+                .ignore()
+                .incr(comments_generation_key(post_id), 1)
+                .ignore(),
+        )
+        .await;
+    }
+
+    // Posts were created or published
+    pub async fn invalidate_post_lists(&self) {
+        self.invalidate(&mut redis::pipe()).await;
+    }
+
+    // A user was deleted: their posts, of which there may be many, so every single post
+    // entry, and the listings
+    pub async fn invalidate_user_posts(&self, user_id: Uuid) {
+        self.clear_local_posts().await;
+        self.notify(Invalidation::UserPosts(user_id)).await;
+        self.invalidate(redis::pipe().incr(SINGLE_POSTS_GENERATION_KEY, 1).ignore()).await;
+    }
+
+    // A user's profile changed, the caller dropped its own /auth/me entry
+    pub async fn invalidate_user(&self, user_id: Uuid) {
+        self.notify(Invalidation::User(user_id)).await;
+    }
+
+    async fn notify(&self, invalidation: Invalidation) {
+        if let Some(notifier) = &self.notifier {
+            notifier.notify(invalidation).await;
+        }
+    }
+
+    // Only this instance's entry, invalidations of other instances (CACHE_NOTIFY) land here
+    #[cfg(feature = "post-cache")]
+    pub async fn invalidate_local_post(&self, post_id: Uuid) {
+        if let Some(posts) = &self.posts {
+            self.local_generation.fetch_add(1, Ordering::SeqCst);
+            posts.invalidate(&post_id).await;
+        }
+    }
+
+    #[cfg(not(feature = "post-cache"))]
+    pub async fn invalidate_local_post(&self, _post_id: Uuid) {}
+
+    #[cfg(feature = "post-cache")]
+    pub async fn clear_local_posts(&self) {
+        if let Some(posts) = &self.posts {
+            self.local_generation.fetch_add(1, Ordering::SeqCst);
+            posts.invalidate_all();
+        }
+    }
+
+    #[cfg(not(feature = "post-cache"))]
+    pub async fn clear_local_posts(&self) {}
+
+    // Every invalidation also bumps the listings generation, in the same round trip
+    async fn invalidate(&self, pipeline: &mut redis::Pipeline) {
+        let Some(mut redis) = self.redis.clone() else {
+            return;
+        };
+        if let Err(e) = pipeline
+            .incr(POSTS_GENERATION_KEY, 1)
+            .ignore()
+            .query_async::<()>(&mut redis)
+            .await
+        {
+            cache_error(e);
+        }
+    }
+}
+
+// A JSON response body serialized once, either cached or about to be
+pub struct CachedJson(Bytes);
+
+impl CachedJson {
+    fn serialize<T: Serialize>(value: &T) -> Result<Self, AppError> {
+        to_json_bytes(value).map(|body| Self(Bytes::from(body)))
+    }
+
+    // Weak ETag hashing the body, so it changes with any field (counters included).
+    // A single resource's is prefixed by its version (`W/"3-…"`), so it can be sent back
+    // as If-Match to update it (see `expected_version`).
+    fn etag(&self) -> String {
+        let hash = Sha256::digest(&self.0);
+        let hex: String = hash[..16].iter().map(|byte| format!("{:02x}", byte)).collect();
+        match self.version() {
+            Some(version) => format!("W/\"{}-{}\"", version, hex),
+            None => format!("W/\"{}\"", hex),
+        }
+    }
+
+    fn version(&self) -> Option<i32> {
+        #[derive(Deserialize)]
+        struct Versioned {
+            version: i32,
+        }
+
+        if self.0.first() != Some(&b'{') {
+            return None;
+        }
+        serde_json::from_slice::<Versioned>(&self.0).ok().map(|v| v.version)
+    }
+
+    // Adds the ETag, or answers 304 without a body when If-None-Match already lists it.
+    // Tags are compared weakly (ignoring `W/`), as If-None-Match requires.
+    pub fn conditional(self, headers: &HeaderMap) -> Response {
+        let etag = self.etag();
+        let opaque_tag = etag.trim_start_matches("W/");
+        let not_modified = headers
+            .get_all(header::IF_NONE_MATCH)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .any(|tag| tag == "*" || tag.trim_start_matches("W/") == opaque_tag);
+
+        if not_modified {
+            return (StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response();
+        }
+        ([(header::ETAG, etag)], self).into_response()
+    }
+}
+
+impl IntoResponse for CachedJson {
+    fn into_response(self) -> Response {
+        (
+            [(header::CONTENT_TYPE, HeaderValue::from_static("application/json"))],
+            self.0,
+        )
+            .into_response()
+    }
+}
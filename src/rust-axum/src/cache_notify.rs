@@ -0,0 +1,146 @@
+use sqlx::{
+    postgres::{PgConnectOptions, PgListener, PgPoolOptions},
+    PgPool,
+};
+use std::{env, sync::Arc, time::Duration};
+use uuid::Uuid;
+
+use crate::{cache::Cache, me_cache::MeCache, sql::SQL_NOTIFY_CACHE};
+
+// Cross-instance invalidation of the in-process caches (post-cache posts, /auth/me) over
+// Postgres LISTEN/NOTIFY, enabled by CACHE_NOTIFY. Every entry an instance drops is published
+// on CACHE_CHANNEL and the other instances drop it too. Redis entries are shared already.
+// LISTEN needs a session of its own, so this doesn't work behind PgBouncer transaction pooling.
+const CACHE_CHANNEL: &str = "apibench_cache";
+
+// Wait before listening again after the listener failed
+const RELISTEN_DELAY: Duration = Duration::from_secs(1);
+
+#[derive(Clone)]
+pub struct CacheNotifier {
+    pool: PgPool,
+    // Notifications of this instance are skipped by its own listener
+    instance: Uuid,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Invalidation {
+    Post(Uuid),
+    User(Uuid),
+    // A deleted user: every post entry is dropped
+    UserPosts(Uuid),
+}
+
+pub fn cache_notify_enabled() -> bool {
+    env::var("CACHE_NOTIFY")
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(false)
+}
+
+impl CacheNotifier {
+    // Publishes through `pool`, the primary (NOTIFY isn't allowed on a replica)
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            instance: Uuid::new_v4(),
+        }
+    }
+
+    // Failures are logged, other instances then serve the entry until its TTL
+    pub async fn notify(&self, invalidation: Invalidation) {
+        let (kind, id) = match invalidation {
+            Invalidation::Post(id) => ("post", id),
+            Invalidation::User(id) => ("user", id),
+            Invalidation::UserPosts(id) => ("user_posts", id),
+        };
+        let payload = format!("{} {} {}", self.instance, kind, id);
+        if let Err(e) = sqlx::query(SQL_NOTIFY_CACHE)
+            .bind(CACHE_CHANNEL)
+            .bind(&payload)
+            .execute(&self.pool)
+            .await
+        {
+            tracing::warn!("Cache invalidation not published: {}", e);
+        }
+    }
+}
+
+fn parse(payload: &str) -> Option<(Uuid, Invalidation)> {
+    let mut parts = payload.split(' ');
+    let instance = Uuid::parse_str(parts.next()?).ok()?;
+    let kind = parts.next()?;
+    let id = Uuid::parse_str(parts.next()?).ok()?;
+    match kind {
+        "post" => Some((instance, Invalidation::Post(id))),
+        "user" => Some((instance, Invalidation::User(id))),
+        "user_posts" => Some((instance, Invalidation::UserPosts(id))),
+        _ => None,
+    }
+}
+
+// Listens on its own connection, opened with `options`, for the lifetime of the process.
+// Notifications sent while it was disconnected are lost: the local caches are then cleared.
+pub fn spawn_listener(
+    options: PgConnectOptions,
+    notifier: &CacheNotifier,
+    cache: Cache,
+    me_cache: Option<Arc<MeCache>>,
+) {
+    let instance = notifier.instance;
+    let pool = PgPoolOptions::new().max_connections(1).connect_lazy_with(options);
+    tokio::spawn(async move {
+        let clear = || async {
+            cache.clear_local_posts().await;
+            if let Some(me_cache) = &me_cache {
+                me_cache.clear();
+            }
+        };
+        let mut listening_before = false;
+        loop {
+            let mut listener = match PgListener::connect_with(&pool).await {
+                Ok(listener) => listener,
+                Err(e) => {
+                    tracing::warn!("Cache invalidation listener failed to connect: {}", e);
+                    tokio::time::sleep(RELISTEN_DELAY).await;
+                    continue;
+                }
+            };
+            if let Err(e) = listener.listen(CACHE_CHANNEL).await {
+                tracing::warn!("Cache invalidation listener failed to listen: {}", e);
+                tokio::time::sleep(RELISTEN_DELAY).await;
+                continue;
+            }
+            if listening_before {
+                clear().await;
+            }
+            listening_before = true;
+            tracing::info!("Listening for cache invalidations on {}", CACHE_CHANNEL);
+
+            loop {
+                match listener.try_recv().await {
+                    Ok(Some(notification)) => match parse(notification.payload()) {
+                        Some((sender, _)) if sender == instance => {}
+                        Some((_, Invalidation::Post(post_id))) => cache.invalidate_local_post(post_id).await,
+                        Some((_, Invalidation::UserPosts(_))) => cache.clear_local_posts().await,
+                        Some((_, Invalidation::User(user_id))) => {
+                            if let Some(me_cache) = &me_cache {
+                                me_cache.invalidate(user_id);
+                            }
+                        }
+                        None => tracing::warn!("Invalid cache invalidation {:?}", notification.payload()),
+                    },
+                    // Reconnected (and listening again) after losing the connection
+                    Ok(None) => {
+                        tracing::warn!("Cache invalidation listener reconnected, local caches cleared");
+                        clear().await;
+                    }
+                    Err(e) => {
+                        tracing::warn!("Cache invalidation listener failed: {}", e);
+                        break;
+                    }
+                }
+            }
+            tokio::time::sleep(RELISTEN_DELAY).await;
+        }
+    });
+}
@@ -0,0 +1,212 @@
+use bytes::{BufMut, Bytes};
+use serde::Serialize;
+use std::{env, fmt::Display, sync::LazyLock};
+
+use crate::error::AppError;
+
+// Key casing of JSON responses. Models are declared camelCase (api/openapi.yaml),
+// snake_case is produced by rewriting the keys of the serialized response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsonCasing {
+    Camel,
+    Snake,
+}
+
+impl JsonCasing {
+    fn from_env() -> Self {
+        match env::var("JSON_CASING").as_deref() {
+            Ok("snake") => Self::Snake,
+            Ok("camel") | Err(_) => Self::Camel,
+            Ok(other) => {
+                tracing::warn!("Unknown JSON_CASING {:?}, using camel", other);
+                Self::Camel
+            }
+        }
+    }
+}
+
+pub static JSON_CASING: LazyLock<JsonCasing> = LazyLock::new(JsonCasing::from_env);
+
+// "likedByMe" -> "liked_by_me", keys already in snake_case are left as they are
+pub fn snake_case(key: &str) -> String {
+    let mut snake = String::with_capacity(key.len() + 4);
+    for c in key.chars() {
+        if c.is_ascii_uppercase() {
+            if !snake.is_empty() {
+                snake.push('_');
+            }
+            snake.push(c.to_ascii_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
+
+// Rewrites every object key of compact JSON (as `encode` writes it), nested ones included,
+// into `out`. Keys are the strings directly followed by ':', the rest is copied as is, so
+// the document keeps its order without going through a serde_json Value.
+fn snake_case_keys(json: &[u8], out: &mut impl BufMut) {
+    let mut i = 0;
+    while i < json.len() {
+        let start = i;
+        while i < json.len() && json[i] != b'"' {
+            i += 1;
+        }
+        out.put_slice(&json[start..i]);
+        if i == json.len() {
+            break;
+        }
+
+        // A string, escapes included (\" doesn't end it)
+        let string_start = i + 1;
+        let mut end = string_start;
+        while end < json.len() && json[end] != b'"' {
+            end += if json[end] == b'\\' { 2 } else { 1 };
+        }
+        let end = end.min(json.len());
+        let string = &json[string_start..end];
+        out.put_u8(b'"');
+        if json.get(end + 1) == Some(&b':') {
+            put_snake_case(string, out);
+        } else {
+            out.put_slice(string);
+        }
+        if end < json.len() {
+            out.put_u8(b'"');
+        }
+        i = end + 1;
+    }
+}
+
+// Same rule as `snake_case`, on the raw bytes of a key: escape sequences (\u00C9) are kept
+fn put_snake_case(key: &[u8], out: &mut impl BufMut) {
+    let mut i = 0;
+    while i < key.len() {
+        match key[i] {
+            b'\\' => {
+                let escape_end = if key.get(i + 1) == Some(&b'u') { i + 6 } else { i + 2 };
+                let escape_end = escape_end.min(key.len());
+                out.put_slice(&key[i..escape_end]);
+                i = escape_end;
+                continue;
+            }
+            c if c.is_ascii_uppercase() => {
+                if i > 0 {
+                    out.put_u8(b'_');
+                }
+                out.put_u8(c.to_ascii_lowercase());
+            }
+            c => out.put_u8(c),
+        }
+        i += 1;
+    }
+}
+
+// Serializes a response body with the configured key casing
+pub fn to_json_bytes<T: Serialize>(value: &T) -> Result<Vec<u8>, AppError> {
+    let json = encode(value)?;
+    match *JSON_CASING {
+        JsonCasing::Camel => Ok(json),
+        JsonCasing::Snake => {
+            let mut snake = Vec::with_capacity(json.len() + json.len() / 16);
+            snake_case_keys(&json, &mut snake);
+            Ok(snake)
+        }
+    }
+}
+
+// Same, as a response body sent once: serialized into a buffer of the pool with the
+// `pooled-buffers` feature, rather than into a Vec allocated (and regrown) per response
+#[cfg(not(feature = "pooled-buffers"))]
+pub fn to_json_body<T: Serialize>(value: &T) -> Result<Bytes, AppError> {
+    to_json_bytes(value).map(Bytes::from)
+}
+
+#[cfg(feature = "pooled-buffers")]
+pub fn to_json_body<T: Serialize>(value: &T) -> Result<Bytes, AppError> {
+    crate::response_buffer::pooled_body(|buffer| match *JSON_CASING {
+        JsonCasing::Camel => encode_into(buffer, value),
+        JsonCasing::Snake => {
+            snake_case_keys(&encode(value)?, buffer);
+            Ok(())
+        }
+    })
+}
+
+fn serialize_error(e: impl Display) -> AppError {
+    AppError::InternalServerError(format!("Failed to serialize response: {}", e))
+}
+
+#[cfg(not(feature = "sonic-json"))]
+fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>, AppError> {
+    serde_json::to_vec(value).map_err(serialize_error)
+}
+
+// SIMD encoder, a drop-in for serde_json on the same Serialize impls
+#[cfg(feature = "sonic-json")]
+fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>, AppError> {
+    sonic_rs::to_vec(value).map_err(serialize_error)
+}
+
+#[cfg(all(feature = "pooled-buffers", not(feature = "sonic-json")))]
+fn encode_into<T: Serialize>(buffer: &mut bytes::BytesMut, value: &T) -> Result<(), AppError> {
+    serde_json::to_writer(buffer.writer(), value).map_err(serialize_error)
+}
+
+#[cfg(all(feature = "pooled-buffers", feature = "sonic-json"))]
+fn encode_into<T: Serialize>(buffer: &mut bytes::BytesMut, value: &T) -> Result<(), AppError> {
+    sonic_rs::to_writer(buffer.writer(), value).map_err(serialize_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snake(json: &str) -> String {
+        let mut out = Vec::new();
+        snake_case_keys(json.as_bytes(), &mut out);
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn rewrites_keys_in_order() {
+        assert_eq!(
+            snake(r#"{"postId":1,"likeCount":2,"id":"x","createdAt":null}"#),
+            r#"{"post_id":1,"like_count":2,"id":"x","created_at":null}"#
+        );
+    }
+
+    #[test]
+    fn rewrites_nested_keys() {
+        assert_eq!(
+            snake(r#"{"repostOf":{"authorId":"a","likedByMe":true},"items":[{"nextCursor":null},{}]}"#),
+            r#"{"repost_of":{"author_id":"a","liked_by_me":true},"items":[{"next_cursor":null},{}]}"#
+        );
+    }
+
+    #[test]
+    fn leaves_string_values_alone() {
+        assert_eq!(snake(r#"["aB","cD"]"#), r#"["aB","cD"]"#);
+        assert_eq!(
+            snake(r#"{"content":"likeCount\":\"aB\":","bio":"x\\","avatarUrl":"AB"}"#),
+            r#"{"content":"likeCount\":\"aB\":","bio":"x\\","avatar_url":"AB"}"#
+        );
+    }
+
+    #[test]
+    fn keeps_escapes_in_keys() {
+        assert_eq!(snake(r#"{"a\"B":1}"#), r#"{"a\"_b":1}"#);
+        assert_eq!(snake(r#"{"caf\u00e9Au":1}"#), r#"{"caf\u00e9_au":1}"#);
+        assert_eq!(snake(r#"{"\u00C9tatX":1}"#), r#"{"\u00C9tat_x":1}"#);
+        assert_eq!(snake(r#"{"\u0041B":"\u0041B"}"#), r#"{"\u0041_b":"\u0041B"}"#);
+        assert_eq!(snake(r#"{"a\\":{"bC":1}}"#), r#"{"a\\":{"b_c":1}}"#);
+    }
+
+    #[test]
+    fn matches_snake_case() {
+        for key in ["likedByMe", "id", "already_snake", "URL", "avatarUrl"] {
+            assert_eq!(snake(&format!(r#"{{"{}":0}}"#, key)), format!(r#"{{"{}":0}}"#, snake_case(key)));
+        }
+    }
+}
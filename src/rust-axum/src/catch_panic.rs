@@ -0,0 +1,76 @@
+use axum::response::{IntoResponse, Response};
+use std::any::Any;
+#[cfg(not(feature = "no-tracing"))]
+use {
+    crate::log_format::{ACCESS_LOG, JSON_LOGS},
+    axum::{
+        extract::{MatchedPath, Request},
+        middleware::Next,
+    },
+    tower_http::request_id::RequestId,
+    tracing::{field::Empty, Instrument},
+};
+
+use crate::error::{AppError, ErrorCode};
+
+// CatchPanicLayer handler: the usual JSON 500 instead of a dropped connection
+pub fn panic_response(panic: Box<dyn Any + Send + 'static>) -> Response {
+    let message = panic
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic");
+    tracing::error!("Handler panicked: {}", message);
+    AppError::from(ErrorCode::InternalError).into_response()
+}
+
+// Runs the request in a span carrying its x-request-id, so errors logged by handlers
+// (and by panic_response) can be matched to the response the client got.
+// JSON logs and the access log also get the method and route on the span.
+#[cfg(not(feature = "no-tracing"))]
+pub async fn request_span_middleware(request: Request, next: Next) -> Response {
+    let request_id = request
+        .extensions()
+        .get::<RequestId>()
+        .and_then(|id| id.header_value().to_str().ok())
+        .unwrap_or_default();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or("", |route| route.as_str());
+
+    #[cfg(not(feature = "otel"))]
+    let span = tracing::info_span!("request", request_id = %request_id, method = Empty, route = Empty);
+    // Exported as the server span of the request, named after its route
+    #[cfg(feature = "otel")]
+    let span = tracing::info_span!(
+        "request",
+        request_id = %request_id,
+        method = Empty,
+        route = Empty,
+        otel.name = %format!("{} {}", request.method(), route).trim_end(),
+        otel.kind = "server",
+        otel.status_code = Empty,
+        http.request.method = %request.method(),
+        http.route = route,
+        http.response.status_code = Empty,
+    );
+    if *JSON_LOGS || *ACCESS_LOG {
+        span.record("method", request.method().as_str());
+        span.record("route", route);
+    }
+
+    #[cfg(not(feature = "otel"))]
+    let response = next.run(request).instrument(span).await;
+    #[cfg(feature = "otel")]
+    let response = next.run(request).instrument(span.clone()).await;
+
+    #[cfg(feature = "otel")]
+    {
+        span.record("http.response.status_code", response.status().as_u16());
+        if response.status().is_server_error() {
+            span.record("otel.status_code", "error");
+        }
+    }
+    response
+}
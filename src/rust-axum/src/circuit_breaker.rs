@@ -0,0 +1,151 @@
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::{
+    env,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::error::{AppError, DbUnavailable, ErrorCode};
+
+// Circuit breaker in front of the routes, enabled by DB_CIRCUIT_FAILURE_RATE.
+// Once that share of the requests of a DB_CIRCUIT_WINDOW_SECS window (at least
+// DB_CIRCUIT_MIN_REQUESTS of them) failed on an unavailable database (pool timeout, lost
+// connection, statement timeout) or timed out (504), it opens: requests fail fast with a 503 for
+// DB_CIRCUIT_OPEN_SECS instead of each waiting out DB_POOL_ACQUIRE_TIMEOUT. It then lets a
+// single probe request through, which closes it again or reopens it.
+#[derive(Clone)]
+pub struct CircuitBreaker {
+    state: Arc<Mutex<BreakerState>>,
+    failure_rate: f64,
+    min_requests: u32,
+    window: Duration,
+    open_for: Duration,
+}
+
+enum BreakerState {
+    Closed { window_start: Instant, requests: u32, failures: u32 },
+    Open { until: Instant },
+    // A probe started at this instant is in flight, others still fail fast
+    HalfOpen { probe_started: Instant },
+}
+
+pub fn circuit_breaker_from_env() -> Option<CircuitBreaker> {
+    let failure_rate = env::var("DB_CIRCUIT_FAILURE_RATE")
+        .ok()
+        .and_then(|v| v.parse::<f64>().ok())
+        .filter(|&rate| rate > 0.0 && rate <= 1.0)?;
+    let min_requests = env::var("DB_CIRCUIT_MIN_REQUESTS")
+        .ok()
+        .and_then(|v| v.parse::<u32>().ok())
+        .unwrap_or(20)
+        .max(1);
+    let window_secs = env::var("DB_CIRCUIT_WINDOW_SECS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .filter(|&secs| secs > 0)
+        .unwrap_or(10);
+    let open_secs = env::var("DB_CIRCUIT_OPEN_SECS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .filter(|&secs| secs > 0)
+        .unwrap_or(5);
+
+    tracing::info!(
+        "Database circuit breaker enabled: opens at {}% failures over {} s (min {} requests), for {} s",
+        failure_rate * 100.0,
+        window_secs,
+        min_requests,
+        open_secs
+    );
+    Some(CircuitBreaker {
+        state: Arc::new(Mutex::new(BreakerState::Closed {
+            window_start: Instant::now(),
+            requests: 0,
+            failures: 0,
+        })),
+        failure_rate,
+        min_requests,
+        window: Duration::from_secs(window_secs),
+        open_for: Duration::from_secs(open_secs),
+    })
+}
+
+impl CircuitBreaker {
+    // Whether the request may run, and if so whether it is the half-open probe
+    fn admit(&self) -> Option<bool> {
+        let mut state = self.state.lock().expect("circuit breaker lock poisoned");
+        let now = Instant::now();
+        match *state {
+            BreakerState::Closed { .. } => Some(false),
+            BreakerState::Open { until } if now < until => None,
+            // A probe that never reported back (e.g. cancelled) is replaced after a while
+            BreakerState::HalfOpen { probe_started } if now < probe_started + self.open_for => None,
+            BreakerState::Open { .. } | BreakerState::HalfOpen { .. } => {
+                *state = BreakerState::HalfOpen { probe_started: now };
+                Some(true)
+            }
+        }
+    }
+
+    fn record(&self, probe: bool, failed: bool) {
+        let mut state = self.state.lock().expect("circuit breaker lock poisoned");
+        let now = Instant::now();
+        match &mut *state {
+            BreakerState::Closed { window_start, requests, failures } => {
+                if now.duration_since(*window_start) >= self.window {
+                    (*window_start, *requests, *failures) = (now, 0, 0);
+                }
+                *requests += 1;
+                *failures += failed as u32;
+                if *requests >= self.min_requests
+                    && f64::from(*failures) >= self.failure_rate * f64::from(*requests)
+                {
+                    tracing::warn!(
+                        "Database circuit breaker open: {} of {} requests failed, failing fast for {:?}",
+                        failures,
+                        requests,
+                        self.open_for
+                    );
+                    *state = BreakerState::Open { until: now + self.open_for };
+                }
+            }
+            BreakerState::HalfOpen { .. } if probe => {
+                if failed {
+                    tracing::warn!("Database circuit breaker probe failed, open for {:?}", self.open_for);
+                    *state = BreakerState::Open { until: now + self.open_for };
+                } else {
+                    tracing::info!("Database circuit breaker probe succeeded, closed");
+                    *state = BreakerState::Closed { window_start: now, requests: 0, failures: 0 };
+                }
+            }
+            // Requests admitted before the breaker opened finishing late
+            BreakerState::Open { .. } | BreakerState::HalfOpen { .. } => {}
+        }
+    }
+}
+
+pub async fn circuit_breaker_middleware(
+    State(breaker): State<CircuitBreaker>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(probe) = breaker.admit() else {
+        return AppError::Coded(
+            ErrorCode::ServiceUnavailable,
+            "Database unavailable, retry later".to_string(),
+        )
+        .into_response();
+    };
+    let response = next.run(request).await;
+    // A 504 from REQUEST_TIMEOUT_*_MS (timeout.rs, run inside) is a database stall too
+    // when the timeout is shorter than the pool's acquire timeout
+    let failed = response.extensions().get::<DbUnavailable>().is_some()
+        || response.status() == StatusCode::GATEWAY_TIMEOUT;
+    breaker.record(probe, failed);
+    response
+}
@@ -0,0 +1,54 @@
+use std::env;
+use tower_http::compression::{
+    predicate::{And, NotForContentType, Predicate, SizeAbove},
+    CompressionLayer,
+};
+
+type CompressWhen = And<And<And<SizeAbove, NotForContentType>, NotForContentType>, NotForContentType>;
+
+// Response compression negotiated from Accept-Encoding, among the algorithms listed in
+// COMPRESSION_ALGORITHMS (`gzip`, `br`, `zstd`). Disabled unless at least one is enabled.
+// Bodies under COMPRESSION_MIN_BYTES are sent as they are, so are images (avatars) already compressed.
+pub fn compression_from_env() -> Option<CompressionLayer<CompressWhen>> {
+    let algorithms = env::var("COMPRESSION_ALGORITHMS").ok()?;
+    let (mut gzip, mut br, mut zstd) = (false, false, false);
+    for algorithm in algorithms.split(',').map(str::trim).filter(|a| !a.is_empty()) {
+        match algorithm {
+            "gzip" => gzip = true,
+            "br" => br = true,
+            "zstd" => zstd = true,
+            other => tracing::warn!("Unknown compression algorithm {:?}, ignored", other),
+        }
+    }
+    if !(gzip || br || zstd) {
+        return None;
+    }
+
+    let min_bytes = env::var("COMPRESSION_MIN_BYTES")
+        .ok()
+        .and_then(|v| v.parse::<u16>().ok())
+        .unwrap_or(1024);
+
+    let enabled: Vec<&str> = [(gzip, "gzip"), (br, "br"), (zstd, "zstd")]
+        .into_iter()
+        .filter_map(|(enabled, name)| enabled.then_some(name))
+        .collect();
+    tracing::info!(
+        "Response compression enabled: {} for bodies of at least {} bytes",
+        enabled.join(", "),
+        min_bytes
+    );
+
+    Some(
+        CompressionLayer::new()
+            .gzip(gzip)
+            .br(br)
+            .zstd(zstd)
+            .compress_when(
+                SizeAbove::new(min_bytes)
+                    .and(NotForContentType::GRPC)
+                    .and(NotForContentType::IMAGES)
+                    .and(NotForContentType::SSE),
+            ),
+    )
+}
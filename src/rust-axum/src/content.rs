@@ -0,0 +1,117 @@
+// Parsing of user-written post/comment content (hashtags, mentions)
+
+// Longest hashtag stored, matches hashtags.tag VARCHAR(100)
+const MAX_HASHTAG_LENGTH: usize = 100;
+// Usernames are VARCHAR(255)
+const MAX_MENTION_LENGTH: usize = 255;
+// Cap per post/comment to bound notification fan-out
+const MAX_MENTIONS_PER_CONTENT: usize = 20;
+// Cap per post so a single request can't fan out into thousands of rows
+const MAX_HASHTAGS_PER_POST: usize = 30;
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+// Usernames may contain '.' and '-' (validation.rs), not at the end of a mention:
+// "@alice." ending a sentence mentions alice
+fn is_mention_inner_char(c: char) -> bool {
+    matches!(c, '.' | '-')
+}
+
+// Words following `sigil` at the start of the content or after a non-word character,
+// lowercased and deduplicated in order of appearance. `is_inner` accepts the other
+// characters a word may contain, trimmed from its end.
+fn extract_prefixed_words(
+    content: &str,
+    sigil: char,
+    is_inner: fn(char) -> bool,
+    max_len: usize,
+    max_count: usize,
+) -> Vec<String> {
+    let mut words: Vec<String> = Vec::new();
+    let mut previous: Option<char> = None;
+    let mut chars = content.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        let at_boundary = previous.is_none_or(|p| !is_word_char(p));
+        previous = Some(c);
+        if c != sigil || !at_boundary {
+            continue;
+        }
+
+        let word_start = start + c.len_utf8();
+        let mut word_end = word_start;
+        while let Some(&(i, next)) = chars.peek() {
+            if !is_word_char(next) && !is_inner(next) {
+                break;
+            }
+            word_end = i + next.len_utf8();
+            previous = Some(next);
+            chars.next();
+        }
+
+        let word = content[word_start..word_end].trim_end_matches(is_inner).to_lowercase();
+        if word.is_empty() || word.chars().count() > max_len || words.contains(&word) {
+            continue;
+        }
+        words.push(word);
+        if words.len() == max_count {
+            break;
+        }
+    }
+
+    words
+}
+
+pub fn extract_hashtags(content: &str) -> Vec<String> {
+    extract_prefixed_words(content, '#', |_| false, MAX_HASHTAG_LENGTH, MAX_HASHTAGS_PER_POST)
+}
+
+// Lowercased usernames mentioned as `@username`
+pub fn extract_mentions(content: &str) -> Vec<String> {
+    extract_prefixed_words(content, '@', is_mention_inner_char, MAX_MENTION_LENGTH, MAX_MENTIONS_PER_CONTENT)
+}
+
+// Normalize a tag coming from a URL (optional leading '#', any case)
+pub fn normalize_hashtag(tag: &str) -> String {
+    tag.trim_start_matches('#').to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_hashtags() {
+        assert_eq!(extract_hashtags("#Rust and #async_io, #rust again"), ["rust", "async_io"]);
+        assert_eq!(extract_hashtags("(#tag) #été"), ["tag", "été"]);
+    }
+
+    #[test]
+    fn hashtags_need_a_word_boundary() {
+        assert_eq!(extract_hashtags("issue#12 a##b #"), ["b"]);
+        assert_eq!(extract_hashtags("#v1.2 #a-b"), ["v1", "a"]);
+    }
+
+    #[test]
+    fn caps_hashtags() {
+        let too_long = format!("#{}", "a".repeat(MAX_HASHTAG_LENGTH + 1));
+        assert!(extract_hashtags(&too_long).is_empty());
+        let many: String = (0..MAX_HASHTAGS_PER_POST + 5).map(|i| format!("#t{} ", i)).collect();
+        assert_eq!(extract_hashtags(&many).len(), MAX_HASHTAGS_PER_POST);
+    }
+
+    #[test]
+    fn extracts_mentions() {
+        assert_eq!(extract_mentions("@Alice and @bob_2, @alice"), ["alice", "bob_2"]);
+        assert_eq!(extract_mentions("mail me@example.com"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn mentions_keep_inner_dots_and_dashes() {
+        assert_eq!(extract_mentions("hi @john.doe and @mary-jane"), ["john.doe", "mary-jane"]);
+        assert_eq!(extract_mentions("thanks @alice. Also @bob-, @carol..."), ["alice", "bob", "carol"]);
+        assert_eq!(extract_mentions("@. @-"), Vec::<String>::new());
+    }
+}
@@ -0,0 +1,180 @@
+use serde::Serialize;
+use std::{
+    env,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        LazyLock,
+    },
+};
+use tokio::runtime::{Handle, RuntimeFlavor, RuntimeMetrics};
+
+use crate::{runtime::Shard, ALLOCATOR};
+
+// GET /debug/stats, enabled by DEBUG_STATS: Tokio runtime metrics, resident memory and the
+// jemalloc statistics in alloc-jemalloc builds, for the benchmark harness to sample during a run
+pub static DEBUG_STATS: LazyLock<bool> = LazyLock::new(|| {
+    env::var("DEBUG_STATS")
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(false)
+});
+
+// bcrypt jobs queued or running on the blocking pool, process-wide
+static BLOCKING_JOBS: AtomicUsize = AtomicUsize::new(0);
+
+// Counts a spawn_blocking job until it is dropped, moved into the job's closure
+pub struct BlockingJob(());
+
+impl BlockingJob {
+    pub fn start() -> Self {
+        BLOCKING_JOBS.fetch_add(1, Ordering::Relaxed);
+        BlockingJob(())
+    }
+}
+
+impl Drop for BlockingJob {
+    fn drop(&mut self) {
+        BLOCKING_JOBS.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DebugStats {
+    pub shard: usize,
+    pub shards: usize,
+    pub runtime: RuntimeStats,
+    pub memory: MemoryStats,
+}
+
+// Of the runtime serving the request: the shard's with the sharded runtime
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuntimeStats {
+    pub flavor: &'static str,
+    pub workers: usize,
+    pub alive_tasks: usize,
+    pub global_queue_depth: usize,
+    // Time each worker spent polling tasks since startup
+    pub worker_busy_ms: Vec<u64>,
+    pub blocking_jobs: usize,
+    // Only in tokio_unstable builds, where Tokio exposes it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blocking_pool: Option<BlockingPoolStats>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockingPoolStats {
+    pub threads: usize,
+    pub idle_threads: usize,
+    pub queue_depth: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryStats {
+    pub allocator: &'static str,
+    // VmRSS of /proc/self/status, null off Linux
+    pub rss_bytes: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jemalloc: Option<JemallocStats>,
+}
+
+// Bytes, as of the refresh made by the request (jemalloc caches them until told to)
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JemallocStats {
+    pub allocated: usize,
+    pub active: usize,
+    pub resident: usize,
+    pub mapped: usize,
+    pub retained: usize,
+    pub metadata: usize,
+}
+
+impl DebugStats {
+    pub fn collect(shard: Shard) -> Self {
+        DebugStats {
+            shard: shard.index,
+            shards: shard.count,
+            runtime: runtime_stats(),
+            memory: MemoryStats {
+                allocator: ALLOCATOR,
+                rss_bytes: rss_bytes(),
+                jemalloc: jemalloc_stats(),
+            },
+        }
+    }
+}
+
+fn runtime_stats() -> RuntimeStats {
+    let handle = Handle::current();
+    let metrics = handle.metrics();
+    RuntimeStats {
+        flavor: match handle.runtime_flavor() {
+            RuntimeFlavor::CurrentThread => "current_thread",
+            _ => "multi_thread",
+        },
+        workers: metrics.num_workers(),
+        alive_tasks: metrics.num_alive_tasks(),
+        global_queue_depth: metrics.global_queue_depth(),
+        worker_busy_ms: (0..metrics.num_workers())
+            .map(|worker| metrics.worker_total_busy_duration(worker).as_millis() as u64)
+            .collect(),
+        blocking_jobs: BLOCKING_JOBS.load(Ordering::Relaxed),
+        blocking_pool: blocking_pool_stats(&metrics),
+    }
+}
+
+#[cfg(tokio_unstable)]
+fn blocking_pool_stats(metrics: &RuntimeMetrics) -> Option<BlockingPoolStats> {
+    Some(BlockingPoolStats {
+        threads: metrics.num_blocking_threads(),
+        idle_threads: metrics.num_idle_blocking_threads(),
+        queue_depth: metrics.blocking_queue_depth(),
+    })
+}
+
+#[cfg(not(tokio_unstable))]
+fn blocking_pool_stats(_metrics: &RuntimeMetrics) -> Option<BlockingPoolStats> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn rss_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let kib = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))?
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(kib * 1024)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn rss_bytes() -> Option<u64> {
+    None
+}
+
+#[cfg(all(feature = "alloc-jemalloc", not(feature = "alloc-mimalloc")))]
+fn jemalloc_stats() -> Option<JemallocStats> {
+    use tikv_jemalloc_ctl::{epoch, stats};
+
+    epoch::advance().ok()?;
+    Some(JemallocStats {
+        allocated: stats::allocated::read().ok()?,
+        active: stats::active::read().ok()?,
+        resident: stats::resident::read().ok()?,
+        mapped: stats::mapped::read().ok()?,
+        retained: stats::retained::read().ok()?,
+        metadata: stats::metadata::read().ok()?,
+    })
+}
+
+#[cfg(not(all(feature = "alloc-jemalloc", not(feature = "alloc-mimalloc"))))]
+fn jemalloc_stats() -> Option<JemallocStats> {
+    None
+}
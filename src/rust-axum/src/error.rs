@@ -1,14 +1,201 @@
 use axum::{
-    http::StatusCode,
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
-    Json,
 };
-use serde_json::json;
+use serde::Serialize;
+
+use crate::{extract::Json, pool_metrics, request_id::current_request_id, validation::FieldError};
+
+// Stable machine-readable error codes, sent as `code` next to the English `detail`.
+// Variants not listed below get the generic code of their status.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    // Generic codes, one per status
+    BadRequest,
+    Unauthorized,
+    Forbidden,
+    NotFound,
+    MethodNotAllowed,
+    Conflict,
+    ValidationFailed,
+    UnprocessableEntity,
+    PayloadTooLarge,
+    RateLimited,
+    InternalError,
+    ServiceUnavailable,
+    Timeout,
+
+    // 400
+    InvalidId,
+    InvalidJson,
+    InvalidQuery,
+    InvalidCursor,
+    InvalidAvatar,
+    CannotFollowSelf,
+    // 401
+    MissingToken,
+    InvalidToken,
+    InvalidCredentials,
+    // 403
+    AdminRequired,
+    NotOwner,
+    InvalidCsrfToken,
+    // 404
+    PostNotFound,
+    UserNotFound,
+    CommentNotFound,
+    ConversationNotFound,
+    NotificationNotFound,
+    LikeNotFound,
+    BookmarkNotFound,
+    FollowNotFound,
+    RouteNotFound,
+    // 409
+    AlreadyLiked,
+    AlreadyBookmarked,
+    AlreadyReposted,
+    AlreadyFollowing,
+    AlreadyPublished,
+    UsernameTaken,
+    EmailTaken,
+    IdempotencyKeyInProgress,
+    // 412
+    VersionMismatch,
+    // 415
+    UnsupportedMediaType,
+    // 422
+    InvalidBody,
+    InvalidPagination,
+    IdempotencyKeyReused,
+    // 503
+    Overloaded,
+}
+
+impl ErrorCode {
+    pub fn status(self) -> StatusCode {
+        match self {
+            ErrorCode::BadRequest
+            | ErrorCode::InvalidId
+            | ErrorCode::InvalidJson
+            | ErrorCode::InvalidQuery
+            | ErrorCode::InvalidCursor
+            | ErrorCode::InvalidAvatar
+            | ErrorCode::CannotFollowSelf => StatusCode::BAD_REQUEST,
+            ErrorCode::Unauthorized
+            | ErrorCode::MissingToken
+            | ErrorCode::InvalidToken
+            | ErrorCode::InvalidCredentials => StatusCode::UNAUTHORIZED,
+            ErrorCode::Forbidden
+            | ErrorCode::AdminRequired
+            | ErrorCode::NotOwner
+            | ErrorCode::InvalidCsrfToken => StatusCode::FORBIDDEN,
+            ErrorCode::NotFound
+            | ErrorCode::PostNotFound
+            | ErrorCode::UserNotFound
+            | ErrorCode::CommentNotFound
+            | ErrorCode::ConversationNotFound
+            | ErrorCode::NotificationNotFound
+            | ErrorCode::LikeNotFound
+            | ErrorCode::BookmarkNotFound
+            | ErrorCode::FollowNotFound
+            | ErrorCode::RouteNotFound => StatusCode::NOT_FOUND,
+            ErrorCode::MethodNotAllowed => StatusCode::METHOD_NOT_ALLOWED,
+            ErrorCode::Conflict
+            | ErrorCode::AlreadyLiked
+            | ErrorCode::AlreadyBookmarked
+            | ErrorCode::AlreadyReposted
+            | ErrorCode::AlreadyFollowing
+            | ErrorCode::AlreadyPublished
+            | ErrorCode::UsernameTaken
+            | ErrorCode::EmailTaken
+            | ErrorCode::IdempotencyKeyInProgress => StatusCode::CONFLICT,
+            ErrorCode::ValidationFailed
+            | ErrorCode::UnprocessableEntity
+            | ErrorCode::InvalidBody
+            | ErrorCode::InvalidPagination
+            | ErrorCode::IdempotencyKeyReused => StatusCode::UNPROCESSABLE_ENTITY,
+            ErrorCode::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            ErrorCode::VersionMismatch => StatusCode::PRECONDITION_FAILED,
+            ErrorCode::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            ErrorCode::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            ErrorCode::InternalError => StatusCode::INTERNAL_SERVER_ERROR,
+            ErrorCode::ServiceUnavailable | ErrorCode::Overloaded => StatusCode::SERVICE_UNAVAILABLE,
+            ErrorCode::Timeout => StatusCode::GATEWAY_TIMEOUT,
+        }
+    }
+
+    // Default `detail` when the error is built from the code alone
+    pub fn message(self) -> &'static str {
+        match self {
+            ErrorCode::BadRequest => "Bad request",
+            ErrorCode::Unauthorized => "Unauthorized",
+            ErrorCode::Forbidden => "Forbidden",
+            ErrorCode::NotFound => "Not found",
+            ErrorCode::MethodNotAllowed => "Method not allowed",
+            ErrorCode::Conflict => "Conflict",
+            ErrorCode::ValidationFailed => "Validation failed",
+            ErrorCode::UnprocessableEntity => "Unprocessable entity",
+            ErrorCode::PayloadTooLarge => "Request body too large",
+            ErrorCode::RateLimited => "Too many requests",
+            ErrorCode::InternalError => "Internal server error",
+            ErrorCode::ServiceUnavailable => "Service temporarily unavailable, retry later",
+            ErrorCode::Timeout => "Request took too long",
+            ErrorCode::InvalidId => "Invalid ID",
+            ErrorCode::InvalidJson => "Invalid JSON body",
+            ErrorCode::InvalidQuery => "Invalid query string",
+            ErrorCode::InvalidCursor => "Invalid cursor",
+            ErrorCode::InvalidAvatar => "Invalid avatar",
+            ErrorCode::CannotFollowSelf => "You cannot follow yourself",
+            ErrorCode::MissingToken => "Missing authorization header",
+            ErrorCode::InvalidToken => "Invalid token",
+            ErrorCode::InvalidCredentials => "Invalid credentials",
+            ErrorCode::AdminRequired => "Admin access required",
+            ErrorCode::NotOwner => "You can only modify your own resources",
+            ErrorCode::InvalidCsrfToken => "Invalid CSRF token",
+            ErrorCode::PostNotFound => "Post not found",
+            ErrorCode::UserNotFound => "User not found",
+            ErrorCode::CommentNotFound => "Comment not found",
+            ErrorCode::ConversationNotFound => "Conversation not found",
+            ErrorCode::NotificationNotFound => "Notification not found",
+            ErrorCode::LikeNotFound => "Post or like not found",
+            ErrorCode::BookmarkNotFound => "Post or bookmark not found",
+            ErrorCode::FollowNotFound => "User or follow not found",
+            ErrorCode::RouteNotFound => "No such route",
+            ErrorCode::AlreadyLiked => "Post already liked",
+            ErrorCode::AlreadyBookmarked => "Post already bookmarked",
+            ErrorCode::AlreadyReposted => "Post already reposted",
+            ErrorCode::AlreadyFollowing => "User already followed",
+            ErrorCode::AlreadyPublished => "Post is already published",
+            ErrorCode::UsernameTaken => "Username already taken",
+            ErrorCode::EmailTaken => "Email already taken",
+            ErrorCode::IdempotencyKeyInProgress => "A request with this Idempotency-Key is still in progress",
+            ErrorCode::UnsupportedMediaType => "Unsupported Content-Type",
+            ErrorCode::InvalidBody => "Request body doesn't match the expected shape",
+            ErrorCode::InvalidPagination => "Invalid limit or offset",
+            ErrorCode::VersionMismatch => "Resource was modified since the given version",
+            ErrorCode::IdempotencyKeyReused => "Idempotency-Key was already used for a different request",
+            ErrorCode::Overloaded => "Server overloaded, retry later",
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ErrorBody<'a> {
+    detail: &'a str,
+    code: ErrorCode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fields: Option<Vec<FieldError>>,
+    // The x-request-id header of the response, to find the request in the logs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<&'a str>,
+}
 
 #[derive(Debug, thiserror::Error)]
 pub enum AppError {
     #[error("Database error: {0}")]
-    Database(#[from] sqlx::Error),
+    Database(sqlx::Error),
     
     #[error("Unauthorized: {0}")]
     Unauthorized(String),
@@ -25,40 +212,167 @@ pub enum AppError {
     #[error("Conflict: {0}")]
     Conflict(String),
     
+    #[error("Validation failed: {0:?}")]
+    Validation(Vec<FieldError>),
+    
+    #[error("Unprocessable entity: {0}")]
+    UnprocessableEntity(String),
+    
+    #[error("Payload too large: {0}")]
+    PayloadTooLarge(String),
+    
+    #[error("Too many requests: {0}")]
+    TooManyRequests(String),
+    
     #[error("Internal server error: {0}")]
     InternalServerError(String),
+    
+    // The database is saturated or unreachable, sent with a Retry-After header
+    #[error("Service unavailable: {0}")]
+    ServiceUnavailable(sqlx::Error),
+    
+    // Any status, given by the code
+    #[error("{1}")]
+    Coded(ErrorCode, String),
+}
+
+impl AppError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            AppError::Database(_) => ErrorCode::InternalError,
+            AppError::Unauthorized(_) => ErrorCode::Unauthorized,
+            AppError::Forbidden(_) => ErrorCode::Forbidden,
+            AppError::NotFound(_) => ErrorCode::NotFound,
+            AppError::BadRequest(_) => ErrorCode::BadRequest,
+            AppError::Conflict(_) => ErrorCode::Conflict,
+            AppError::Validation(_) => ErrorCode::ValidationFailed,
+            AppError::UnprocessableEntity(_) => ErrorCode::UnprocessableEntity,
+            AppError::PayloadTooLarge(_) => ErrorCode::PayloadTooLarge,
+            AppError::TooManyRequests(_) => ErrorCode::RateLimited,
+            AppError::InternalServerError(_) => ErrorCode::InternalError,
+            AppError::ServiceUnavailable(_) => ErrorCode::ServiceUnavailable,
+            AppError::Coded(code, _) => *code,
+        }
+    }
+}
+
+// `ErrorCode::PostNotFound.into()`, with the code's default message
+impl From<ErrorCode> for AppError {
+    fn from(code: ErrorCode) -> Self {
+        AppError::Coded(code, code.message().to_string())
+    }
+}
+
+// Seconds clients are told to wait after a 503
+const RETRY_AFTER_SECS: u64 = 1;
+
+// Pool exhaustion, lost connections and timed out statements are transient, anything else is a bug or bad data
+fn is_transient(e: &sqlx::Error) -> bool {
+    match e {
+        sqlx::Error::PoolTimedOut | sqlx::Error::PoolClosed | sqlx::Error::Io(_) | sqlx::Error::Tls(_) => true,
+        // admin_shutdown, cannot_connect_now, too_many_connections, query_canceled (statement_timeout)
+        sqlx::Error::Database(db_err) => {
+            matches!(db_err.code().as_deref(), Some("57P01" | "57P03" | "53300" | "57014"))
+        }
+        _ => false,
+    }
+}
+
+// Failures a plain retry of the same reads can get past: serialization failures,
+// deadlocks, and connections reset under the query (e.g. during a failover)
+pub fn is_retryable(e: &sqlx::Error) -> bool {
+    match e {
+        sqlx::Error::Io(_) => true,
+        // serialization_failure, deadlock_detected, admin_shutdown
+        sqlx::Error::Database(db_err) => {
+            matches!(db_err.code().as_deref(), Some("40001" | "40P01" | "57P01"))
+        }
+        _ => false,
+    }
+}
+
+// Marks responses of requests that failed on a retryable database error, see retry.rs
+#[derive(Debug, Clone, Copy)]
+pub struct RetryableDbError;
+
+// Marks responses of requests that failed on an unavailable database, see circuit_breaker.rs
+#[derive(Debug, Clone, Copy)]
+pub struct DbUnavailable;
+
+impl From<sqlx::Error> for AppError {
+    fn from(e: sqlx::Error) -> Self {
+        if matches!(e, sqlx::Error::PoolTimedOut) {
+            pool_metrics::record_timeout();
+        }
+        if is_transient(&e) {
+            tracing::warn!("Database unavailable: {:?}", e);
+            AppError::ServiceUnavailable(e)
+        } else {
+            AppError::Database(e)
+        }
+    }
 }
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        let (status, error_message) = match self {
+        let code = self.code();
+        let retryable = matches!(
+            &self,
+            AppError::Database(e) | AppError::ServiceUnavailable(e) if is_retryable(e)
+        );
+        let db_unavailable = matches!(self, AppError::ServiceUnavailable(_));
+        let (status, error_message, fields) = match self {
             AppError::Database(ref e) => {
-                if let sqlx::Error::Database(db_err) = e {
-                    if db_err.code().as_deref() == Some("57P01") {
-                        tracing::debug!("Connection terminated, will retry: {:?}", e);
-                    } else {
-                        tracing::error!("Database error: {:?}", e);
-                    }
-                } else {
-                    tracing::error!("Database error: {:?}", e);
-                }
-                (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error")
+                tracing::error!("Database error: {:?}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error".to_string(), None)
             }
-            AppError::Unauthorized(ref message) => (StatusCode::UNAUTHORIZED, message.as_str()),
-            AppError::Forbidden(ref message) => (StatusCode::FORBIDDEN, message.as_str()),
-            AppError::NotFound(ref message) => (StatusCode::NOT_FOUND, message.as_str()),
-            AppError::BadRequest(ref message) => (StatusCode::BAD_REQUEST, message.as_str()),
-            AppError::Conflict(ref message) => (StatusCode::CONFLICT, message.as_str()),
-            AppError::InternalServerError(ref message) => {
+            AppError::Unauthorized(message) => (StatusCode::UNAUTHORIZED, message, None),
+            AppError::Forbidden(message) => (StatusCode::FORBIDDEN, message, None),
+            AppError::NotFound(message) => (StatusCode::NOT_FOUND, message, None),
+            AppError::BadRequest(message) => (StatusCode::BAD_REQUEST, message, None),
+            AppError::Conflict(message) => (StatusCode::CONFLICT, message, None),
+            // Field errors are listed next to the usual detail message
+            AppError::Validation(errors) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                code.message().to_string(),
+                Some(errors),
+            ),
+            AppError::UnprocessableEntity(message) => (StatusCode::UNPROCESSABLE_ENTITY, message, None),
+            AppError::PayloadTooLarge(message) => (StatusCode::PAYLOAD_TOO_LARGE, message, None),
+            AppError::TooManyRequests(message) => (StatusCode::TOO_MANY_REQUESTS, message, None),
+            AppError::InternalServerError(message) => {
                 tracing::error!("Internal server error: {}", message);
-                (StatusCode::INTERNAL_SERVER_ERROR, message.as_str())
+                (StatusCode::INTERNAL_SERVER_ERROR, message, None)
+            }
+            AppError::ServiceUnavailable(_) => (StatusCode::SERVICE_UNAVAILABLE, code.message().to_string(), None),
+            AppError::Coded(code, message) => {
+                if code.status().is_server_error() {
+                    tracing::error!("Internal server error: {}", message);
+                }
+                (code.status(), message, None)
             }
         };
 
-        let body = Json(json!({
-            "detail": error_message,
-        }));
+        let request_id = current_request_id();
+        let body = Json(ErrorBody {
+            detail: &error_message,
+            code,
+            fields,
+            request_id: request_id.as_ref().and_then(|id| id.to_str().ok()),
+        });
 
-        (status, body).into_response()
+        let mut response = (status, body).into_response();
+        if status == StatusCode::SERVICE_UNAVAILABLE {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(RETRY_AFTER_SECS));
+        }
+        if retryable {
+            response.extensions_mut().insert(RetryableDbError);
+        }
+        if db_unavailable {
+            response.extensions_mut().insert(DbUnavailable);
+        }
+        response
     }
 }
@@ -0,0 +1,217 @@
+use axum::{
+    extract::{
+        multipart::MultipartRejection,
+        path::ErrorKind,
+        rejection::{PathRejection, QueryRejection},
+        FromRequest, FromRequestParts, Request,
+    },
+    http::{header, request::Parts, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
+use uuid::Uuid;
+
+#[cfg(not(feature = "sonic-json"))]
+use axum::extract::rejection::JsonRejection;
+
+use crate::{
+    casing::to_json_body,
+    error::{AppError, ErrorCode},
+};
+
+// Extractors wrapping axum's, so a malformed request gets the usual JSON error body
+// (with a stable code) instead of axum's plain text rejection.
+
+// Request and response body. Responses are serialized like axum::Json, with their keys
+// rewritten to snake_case when JSON_CASING=snake.
+// Builds with the `sonic-json` feature parse and serialize with sonic-rs (SIMD) instead of serde_json,
+// builds with `pooled-buffers` serialize into pooled buffers and set Content-Length themselves.
+pub struct Json<T>(pub T);
+
+#[cfg(not(feature = "sonic-json"))]
+impl<T, S> FromRequest<S> for Json<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        match axum::Json::<T>::from_request(req, state).await {
+            Ok(axum::Json(value)) => Ok(Self(value)),
+            Err(JsonRejection::MissingJsonContentType(e)) => {
+                Err(AppError::Coded(ErrorCode::UnsupportedMediaType, e.body_text()))
+            }
+            Err(JsonRejection::JsonSyntaxError(e)) => Err(AppError::Coded(ErrorCode::InvalidJson, e.body_text())),
+            Err(JsonRejection::JsonDataError(e)) => Err(AppError::Coded(ErrorCode::InvalidBody, e.body_text())),
+            Err(e) => Err(status_error(e.status(), e.body_text())),
+        }
+    }
+}
+
+// Same checks and errors as axum::Json, with the body parsed by sonic-rs
+#[cfg(feature = "sonic-json")]
+impl<T, S> FromRequest<S> for Json<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        // application/json or application/*+json, parameters (charset) ignored
+        let is_json = req
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(';').next())
+            .map(|essence| essence.trim().to_ascii_lowercase())
+            .is_some_and(|essence| {
+                essence == "application/json"
+                    || (essence.starts_with("application/") && essence.ends_with("+json"))
+            });
+        if !is_json {
+            return Err(AppError::Coded(
+                ErrorCode::UnsupportedMediaType,
+                "Expected request with `Content-Type: application/json`".to_string(),
+            ));
+        }
+
+        let body = axum::body::Bytes::from_request(req, state)
+            .await
+            .map_err(|e| status_error(e.status(), e.body_text()))?;
+        sonic_rs::from_slice(&body).map(Self).map_err(|e| {
+            // sonic-rs appends an excerpt of the input on the following lines
+            let message = e.to_string();
+            let message = message.lines().next().unwrap_or_default();
+            if e.is_syntax() || e.is_eof() {
+                AppError::Coded(
+                    ErrorCode::InvalidJson,
+                    format!("Failed to parse the request body as JSON: {}", message),
+                )
+            } else {
+                AppError::Coded(
+                    ErrorCode::InvalidBody,
+                    format!("Failed to deserialize the JSON body into the target type: {}", message),
+                )
+            }
+        })
+    }
+}
+
+impl<T: Serialize> IntoResponse for Json<T> {
+    fn into_response(self) -> Response {
+        match to_json_body(&self.0) {
+            #[cfg(not(feature = "pooled-buffers"))]
+            Ok(body) => (
+                [(header::CONTENT_TYPE, HeaderValue::from_static("application/json"))],
+                body,
+            )
+                .into_response(),
+            #[cfg(feature = "pooled-buffers")]
+            Ok(body) => (
+                [
+                    (header::CONTENT_TYPE, HeaderValue::from_static("application/json")),
+                    (header::CONTENT_LENGTH, HeaderValue::from(body.len())),
+                ],
+                body,
+            )
+                .into_response(),
+            Err(e) => e.into_response(),
+        }
+    }
+}
+
+pub struct Query<T>(pub T);
+
+impl<T, S> FromRequestParts<S> for Query<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        match axum::extract::Query::<T>::from_request_parts(parts, state).await {
+            Ok(axum::extract::Query(value)) => Ok(Self(value)),
+            Err(QueryRejection::FailedToDeserializeQueryString(e)) => {
+                Err(AppError::Coded(ErrorCode::InvalidQuery, e.body_text()))
+            }
+            Err(e) => Err(status_error(e.status(), e.body_text())),
+        }
+    }
+}
+
+// Errors reading the fields themselves are mapped by the handler
+pub struct Multipart(pub axum::extract::Multipart);
+
+impl<S> FromRequest<S> for Multipart
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        match axum::extract::Multipart::from_request(req, state).await {
+            Ok(multipart) => Ok(Self(multipart)),
+            Err(MultipartRejection::InvalidBoundary(e)) => {
+                Err(AppError::Coded(ErrorCode::UnsupportedMediaType, e.body_text()))
+            }
+            Err(e) => Err(status_error(e.status(), e.body_text())),
+        }
+    }
+}
+
+// Rejections without a dedicated code keep axum's status
+fn status_error(status: StatusCode, message: String) -> AppError {
+    match status {
+        StatusCode::PAYLOAD_TOO_LARGE => AppError::PayloadTooLarge(message),
+        status if status.is_server_error() => AppError::InternalServerError(message),
+        _ => AppError::BadRequest(message),
+    }
+}
+
+// IDs are parsed by the types below, `Path(PostId(post_id)): Path<PostId>`
+pub struct Path<T>(pub T);
+
+impl<T, S> FromRequestParts<S> for Path<T>
+where
+    T: DeserializeOwned + Send,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        match axum::extract::Path::<T>::from_request_parts(parts, state).await {
+            Ok(axum::extract::Path(value)) => Ok(Self(value)),
+            Err(PathRejection::FailedToDeserializePathParams(e)) => match e.into_kind() {
+                // Raised by the ID types, already worded for the client
+                ErrorKind::Message(message) => Err(AppError::Coded(ErrorCode::InvalidId, message)),
+                kind => Err(AppError::BadRequest(kind.to_string())),
+            },
+            Err(e) => Err(status_error(e.status(), e.body_text())),
+        }
+    }
+}
+
+macro_rules! uuid_path_param {
+    ($name:ident, $message:literal) => {
+        #[derive(Debug, Clone, Copy)]
+        pub struct $name(pub Uuid);
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let value = String::deserialize(deserializer)?;
+                Uuid::parse_str(&value)
+                    .map(Self)
+                    .map_err(|_| serde::de::Error::custom($message))
+            }
+        }
+    };
+}
+
+uuid_path_param!(UserId, "Invalid user ID");
+uuid_path_param!(PostId, "Invalid post ID");
+uuid_path_param!(CommentId, "Invalid comment ID");
+uuid_path_param!(ConversationId, "Invalid conversation ID");
+uuid_path_param!(NotificationId, "Invalid notification ID");
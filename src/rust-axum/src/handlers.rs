@@ -1,16 +1,38 @@
 use axum::{
-    extract::{Path, Query, State, Extension},
-    http::StatusCode,
-    Json,
+    body::Bytes,
+    extract::{multipart::MultipartError, RawQuery, State, Extension},
+    http::{header, HeaderMap, HeaderName, Method, StatusCode, Uri},
+    response::{IntoResponse, Response},
 };
+use chrono::{DateTime, Utc};
 use serde::Deserialize;
+use sqlx::{postgres::PgArguments, Arguments, Postgres, QueryBuilder};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+use tokio::{sync::Semaphore, task::JoinSet};
 use uuid::Uuid;
 
 use crate::{
-    auth::{create_token, hash_password, verify_password, Claims},
-    error::AppError,
+    auth::{auth_cookies, create_impersonation_token, create_token, hash_password, verify_password, Claims, OptionalClaims, TokenProfile},
+    content::{extract_hashtags, extract_mentions, normalize_hashtag},
+    debug_stats::DebugStats,
+    error::{AppError, ErrorCode},
+    extract::{CommentId, ConversationId, Json, Multipart, NotificationId, Path, PostId, Query, UserId},
+    health::{readiness, Liveness, Readiness},
+    load_shed::{LoadShedding, LoadSheddingStats},
+    me_cache::{MeCache, MeCacheStats},
     models::*,
+    pipeline::join_queries,
+    pool_metrics::{PoolStats, Pools},
+    posts_json::{posts_json_select, POSTS_JSON_BY_DB, POSTS_JSON_END},
+    retry::{retry_stats, RetryStats},
+    runtime::Shard,
+    seed::{seed, SEED_PASSWORD},
     sql::*,
+    streaming::{stream_json_array, STREAM_MIN_PAGE_SIZE},
+    validation::{FieldErrors, PagedQuery, Paginated, Validate, ValidatedJson},
     AppState,
 };
 
@@ -26,6 +48,332 @@ fn default_limit() -> i64 {
     20
 }
 
+macro_rules! paginated {
+    ($($name:ident),*) => {
+        $(impl Paginated for $name {
+            fn limit_mut(&mut self) -> &mut i64 {
+                &mut self.limit
+            }
+
+            fn offset(&self) -> i64 {
+                self.offset
+            }
+        })*
+    };
+    (keyset: $($name:ident),*) => {
+        $(impl Paginated for $name {
+            fn limit_mut(&mut self) -> &mut i64 {
+                &mut self.limit
+            }
+
+            fn offset(&self) -> i64 {
+                self.offset
+            }
+
+            fn cursor_mut(&mut self) -> Option<&mut Option<String>> {
+                Some(&mut self.cursor)
+            }
+        })*
+    };
+}
+
+paginated!(PaginationQuery, SearchQuery);
+paginated!(keyset: ListQuery, PostListQuery, CommentListQuery);
+
+// Offset pagination for the main listings, `envelope=true` wraps results in a Page.
+// Passing `cursor` (empty for the first page) switches to keyset pagination instead.
+#[derive(Debug, Deserialize)]
+pub struct ListQuery {
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+    #[serde(default)]
+    pub offset: i64,
+    pub cursor: Option<String>,
+    #[serde(default)]
+    pub envelope: bool,
+}
+
+// GET /posts listing options, sorting only applies to offset pagination
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostListQuery {
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+    #[serde(default)]
+    pub offset: i64,
+    pub cursor: Option<String>,
+    #[serde(default)]
+    pub envelope: bool,
+    pub sort: Option<PostSort>,
+    pub order: Option<SortOrder>,
+    pub author_id: Option<String>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+}
+
+// Parsed PostListQuery filters, every value is bound as a parameter
+#[derive(Debug, Clone, Copy)]
+struct PostFilters {
+    // Drafts are only listed for their author
+    viewer: Option<Uuid>,
+    author_id: Option<Uuid>,
+    created_after: Option<DateTime<Utc>>,
+    created_before: Option<DateTime<Utc>>,
+}
+
+impl PostFilters {
+    fn from_query(query: &PostListQuery, viewer: Option<Uuid>) -> Result<Self, AppError> {
+        let author_id = query
+            .author_id
+            .as_deref()
+            .map(Uuid::parse_str)
+            .transpose()
+            .map_err(|_| AppError::Coded(ErrorCode::InvalidId, "Invalid author ID".to_string()))?;
+        Ok(Self {
+            viewer,
+            author_id,
+            created_after: query.created_after,
+            created_before: query.created_before,
+        })
+    }
+
+    // Appends the WHERE clause to a query selecting from `posts p`, soft-deleted posts excluded
+    fn push_where(&self, builder: &mut QueryBuilder<'_, Postgres>) {
+        builder.push("WHERE p.deleted_at IS NULL");
+        match self.viewer {
+            Some(viewer) => {
+                builder
+                    .push(" AND (p.status = 'published' OR p.author_id = ")
+                    .push_bind(viewer)
+                    .push(")");
+            }
+            None => {
+                builder.push(" AND p.status = 'published'");
+            }
+        }
+        if let Some(author_id) = self.author_id {
+            builder.push(" AND p.author_id = ").push_bind(author_id);
+        }
+        if let Some(created_after) = self.created_after {
+            builder.push(" AND p.created_at > ").push_bind(created_after);
+        }
+        if let Some(created_before) = self.created_before {
+            builder.push(" AND p.created_at < ").push_bind(created_before);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PostSort {
+    #[default]
+    CreatedAt,
+    LikeCount,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+// Allow-listed ORDER BY clauses, id breaks ties so offset pages are stable
+fn post_order_by(sort: PostSort, order: SortOrder) -> &'static str {
+    match (sort, order) {
+        (PostSort::CreatedAt, SortOrder::Desc) => "p.created_at DESC, p.id DESC",
+        (PostSort::CreatedAt, SortOrder::Asc) => "p.created_at ASC, p.id ASC",
+        (PostSort::LikeCount, SortOrder::Desc) => "p.likes_count DESC, p.id DESC",
+        (PostSort::LikeCount, SortOrder::Asc) => "p.likes_count ASC, p.id ASC",
+    }
+}
+
+// The same, on the columns of a listing aggregated by Postgres (POSTS_JSON_MODE=postgres)
+fn post_json_order_by(sort: PostSort, order: SortOrder) -> &'static str {
+    match (sort, order) {
+        (PostSort::CreatedAt, SortOrder::Desc) => "l.created_at DESC, l.id DESC",
+        (PostSort::CreatedAt, SortOrder::Asc) => "l.created_at ASC, l.id ASC",
+        (PostSort::LikeCount, SortOrder::Desc) => "l.like_count DESC, l.id DESC",
+        (PostSort::LikeCount, SortOrder::Asc) => "l.like_count ASC, l.id ASC",
+    }
+}
+
+// GET /posts/{post_id}/comments options, oldest first unless `order=desc`
+#[derive(Debug, Deserialize)]
+pub struct CommentListQuery {
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+    #[serde(default)]
+    pub offset: i64,
+    pub cursor: Option<String>,
+    pub order: Option<SortOrder>,
+}
+
+// Largest comment page, whatever MAX_PAGE_SIZE allows
+const MAX_COMMENTS_PAGE_SIZE: i64 = 100;
+
+#[derive(Debug, Deserialize)]
+pub struct CursorQuery {
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+    pub cursor: Option<String>,
+}
+
+impl Paginated for CursorQuery {
+    fn limit_mut(&mut self) -> &mut i64 {
+        &mut self.limit
+    }
+}
+
+// Keyset position of the last item returned, encoded as "<created_at micros>_<id>"
+fn encode_cursor(created_at: DateTime<Utc>, id: Uuid) -> String {
+    format!("{}_{}", created_at.timestamp_micros(), id)
+}
+
+fn decode_cursor(cursor: &str) -> Result<(DateTime<Utc>, Uuid), AppError> {
+    let invalid = || AppError::from(ErrorCode::InvalidCursor);
+    let (micros, id) = cursor.split_once('_').ok_or_else(invalid)?;
+    let created_at = micros
+        .parse::<i64>()
+        .ok()
+        .and_then(DateTime::from_timestamp_micros)
+        .ok_or_else(invalid)?;
+    let id = Uuid::parse_str(id).map_err(|_| invalid())?;
+    Ok((created_at, id))
+}
+
+// Requester id for viewer-specific fields, None when anonymous
+fn viewer_id(viewer: &Option<Claims>) -> Option<Uuid> {
+    viewer.as_ref().and_then(|claims| Uuid::parse_str(&claims.sub).ok())
+}
+
+// Version the client expects to update, from If-Match (`3`, `"3"`, `*` for any, or the
+// `W/"3-…"` ETag of GET /posts/{post_id}) or the body
+fn expected_version(headers: &HeaderMap, body_version: Option<i32>) -> Result<Option<i32>, AppError> {
+    let Some(value) = headers.get(header::IF_MATCH) else {
+        return Ok(body_version);
+    };
+    let invalid = || AppError::BadRequest("If-Match must be a version number or ETag".to_string());
+    let value = value.to_str().map_err(|_| invalid())?.trim();
+    if value == "*" {
+        return Ok(None);
+    }
+    let tag = value.trim_start_matches("W/").trim_matches('"');
+    let version = tag.split_once('-').map_or(tag, |(version, _)| version);
+    version.parse().map(Some).map_err(|_| invalid())
+}
+
+// An update matching no row hit a stale version if the row is still there
+async fn missed_update_error(
+    app_state: &AppState,
+    exists_query: &'static str,
+    id: Uuid,
+    expected_version: Option<i32>,
+    not_found: ErrorCode,
+) -> AppError {
+    if expected_version.is_none() {
+        return not_found.into();
+    }
+    match sqlx::query_scalar::<_, Uuid>(exists_query)
+        .bind(id)
+        .fetch_optional(&app_state.db)
+        .await
+    {
+        Ok(Some(_)) => ErrorCode::VersionMismatch.into(),
+        Ok(None) => not_found.into(),
+        Err(e) => e.into(),
+    }
+}
+
+// An empty cursor starts keyset pagination from the first page
+fn decode_optional_cursor(cursor: &str) -> Result<Option<(DateTime<Utc>, Uuid)>, AppError> {
+    if cursor.is_empty() {
+        Ok(None)
+    } else {
+        decode_cursor(cursor).map(Some)
+    }
+}
+
+// Drop the extra row fetched past `limit`, the cursor points at the last row kept
+fn next_cursor<T>(
+    rows: &mut Vec<T>,
+    limit: i64,
+    key: impl Fn(&T) -> (DateTime<Utc>, Uuid),
+) -> Option<String> {
+    if rows.len() as i64 > limit {
+        rows.truncate(limit.max(0) as usize);
+        rows.last().map(|row| {
+            let (created_at, id) = key(row);
+            encode_cursor(created_at, id)
+        })
+    } else {
+        None
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrendingQuery {
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+    #[serde(default = "default_trending_window_hours")]
+    pub window_hours: i32,
+}
+
+impl Paginated for TrendingQuery {
+    fn limit_mut(&mut self) -> &mut i64 {
+        &mut self.limit
+    }
+}
+
+fn default_trending_window_hours() -> i32 {
+    24
+}
+
+// Longest trending window accepted (30 days)
+const MAX_TRENDING_WINDOW_HOURS: i32 = 720;
+// Trending results are cheap to serve stale, let clients and proxies cache them briefly
+const TRENDING_CACHE_CONTROL: &str = "public, max-age=60";
+const TRENDING_PRIVATE_CACHE_CONTROL: &str = "private, max-age=60";
+
+#[derive(Debug, Deserialize)]
+pub struct SearchQuery {
+    pub q: String,
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+    #[serde(default)]
+    pub offset: i64,
+}
+
+// Most posts accepted by a single POST /posts/batch
+const MAX_BATCH_POSTS: usize = 100;
+
+// Most ids accepted by a single POST /posts/lookup
+const MAX_LOOKUP_POSTS: usize = 100;
+
+// Largest group conversation, including its creator
+const MAX_CONVERSATION_PARTICIPANTS: usize = 50;
+
+#[derive(Debug, Deserialize)]
+pub struct StatsQuery {
+    #[serde(default = "default_stats_days")]
+    pub days: i32,
+}
+
+fn default_stats_days() -> i32 {
+    30
+}
+
+// Longest posts-per-day history returned by /admin/stats
+const MAX_STATS_DAYS: i32 = 365;
+
+// Most users accepted by a single POST /admin/users/import
+const MAX_IMPORT_USERS: usize = 1000;
+
+// Users inserted per multi-row INSERT during an import
+const IMPORT_INSERT_BATCH_SIZE: usize = 100;
+
 ////////////////////////////////////////////////////////////////////////////////
 // Auth endpoints
 ////////////////////////////////////////////////////////////////////////////////
@@ -33,7 +381,7 @@ fn default_limit() -> i64 {
 pub async fn login(
     State(app_state): State<AppState>,
     Json(credentials): Json<LoginCredentials>,
-) -> Result<Json<LoginResponse>, AppError> {
+) -> Result<(HeaderMap, Json<LoginResponse>), AppError> {
     let login_row: Option<LoginRow> = sqlx::query_as(SQL_LOGIN)
         .bind(&credentials.email)
         .fetch_optional(&app_state.db)
@@ -43,14 +391,27 @@ pub async fn login(
         let is_valid = verify_password(&credentials.password, &row.password_hash).await?;
 
         if is_valid {
-            let token = create_token(&row.id, row.is_admin, &app_state.auth_config)?;
-            return Ok(Json(LoginResponse {
-                access_token: token,
-            }));
+            let token = create_token(
+                &row.id,
+                row.is_admin,
+                TokenProfile::from(&row),
+                &app_state.auth_config,
+            )?;
+            let headers = if app_state.auth_config.cookie_auth {
+                auth_cookies(&token, &app_state.auth_config)?
+            } else {
+                HeaderMap::new()
+            };
+            return Ok((
+                headers,
+                Json(LoginResponse {
+                    access_token: token,
+                }),
+            ));
         }
     }
 
-    Err(AppError::Unauthorized("Invalid credentials".to_string()))
+    Err(AppError::from(ErrorCode::InvalidCredentials))
 }
 
 pub async fn me(
@@ -58,19 +419,73 @@ pub async fn me(
     Extension(claims): Extension<Claims>,
 ) -> Result<Json<User>, AppError> {
     let user_uuid = Uuid::parse_str(&claims.sub)
-        .map_err(|_| AppError::BadRequest("Invalid user ID".to_string()))?;
+        .map_err(|_| AppError::Coded(ErrorCode::InvalidId, "Invalid user ID".to_string()))?;
+
+    // Serve from the token unless explicitly asked to hit the database, or the user has a bio
+    // (only its hash is in the token)
+    if !app_state.auth_config.me_from_db {
+        if let Some(profile) = claims.profile.filter(|profile| profile.bio_hash.is_none()) {
+            return Ok(Json(User {
+                id: user_uuid,
+                username: profile.username,
+                email: profile.email,
+                bio: None,
+                avatar_url: profile.avatar_url,
+                created_at: profile.created_at,
+                version: profile.version,
+            }));
+        }
+    }
+
+    if let Some(user) = app_state.me_cache.as_ref().and_then(|cache| cache.get(user_uuid)) {
+        return Ok(Json(user));
+    }
 
     let user_row: Option<UserRow> = sqlx::query_as(SQL_ME)
         .bind(user_uuid)
-        .fetch_optional(&app_state.db)
+        .fetch_optional(app_state.read_db())
         .await?;
 
     match user_row {
-        Some(row) => Ok(Json(User::from(row))),
+        Some(row) => {
+            let user = User::from(row);
+            if let Some(me_cache) = &app_state.me_cache {
+                me_cache.put(&user);
+            }
+            Ok(Json(user))
+        }
         None => Err(AppError::Unauthorized("User not found".to_string())),
     }
 }
 
+// The user's profile changed or was deleted, /auth/me must not serve the cached one
+async fn invalidate_me(app_state: &AppState, user_id: Uuid) {
+    if let Some(me_cache) = &app_state.me_cache {
+        me_cache.invalidate(user_id);
+    }
+    app_state.cache.invalidate_user(user_id).await;
+}
+
+pub async fn rotate_secret(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(rotation): Json<RotateSecret>,
+) -> Result<StatusCode, AppError> {
+    if !claims.is_admin {
+        return Err(AppError::from(ErrorCode::AdminRequired));
+    }
+
+    let secret = rotation.secret.trim();
+    if secret.is_empty() {
+        return Err(AppError::BadRequest("Secret must not be empty".to_string()));
+    }
+
+    app_state.auth_config.rotate_secret(secret.to_string());
+    tracing::info!("JWT signing secret rotated by {}", claims.sub);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 // Users endpoints (Admin only)
 ////////////////////////////////////////////////////////////////////////////////
@@ -78,62 +493,119 @@ pub async fn me(
 pub async fn create_user(
     State(app_state): State<AppState>,
     Extension(claims): Extension<Claims>,
-    Json(user_data): Json<CreateUser>,
+    ValidatedJson(user_data): ValidatedJson<CreateUser>,
 ) -> Result<(StatusCode, Json<User>), AppError> {
     if !claims.is_admin {
-        return Err(AppError::Forbidden("Admin access required".to_string()));
+        return Err(AppError::from(ErrorCode::AdminRequired));
     }
 
-    let password_hash = hash_password(&user_data.password).await?;
+    let password_hash = hash_password(&user_data.password, app_state.auth_config.bcrypt_cost).await?;
 
-    let created_id: Uuid = sqlx::query_scalar(SQL_CREATE_USER)
+    // Single round trip, the inserted row is returned as is
+    let result: Result<UserRow, sqlx::Error> = sqlx::query_as(SQL_CREATE_USER)
         .bind(&user_data.username)
         .bind(&user_data.email)
         .bind(&password_hash)
         .bind(None::<String>) // bio is None for new users
         .fetch_one(&app_state.db)
-        .await
-        .map_err(|_| AppError::BadRequest("Failed to create user".to_string()))?;
+        .await;
+
+    let user_row = match result {
+        Ok(row) => row,
+        Err(e) => {
+            if let Some(db_err) = e.as_database_error() {
+                // unique_violation, told apart by the constraint that was hit
+                if db_err.code().as_deref() == Some("23505") {
+                    return Err(match db_err.constraint() {
+                        Some("users_email_key") => AppError::from(ErrorCode::EmailTaken),
+                        _ => AppError::from(ErrorCode::UsernameTaken),
+                    });
+                }
+            }
+            return Err(e.into());
+        }
+    };
+
+    Ok((StatusCode::CREATED, Json(User::from(user_row))))
+}
+
+pub async fn count_users(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<Count>, AppError> {
+    if !claims.is_admin {
+        return Err(AppError::from(ErrorCode::AdminRequired));
+    }
 
-    let user_row: UserRow = sqlx::query_as(SQL_GET_USER)
-        .bind(created_id)
+    let count: i64 = sqlx::query_scalar(SQL_COUNT_USERS)
         .fetch_one(&app_state.db)
         .await?;
 
-    Ok((StatusCode::CREATED, Json(User::from(user_row))))
+    Ok(Json(Count { count }))
 }
 
 pub async fn list_users(
     State(app_state): State<AppState>,
     Extension(claims): Extension<Claims>,
-    Query(pagination): Query<PaginationQuery>,
-) -> Result<Json<Vec<User>>, AppError> {
+    PagedQuery(query): PagedQuery<ListQuery>,
+) -> Result<Json<Listing<User>>, AppError> {
     if !claims.is_admin {
-        return Err(AppError::Forbidden("Admin access required".to_string()));
+        return Err(AppError::from(ErrorCode::AdminRequired));
     }
 
-    let user_rows: Vec<UserRow> = sqlx::query_as(SQL_LIST_USERS)
-        .bind(pagination.limit)
-        .bind(pagination.offset)
-        .fetch_all(&app_state.db)
-        .await?;
+    if let Some(cursor) = &query.cursor {
+        let cursor = decode_optional_cursor(cursor)?;
+        // Fetch one extra row to know whether there is a next page
+        let mut user_rows: Vec<UserRow> = sqlx::query_as(SQL_LIST_USERS_KEYSET)
+            .bind(cursor.map(|(created_at, _)| created_at))
+            .bind(cursor.map(|(_, id)| id))
+            .bind(query.limit + 1)
+            .fetch_all(&app_state.db)
+            .await?;
+        let next_cursor = next_cursor(&mut user_rows, query.limit, |row| (row.created_at, row.id));
+        return Ok(Json(Listing::Cursor(CursorPage {
+            items: user_rows.into_iter().map(User::from).collect(),
+            next_cursor,
+        })));
+    }
 
-    let users: Vec<User> = user_rows.into_iter().map(User::from).collect();
-    Ok(Json(users))
+    let list = sqlx::query_as::<_, UserRow>(SQL_LIST_USERS)
+        .bind(query.limit)
+        .bind(query.offset)
+        .fetch_all(&app_state.db);
+
+    if !query.envelope {
+        let user_rows = list.await?;
+        return Ok(Json(Listing::Items(user_rows.into_iter().map(User::from).collect())));
+    }
+
+    let (user_rows, total) = join_queries(
+        &app_state.db,
+        list,
+        sqlx::query_scalar::<_, i64>(SQL_COUNT_USERS).fetch_one(&app_state.db),
+    )
+    .await?;
+    let next_cursor = (query.offset + (user_rows.len() as i64) < total)
+        .then(|| user_rows.last().map(|row| encode_cursor(row.created_at, row.id)))
+        .flatten();
+    Ok(Json(Listing::Page(Page {
+        data: user_rows.into_iter().map(User::from).collect(),
+        total,
+        limit: query.limit,
+        offset: query.offset,
+        next_cursor,
+    })))
 }
 
 pub async fn get_user(
     State(app_state): State<AppState>,
     Extension(claims): Extension<Claims>,
-    Path(target_user_id): Path<String>,
+    Path(UserId(target_uuid)): Path<UserId>,
 ) -> Result<Json<User>, AppError> {
     if !claims.is_admin {
-        return Err(AppError::Forbidden("Admin access required".to_string()));
+        return Err(AppError::from(ErrorCode::AdminRequired));
     }
 
-    let target_uuid = Uuid::parse_str(&target_user_id)
-        .map_err(|_| AppError::BadRequest("Invalid user ID".to_string()))?;
-
     let user_row: Option<UserRow> = sqlx::query_as(SQL_GET_USER)
         .bind(target_uuid)
         .fetch_optional(&app_state.db)
@@ -141,250 +613,873 @@ pub async fn get_user(
 
     match user_row {
         Some(row) => Ok(Json(User::from(row))),
-        None => Err(AppError::NotFound("User not found".to_string())),
+        None => Err(AppError::from(ErrorCode::UserNotFound)),
     }
 }
 
 pub async fn update_user(
     State(app_state): State<AppState>,
     Extension(claims): Extension<Claims>,
-    Path(target_user_id): Path<String>,
-    Json(update_data): Json<UpdateUser>,
+    Path(UserId(target_uuid)): Path<UserId>,
+    headers: HeaderMap,
+    ValidatedJson(update_data): ValidatedJson<UpdateUser>,
 ) -> Result<Json<User>, AppError> {
     if !claims.is_admin {
-        return Err(AppError::Forbidden("Admin access required".to_string()));
+        return Err(AppError::from(ErrorCode::AdminRequired));
     }
 
-    let target_uuid = Uuid::parse_str(&target_user_id)
-        .map_err(|_| AppError::BadRequest("Invalid user ID".to_string()))?;
+    let version = expected_version(&headers, update_data.version)?;
 
     let user_row: Option<UserRow> = sqlx::query_as(SQL_UPDATE_USER)
         .bind(target_uuid)
         .bind(update_data.bio.as_deref())
+        .bind(version)
         .fetch_optional(&app_state.db)
         .await?;
 
     match user_row {
-        Some(row) => Ok(Json(User::from(row))),
-        None => Err(AppError::NotFound("User not found".to_string())),
+        Some(row) => {
+            invalidate_me(&app_state, target_uuid).await;
+            Ok(Json(User::from(row)))
+        }
+        None => Err(
+            missed_update_error(
+                &app_state,
+                SQL_GET_USER,
+                target_uuid,
+                version,
+                ErrorCode::UserNotFound,
+            )
+            .await,
+        ),
+    }
+}
+
+pub async fn update_profile(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    headers: HeaderMap,
+    ValidatedJson(update_data): ValidatedJson<UpdateProfile>,
+) -> Result<Json<User>, AppError> {
+    let user_uuid = Uuid::parse_str(&claims.sub)
+        .map_err(|_| AppError::Coded(ErrorCode::InvalidId, "Invalid user ID".to_string()))?;
+    let version = expected_version(&headers, update_data.version)?;
+
+    let result: Result<Option<UserRow>, sqlx::Error> = sqlx::query_as(SQL_UPDATE_PROFILE)
+        .bind(user_uuid)
+        .bind(update_data.username.as_deref())
+        .bind(update_data.bio.as_deref())
+        .bind(version)
+        .fetch_optional(&app_state.db)
+        .await;
+
+    match result {
+        Ok(Some(row)) => {
+            invalidate_me(&app_state, user_uuid).await;
+            Ok(Json(User::from(row)))
+        }
+        Ok(None) => Err(
+            missed_update_error(
+                &app_state,
+                SQL_GET_USER,
+                user_uuid,
+                version,
+                ErrorCode::UserNotFound,
+            )
+            .await,
+        ),
+        Err(e) => {
+            if let Some(db_err) = e.as_database_error() {
+                if db_err.code().as_deref() == Some("23505") {
+                    return Err(AppError::from(ErrorCode::UsernameTaken)); // unique_violation
+                }
+            }
+            Err(e.into())
+        }
     }
 }
 
 pub async fn delete_user(
     State(app_state): State<AppState>,
     Extension(claims): Extension<Claims>,
-    Path(target_user_id): Path<String>,
+    Path(UserId(target_uuid)): Path<UserId>,
 ) -> Result<StatusCode, AppError> {
     if !claims.is_admin {
-        return Err(AppError::Forbidden("Admin access required".to_string()));
+        return Err(AppError::from(ErrorCode::AdminRequired));
     }
 
-    let target_uuid = Uuid::parse_str(&target_user_id)
-        .map_err(|_| AppError::BadRequest("Invalid user ID".to_string()))?;
-
-    let result = sqlx::query(SQL_DELETE_USER)
+    // Soft delete: the user and their posts are hidden, the row (and its email/username) is kept
+    let deleted: Option<Uuid> = sqlx::query_scalar(SQL_DELETE_USER)
         .bind(target_uuid)
-        .execute(&app_state.db)
+        .fetch_optional(&app_state.db)
         .await?;
 
-    if result.rows_affected() == 0 {
-        return Err(AppError::NotFound("User not found".to_string()));
+    if deleted.is_none() {
+        return Err(AppError::from(ErrorCode::UserNotFound));
     }
+    invalidate_me(&app_state, target_uuid).await;
+    // Their tokens stop working on protected routes
+    app_state.auth_config.user_status.invalidate(target_uuid);
+    // Their posts drop out of the listings and the single post cache
+    app_state.cache.invalidate_user_posts(target_uuid).await;
 
     Ok(StatusCode::NO_CONTENT)
 }
 
-////////////////////////////////////////////////////////////////////////////////
-// Posts endpoints
-////////////////////////////////////////////////////////////////////////////////
+// Avatar formats accepted by PUT /users/me/avatar, mapped to the stored file extension
+fn avatar_extension(content_type: &str) -> Option<&'static str> {
+    match content_type {
+        "image/png" => Some("png"),
+        "image/jpeg" => Some("jpg"),
+        "image/gif" => Some("gif"),
+        "image/webp" => Some("webp"),
+        _ => None,
+    }
+}
 
-pub async fn create_post(
+fn multipart_error(e: MultipartError) -> AppError {
+    if e.status() == StatusCode::PAYLOAD_TOO_LARGE {
+        AppError::PayloadTooLarge("Avatar is too large".to_string())
+    } else {
+        AppError::BadRequest(e.body_text())
+    }
+}
+
+pub async fn upload_avatar(
     State(app_state): State<AppState>,
     Extension(claims): Extension<Claims>,
-    Json(post_data): Json<PostCreate>,
-) -> Result<(StatusCode, Json<Post>), AppError> {
+    Multipart(mut multipart): Multipart,
+) -> Result<Json<User>, AppError> {
     let user_uuid = Uuid::parse_str(&claims.sub)
-        .map_err(|_| AppError::BadRequest("Invalid user ID".to_string()))?;
+        .map_err(|_| AppError::Coded(ErrorCode::InvalidId, "Invalid user ID".to_string()))?;
 
-    let post_row: PostCreateRow = sqlx::query_as(SQL_CREATE_POST)
-        .bind(user_uuid)
-        .bind(&post_data.content)
-        .fetch_one(&app_state.db)
-        .await
-        .map_err(|e| {
-            tracing::error!("Failed to create post: {:?}", e);
-            match e {
-                sqlx::Error::Database(db_err) => {
-                    AppError::BadRequest(format!("Database error: {}", db_err))
-                }
-                sqlx::Error::PoolTimedOut => {
-                    AppError::InternalServerError("Database connection timeout".to_string())
-                }
-                _ => AppError::BadRequest("Failed to create post".to_string())
-            }
+    let mut upload = None;
+    while let Some(field) = multipart.next_field().await.map_err(multipart_error)? {
+        if field.name() != Some("avatar") {
+            continue;
+        }
+        let content_type = field.content_type().unwrap_or_default().to_string();
+        let extension = avatar_extension(&content_type).ok_or_else(|| {
+            AppError::Coded(ErrorCode::InvalidAvatar, "Avatar must be a PNG, JPEG, GIF or WebP image".to_string())
         })?;
+        let data = field.bytes().await.map_err(multipart_error)?;
+        upload = Some((content_type, extension, data));
+        break;
+    }
 
-    let post = Post::from(post_row);
+    let (content_type, extension, data) =
+        upload.ok_or_else(|| AppError::Coded(ErrorCode::InvalidAvatar, "Missing avatar file".to_string()))?;
+    if data.is_empty() {
+        return Err(AppError::Coded(ErrorCode::InvalidAvatar, "Avatar file is empty".to_string()));
+    }
 
-    Ok((StatusCode::CREATED, Json(post)))
-}
+    // Fresh key per upload so cached copies of the previous avatar are never served
+    let key = format!("avatars/{}/{}.{}", user_uuid, Uuid::new_v4(), extension);
+    app_state.storage.put(&key, &content_type, data).await?;
+    let avatar_url = app_state.storage.url(&key);
 
-pub async fn list_posts(
-    State(app_state): State<AppState>,
-    Query(pagination): Query<PaginationQuery>,
-) -> Result<Json<Vec<Post>>, AppError> {
-    let post_rows: Vec<PostRow> = sqlx::query_as(SQL_LIST_POSTS)
-        .bind(pagination.limit)
-        .bind(pagination.offset)
-        .fetch_all(&app_state.db)
+    let user_row: Option<UserRow> = sqlx::query_as(SQL_UPDATE_USER_AVATAR)
+        .bind(user_uuid)
+        .bind(&avatar_url)
+        .fetch_optional(&app_state.db)
         .await?;
 
-    let posts: Vec<Post> = post_rows.into_iter().map(Post::from).collect();
-    Ok(Json(posts))
+    match user_row {
+        Some(row) => {
+            invalidate_me(&app_state, user_uuid).await;
+            Ok(Json(User::from(row)))
+        }
+        None => Err(AppError::from(ErrorCode::UserNotFound)),
+    }
 }
 
-pub async fn get_post(
+pub async fn search_users(
     State(app_state): State<AppState>,
-    Path(post_id): Path<String>,
-) -> Result<Json<Post>, AppError> {
-    let post_uuid = Uuid::parse_str(&post_id)
-        .map_err(|_| AppError::BadRequest("Invalid post ID".to_string()))?;
+    OptionalClaims(viewer): OptionalClaims,
+    PagedQuery(search): PagedQuery<SearchQuery>,
+) -> Result<Json<Vec<UserSearchResult>>, AppError> {
+    let is_admin = viewer.as_ref().is_some_and(|claims| claims.is_admin);
+    if !app_state.user_search_public && !is_admin {
+        return Err(match viewer {
+            Some(_) => AppError::from(ErrorCode::AdminRequired),
+            None => AppError::from(ErrorCode::MissingToken),
+        });
+    }
 
-    let post_row: Option<PostRow> = sqlx::query_as(SQL_GET_POST)
-        .bind(post_uuid)
-        .fetch_optional(&app_state.db)
+    let prefix = search.q.trim().to_lowercase();
+    if prefix.is_empty() {
+        return Err(AppError::BadRequest("Search query must not be empty".to_string()));
+    }
+    // Escape LIKE wildcards so the query is a plain prefix match
+    let pattern = format!(
+        "{}%",
+        prefix.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+    );
+
+    let user_rows: Vec<UserRow> = sqlx::query_as(SQL_SEARCH_USERS)
+        .bind(pattern)
+        .bind(search.limit)
+        .bind(search.offset)
+        .fetch_all(&app_state.db)
         .await?;
 
-    match post_row {
-        Some(row) => Ok(Json(Post::from(row))),
-        None => Err(AppError::NotFound("Post not found".to_string())),
-    }
+    let users = user_rows
+        .into_iter()
+        .map(|row| UserSearchResult::from_row(row, is_admin))
+        .collect();
+    Ok(Json(users))
 }
 
-pub async fn delete_post(
+pub async fn impersonate_user(
     State(app_state): State<AppState>,
     Extension(claims): Extension<Claims>,
-    Path(post_id): Path<String>,
-) -> Result<StatusCode, AppError> {
-    let user_uuid = Uuid::parse_str(&claims.sub)
-        .map_err(|_| AppError::BadRequest("Invalid user ID".to_string()))?;
-    let post_uuid = Uuid::parse_str(&post_id)
-        .map_err(|_| AppError::BadRequest("Invalid post ID".to_string()))?;
-
-    // Check if post exists and get author
-    let author_id: Option<Uuid> = sqlx::query_scalar(SQL_GET_POST_AUTHOR)
-        .bind(post_uuid)
+    Path(UserId(target_uuid)): Path<UserId>,
+) -> Result<Json<LoginResponse>, AppError> {
+    if !claims.is_admin {
+        return Err(AppError::from(ErrorCode::AdminRequired));
+    }
+    if claims.impersonated_by.is_some() {
+        return Err(AppError::Forbidden("Cannot impersonate while impersonating".to_string()));
+    }
+
+    let admin_uuid = Uuid::parse_str(&claims.sub)
+        .map_err(|_| AppError::Coded(ErrorCode::InvalidId, "Invalid user ID".to_string()))?;
+
+    let target_row: Option<ImpersonationRow> = sqlx::query_as(SQL_IMPERSONATE_USER)
+        .bind(target_uuid)
         .fetch_optional(&app_state.db)
         .await?;
 
-    let author_id = author_id.ok_or_else(|| AppError::NotFound("Post not found".to_string()))?;
+    let target_row = target_row.ok_or_else(|| AppError::from(ErrorCode::UserNotFound))?;
 
-    if author_id != user_uuid && !claims.is_admin {
-        return Err(AppError::Forbidden(
-            "You can only delete your own posts".to_string(),
-        ));
-    }
+    tracing::info!("Admin {} impersonating user {}", admin_uuid, target_row.id);
 
-    sqlx::query(SQL_DELETE_POST)
-        .bind(post_uuid)
-        .execute(&app_state.db)
-        .await?;
+    let token = create_impersonation_token(
+        &target_row.id,
+        target_row.is_admin,
+        TokenProfile::from(&target_row),
+        &admin_uuid,
+        &app_state.auth_config,
+    )?;
 
-    Ok(StatusCode::NO_CONTENT)
+    Ok(Json(LoginResponse {
+        access_token: token,
+    }))
 }
 
 ////////////////////////////////////////////////////////////////////////////////
-// Comments endpoints
+// Posts endpoints
 ////////////////////////////////////////////////////////////////////////////////
 
-pub async fn create_comment(
+// Scheduled posts must be due in the future, past publishAt values are rejected
+fn check_publish_at(post: &PostCreate) -> Result<(), AppError> {
+    match post.publish_at {
+        Some(publish_at) if publish_at <= Utc::now() => Err(AppError::BadRequest(
+            "publishAt must be in the future".to_string(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+async fn insert_post<'e, E>(executor: E, author_id: Uuid, post: &PostCreate) -> Result<PostCreateRow, AppError>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    sqlx::query_as(SQL_CREATE_POST)
+        .bind(author_id)
+        .bind(&post.content)
+        .bind(post.effective_status().as_str())
+        .bind(post.publish_at)
+        .fetch_one(executor)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to create post: {:?}", e);
+            match e {
+                sqlx::Error::Database(db_err) => {
+                    AppError::BadRequest(format!("Database error: {}", db_err))
+                }
+                e @ (sqlx::Error::PoolTimedOut | sqlx::Error::PoolClosed | sqlx::Error::Io(_)) => e.into(),
+                _ => AppError::BadRequest("Failed to create post".to_string())
+            }
+        })
+}
+
+pub async fn create_post(
     State(app_state): State<AppState>,
     Extension(claims): Extension<Claims>,
-    Path(post_id): Path<String>,
-    Json(comment_data): Json<CommentCreate>,
-) -> Result<(StatusCode, Json<Comment>), AppError> {
+    ValidatedJson(post_data): ValidatedJson<PostCreate>,
+) -> Result<(StatusCode, Json<Post>), AppError> {
+    let user_uuid = Uuid::parse_str(&claims.sub)
+        .map_err(|_| AppError::Coded(ErrorCode::InvalidId, "Invalid user ID".to_string()))?;
+
+    check_publish_at(&post_data)?;
+
+    let hashtags = extract_hashtags(&post_data.content);
+    // Mentioned users are notified when a draft gets published
+    let mentions = match post_data.effective_status() {
+        PostStatus::Published => extract_mentions(&post_data.content),
+        PostStatus::Draft => Vec::new(),
+    };
+
+    let post_row = if hashtags.is_empty() && mentions.is_empty() {
+        insert_post(&app_state.db, user_uuid, &post_data).await?
+    } else {
+        // Post, hashtag links and mention notifications are written atomically
+        let mut tx = app_state.db.begin().await?;
+        let post_row = insert_post(&mut *tx, user_uuid, &post_data).await?;
+        if !hashtags.is_empty() {
+            sqlx::query(SQL_LINK_POST_HASHTAGS)
+                .bind(post_row.id)
+                .bind(&hashtags)
+                .bind(post_row.created_at)
+                .execute(&mut *tx)
+                .await?;
+        }
+        if !mentions.is_empty() {
+            notify_mentions(&mut *tx, user_uuid, post_row.id, None, &mentions).await?;
+        }
+        tx.commit().await?;
+        post_row
+    };
+
+    let post = Post::from(post_row);
+    app_state.cache.invalidate_post_lists().await;
+
+    Ok((StatusCode::CREATED, Json(post)))
+}
+
+pub async fn create_posts_batch(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    ValidatedJson(batch): ValidatedJson<PostBatchCreate>,
+) -> Result<(StatusCode, Json<Vec<Post>>), AppError> {
     let user_uuid = Uuid::parse_str(&claims.sub)
-        .map_err(|_| AppError::BadRequest("Invalid user ID".to_string()))?;
-    let post_uuid = Uuid::parse_str(&post_id)
-        .map_err(|_| AppError::BadRequest("Invalid post ID".to_string()))?;
+        .map_err(|_| AppError::Coded(ErrorCode::InvalidId, "Invalid user ID".to_string()))?;
+
+    if batch.posts.is_empty() || batch.posts.len() > MAX_BATCH_POSTS {
+        return Err(AppError::BadRequest(format!(
+            "A batch must contain between 1 and {} posts",
+            MAX_BATCH_POSTS
+        )));
+    }
+
+    for post in &batch.posts {
+        check_publish_at(post)?;
+    }
+
+    let ids: Vec<Uuid> = batch.posts.iter().map(|_| Uuid::new_v4()).collect();
+    let statuses: Vec<&str> = batch.posts.iter().map(|post| post.effective_status().as_str()).collect();
+    let publish_ats: Vec<Option<DateTime<Utc>>> = batch.posts.iter().map(|post| post.publish_at).collect();
+    let contents: Vec<String> = batch.posts.into_iter().map(|post| post.content).collect();
 
-    let comment_row: CommentRow = sqlx::query_as(SQL_CREATE_COMMENT)
+    // Hashtag links and mention notifications are written per post, in the same transaction
+    let mut tx = app_state.db.begin().await?;
+    let post_rows: Vec<PostCreateRow> = sqlx::query_as(SQL_CREATE_POSTS_BATCH)
         .bind(user_uuid)
-        .bind(post_uuid)
-        .bind(&comment_data.content)
+        .bind(&ids)
+        .bind(&contents)
+        .bind(&statuses)
+        .bind(&publish_ats)
+        .fetch_all(&mut *tx)
+        .await?;
+
+    for post_row in &post_rows {
+        let hashtags = extract_hashtags(&post_row.content);
+        if !hashtags.is_empty() {
+            sqlx::query(SQL_LINK_POST_HASHTAGS)
+                .bind(post_row.id)
+                .bind(&hashtags)
+                .bind(post_row.created_at)
+                .execute(&mut *tx)
+                .await?;
+        }
+        let mentions = extract_mentions(&post_row.content);
+        if !mentions.is_empty() && post_row.status == PostStatus::Published.as_str() {
+            notify_mentions(&mut *tx, user_uuid, post_row.id, None, &mentions).await?;
+        }
+    }
+    tx.commit().await?;
+    app_state.cache.invalidate_post_lists().await;
+
+    let posts: Vec<Post> = post_rows.into_iter().map(Post::from).collect();
+    Ok((StatusCode::CREATED, Json(posts)))
+}
+
+pub async fn count_posts(State(app_state): State<AppState>) -> Result<Json<Count>, AppError> {
+    let count: i64 = sqlx::query_scalar(SQL_COUNT_PUBLISHED_POSTS)
         .fetch_one(&app_state.db)
-        .await
-        .map_err(|e| {
-            if let Some(db_err) = e.as_database_error() {
-                if let Some(pg_err) = db_err.try_downcast_ref::<sqlx::postgres::PgDatabaseError>() {
-                    // 23503: foreign_key_violation
-                    if pg_err.code() == "23503" {
-                        return AppError::NotFound("Post not found".to_string());
-                    }
+        .await?;
+
+    Ok(Json(Count { count }))
+}
+
+pub async fn list_posts(
+    State(app_state): State<AppState>,
+    OptionalClaims(viewer): OptionalClaims,
+    RawQuery(raw_query): RawQuery,
+    PagedQuery(query): PagedQuery<PostListQuery>,
+) -> Result<Response, AppError> {
+    // likedByMe and the viewer's drafts make authenticated listings per-user
+    let key = match viewer {
+        None => app_state.cache.posts_list_key(raw_query.as_deref().unwrap_or_default()).await,
+        Some(_) => None,
+    };
+
+    // Plain pages serialized by Postgres are passed through, streamed or not
+    if *POSTS_JSON_BY_DB && query.cursor.is_none() && !query.envelope {
+        let posts = app_state
+            .cache
+            .get_or_load_body(key, query_posts_json(&app_state, &viewer, &query))
+            .await?;
+        return Ok(posts.into_response());
+    }
+
+    // Large plain pages that aren't cached are streamed instead of collected
+    if key.is_none() && query.cursor.is_none() && !query.envelope && query.limit >= *STREAM_MIN_PAGE_SIZE {
+        let (mut builder, _) = select_posts(&viewer, &query)?;
+        push_offset_page(&mut builder, &query);
+        return stream_json_array(app_state.read_db().clone(), builder, |row: PostRow| Post::from(row)).await;
+    }
+
+    let posts = app_state
+        .cache
+        .get_or_load(key, query_posts(&app_state, &viewer, &query))
+        .await?;
+    Ok(posts.into_response())
+}
+
+// Base select of post listings with the query's filters, the viewer bound as $1
+fn select_posts(
+    viewer: &Option<Claims>,
+    query: &PostListQuery,
+) -> Result<(QueryBuilder<'static, Postgres>, PostFilters), AppError> {
+    select_posts_after(String::new(), viewer, query)
+}
+
+// The same, with `prefix` written before the select (wrapping it into another query)
+fn select_posts_after(
+    prefix: String,
+    viewer: &Option<Claims>,
+    query: &PostListQuery,
+) -> Result<(QueryBuilder<'static, Postgres>, PostFilters), AppError> {
+    let filters = PostFilters::from_query(query, viewer_id(viewer))?;
+    let mut arguments = PgArguments::default();
+    arguments
+        .add(viewer_id(viewer))
+        .map_err(|e| AppError::InternalServerError(e.to_string()))?;
+    // $1 of the base select is the viewer, filters are bound after it
+    let mut builder = QueryBuilder::with_arguments(prefix + SQL_SELECT_POSTS, arguments);
+    filters.push_where(&mut builder);
+    Ok((builder, filters))
+}
+
+fn push_offset_page(builder: &mut QueryBuilder<'_, Postgres>, query: &PostListQuery) {
+    let order_by = post_order_by(query.sort.unwrap_or_default(), query.order.unwrap_or_default());
+    builder
+        .push("\nORDER BY ")
+        .push(order_by)
+        .push("\nLIMIT ")
+        .push_bind(query.limit)
+        .push(" OFFSET ")
+        .push_bind(query.offset);
+}
+
+// Offset page of posts as the JSON array Postgres aggregated
+async fn query_posts_json(
+    app_state: &AppState,
+    viewer: &Option<Claims>,
+    query: &PostListQuery,
+) -> Result<Bytes, AppError> {
+    let order_by = post_json_order_by(query.sort.unwrap_or_default(), query.order.unwrap_or_default());
+    let (mut builder, _) = select_posts_after(posts_json_select(viewer.is_some(), order_by), viewer, query)?;
+    push_offset_page(&mut builder, query);
+    builder.push(POSTS_JSON_END);
+    let posts: String = builder
+        .build_query_scalar()
+        .fetch_one(app_state.read_db())
+        .await?;
+    Ok(Bytes::from(posts))
+}
+
+async fn query_posts(
+    app_state: &AppState,
+    viewer: &Option<Claims>,
+    query: &PostListQuery,
+) -> Result<Listing<Post>, AppError> {
+    let (mut builder, filters) = select_posts(viewer, query)?;
+
+    if let Some(cursor) = &query.cursor {
+        if query.sort.is_some() || query.order.is_some() {
+            return Err(AppError::BadRequest(
+                "sort and order are not supported with cursor pagination".to_string(),
+            ));
+        }
+
+        if let Some((created_at, id)) = decode_optional_cursor(cursor)? {
+            builder
+                .push(" AND (p.created_at, p.id) < (")
+                .push_bind(created_at)
+                .push(", ")
+                .push_bind(id)
+                .push(")");
+        }
+        // Fetch one extra row to know whether there is a next page
+        builder
+            .push("\nORDER BY p.created_at DESC, p.id DESC\nLIMIT ")
+            .push_bind(query.limit + 1);
+        let mut post_rows: Vec<PostRow> = builder
+            .build_query_as()
+            .fetch_all(app_state.read_db())
+            .await?;
+        let next_cursor = next_cursor(&mut post_rows, query.limit, |row| (row.created_at, row.id));
+        return Ok(Listing::Cursor(CursorPage {
+            items: post_rows.into_iter().map(Post::from).collect(),
+            next_cursor,
+        }));
+    }
+
+    push_offset_page(&mut builder, query);
+    let list = builder
+        .build_query_as::<PostRow>()
+        .fetch_all(app_state.read_db());
+
+    if !query.envelope {
+        let post_rows = list.await?;
+        return Ok(Listing::Items(post_rows.into_iter().map(Post::from).collect()));
+    }
+
+    let mut count = QueryBuilder::new(SQL_COUNT_POSTS);
+    filters.push_where(&mut count);
+    let (post_rows, total) = join_queries(
+        app_state.read_db(),
+        list,
+        count.build_query_scalar::<i64>().fetch_one(app_state.read_db()),
+    )
+    .await?;
+    // The cursor pages by (created_at, id) descending, it only continues the default order
+    let default_order = query.sort.is_none() && query.order.is_none();
+    let next_cursor = (default_order && query.offset + (post_rows.len() as i64) < total)
+        .then(|| post_rows.last().map(|row| encode_cursor(row.created_at, row.id)))
+        .flatten();
+    Ok(Listing::Page(Page {
+        data: post_rows.into_iter().map(Post::from).collect(),
+        total,
+        limit: query.limit,
+        offset: query.offset,
+        next_cursor,
+    }))
+}
+
+pub async fn lookup_posts(
+    State(app_state): State<AppState>,
+    OptionalClaims(viewer): OptionalClaims,
+    Json(lookup): Json<PostLookup>,
+) -> Result<Json<Vec<Post>>, AppError> {
+    if lookup.ids.len() > MAX_LOOKUP_POSTS {
+        return Err(AppError::BadRequest(format!(
+            "At most {} post IDs can be looked up at once",
+            MAX_LOOKUP_POSTS
+        )));
+    }
+
+    let mut post_uuids = Vec::with_capacity(lookup.ids.len());
+    for id in &lookup.ids {
+        let post_uuid = Uuid::parse_str(id)
+            .map_err(|_| AppError::Coded(ErrorCode::InvalidId, "Invalid post ID".to_string()))?;
+        // Duplicates would only repeat the same row
+        if !post_uuids.contains(&post_uuid) {
+            post_uuids.push(post_uuid);
+        }
+    }
+
+    if post_uuids.is_empty() {
+        return Ok(Json(Vec::new()));
+    }
+
+    let post_rows: Vec<PostRow> = sqlx::query_as(SQL_LOOKUP_POSTS)
+        .bind(&post_uuids)
+        .bind(viewer_id(&viewer))
+        .fetch_all(&app_state.db)
+        .await?;
+
+    let posts: Vec<Post> = post_rows.into_iter().map(Post::from).collect();
+    Ok(Json(posts))
+}
+
+pub async fn list_user_posts(
+    State(app_state): State<AppState>,
+    OptionalClaims(viewer): OptionalClaims,
+    Path(UserId(author_uuid)): Path<UserId>,
+    PagedQuery(pagination): PagedQuery<PaginationQuery>,
+) -> Result<Json<Vec<Post>>, AppError> {
+    let post_rows: Vec<PostRow> = sqlx::query_as(SQL_LIST_POSTS_BY_AUTHOR)
+        .bind(author_uuid)
+        .bind(pagination.limit)
+        .bind(pagination.offset)
+        .bind(viewer_id(&viewer))
+        .fetch_all(&app_state.db)
+        .await?;
+
+    let posts: Vec<Post> = post_rows.into_iter().map(Post::from).collect();
+    Ok(Json(posts))
+}
+
+pub async fn trending_posts(
+    State(app_state): State<AppState>,
+    OptionalClaims(viewer): OptionalClaims,
+    PagedQuery(query): PagedQuery<TrendingQuery>,
+) -> Result<([(HeaderName, &'static str); 1], Json<Vec<Post>>), AppError> {
+    if !(1..=MAX_TRENDING_WINDOW_HOURS).contains(&query.window_hours) {
+        return Err(AppError::BadRequest(format!(
+            "windowHours must be between 1 and {}",
+            MAX_TRENDING_WINDOW_HOURS
+        )));
+    }
+
+    let post_rows: Vec<PostRow> = sqlx::query_as(SQL_TRENDING_POSTS)
+        .bind(query.window_hours)
+        .bind(query.limit)
+        .bind(viewer_id(&viewer))
+        .fetch_all(&app_state.db)
+        .await?;
+
+    let posts: Vec<Post> = post_rows.into_iter().map(Post::from).collect();
+    // likedByMe makes authenticated responses per-user
+    let cache_control = if viewer.is_some() {
+        TRENDING_PRIVATE_CACHE_CONTROL
+    } else {
+        TRENDING_CACHE_CONTROL
+    };
+    Ok(([(header::CACHE_CONTROL, cache_control)], Json(posts)))
+}
+
+pub async fn get_post(
+    State(app_state): State<AppState>,
+    OptionalClaims(viewer): OptionalClaims,
+    Path(PostId(post_uuid)): Path<PostId>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let load = async {
+        let post_row: Option<PostRow> = sqlx::query_as(SQL_GET_POST)
+            .bind(post_uuid)
+            .bind(viewer_id(&viewer))
+            .fetch_optional(app_state.read_db())
+            .await?;
+
+        match post_row {
+            Some(row) => Ok(Post::from(row)),
+            None => Err(AppError::from(ErrorCode::PostNotFound)),
+        }
+    };
+    // likedByMe and the author's drafts make authenticated responses per-user
+    let post = match viewer {
+        None => app_state.cache.get_or_load_post(post_uuid, load).await?,
+        Some(_) => app_state.cache.get_or_load(None, load).await?,
+    };
+    Ok(post.conditional(&headers))
+}
+
+pub async fn update_post(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(PostId(post_uuid)): Path<PostId>,
+    headers: HeaderMap,
+    ValidatedJson(update_data): ValidatedJson<UpdatePost>,
+) -> Result<Json<Post>, AppError> {
+    let user_uuid = Uuid::parse_str(&claims.sub)
+        .map_err(|_| AppError::Coded(ErrorCode::InvalidId, "Invalid user ID".to_string()))?;
+    let version = expected_version(&headers, update_data.version)?;
+
+    // Ownership check and update in one statement. The post's hashtag links follow its new
+    // content, in the same transaction
+    let mut tx = app_state.db.begin().await?;
+    let post_row: Option<PostRow> = sqlx::query_as(SQL_UPDATE_POST)
+        .bind(post_uuid)
+        .bind(&update_data.content)
+        .bind(user_uuid)
+        .bind(version)
+        .bind(claims.is_admin)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+    match post_row {
+        Some(row) => {
+            sqlx::query(SQL_UNLINK_POST_HASHTAGS)
+                .bind(post_uuid)
+                .execute(&mut *tx)
+                .await?;
+            let hashtags = extract_hashtags(&row.content);
+            if !hashtags.is_empty() {
+                sqlx::query(SQL_LINK_POST_HASHTAGS)
+                    .bind(post_uuid)
+                    .bind(&hashtags)
+                    .bind(row.created_at)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            tx.commit().await?;
+            app_state.cache.invalidate_post(post_uuid).await;
+            Ok(Json(Post::from(row)))
+        }
+        None => {
+            // Rolled back before the follow-up query takes another connection. Only a
+            // missed update looks the post up again, to tell why it didn't match
+            drop(tx);
+            let author_id: Option<Uuid> = sqlx::query_scalar(SQL_GET_POST_AUTHOR)
+                .bind(post_uuid)
+                .fetch_optional(&app_state.db)
+                .await?;
+            match author_id {
+                None => Err(AppError::from(ErrorCode::PostNotFound)),
+                Some(author_id) if author_id != user_uuid && !claims.is_admin => {
+                    Err(AppError::Coded(
+                        ErrorCode::NotOwner,
+                        "You can only update your own posts".to_string(),
+                    ))
                 }
+                Some(_) if version.is_some() => Err(ErrorCode::VersionMismatch.into()),
+                // Deleted and restored while the update ran
+                Some(_) => Err(AppError::from(ErrorCode::PostNotFound)),
             }
-            AppError::BadRequest("Failed to create comment".to_string())
-        })?;
+        }
+    }
+}
 
-    Ok((StatusCode::CREATED, Json(Comment::from(comment_row))))
+pub async fn delete_post(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(PostId(post_uuid)): Path<PostId>,
+) -> Result<StatusCode, AppError> {
+    let user_uuid = Uuid::parse_str(&claims.sub)
+        .map_err(|_| AppError::Coded(ErrorCode::InvalidId, "Invalid user ID".to_string()))?;
+
+    // Ownership check and delete in one statement
+    let deleted: Option<bool> = sqlx::query_scalar(SQL_DELETE_POST)
+        .bind(post_uuid)
+        .bind(user_uuid)
+        .bind(claims.is_admin)
+        .fetch_optional(&app_state.db)
+        .await?;
+
+    match deleted {
+        Some(true) => {
+            // Its comments are no longer listed either
+            app_state.cache.invalidate_comments(post_uuid).await;
+            Ok(StatusCode::NO_CONTENT)
+        }
+        Some(false) => Err(AppError::Coded(
+            ErrorCode::NotOwner,
+            "You can only delete your own posts".to_string(),
+        )),
+        None => Err(AppError::from(ErrorCode::PostNotFound)),
+    }
 }
 
-pub async fn list_comments(
+pub async fn publish_post(
     State(app_state): State<AppState>,
-    Path(post_id): Path<String>,
-) -> Result<Json<Vec<Comment>>, AppError> {
-    let post_uuid = Uuid::parse_str(&post_id)
-        .map_err(|_| AppError::BadRequest("Invalid post ID".to_string()))?;
+    Extension(claims): Extension<Claims>,
+    Path(PostId(post_uuid)): Path<PostId>,
+) -> Result<Json<Post>, AppError> {
+    let user_uuid = Uuid::parse_str(&claims.sub)
+        .map_err(|_| AppError::Coded(ErrorCode::InvalidId, "Invalid user ID".to_string()))?;
 
-    // Check if post exists
-    let post_exists: Option<PostRow> = sqlx::query_as(SQL_GET_POST)
+    let author_id: Option<Uuid> = sqlx::query_scalar(SQL_GET_POST_AUTHOR)
         .bind(post_uuid)
         .fetch_optional(&app_state.db)
         .await?;
 
-    if post_exists.is_none() {
-        return Err(AppError::NotFound("Post not found".to_string()));
+    let author_id = author_id.ok_or_else(|| AppError::from(ErrorCode::PostNotFound))?;
+
+    if author_id != user_uuid {
+        return Err(AppError::Coded(
+            ErrorCode::NotOwner,
+            "You can only publish your own posts".to_string(),
+        ));
     }
 
-    let comment_rows: Vec<CommentRow> = sqlx::query_as(SQL_LIST_COMMENTS)
+    // Mention notifications were held back while the post was a draft
+    let mut tx = app_state.db.begin().await?;
+    let post_row: Option<PostRow> = sqlx::query_as(SQL_PUBLISH_POST)
         .bind(post_uuid)
-        .fetch_all(&app_state.db)
+        .bind(user_uuid)
+        .fetch_optional(&mut *tx)
         .await?;
 
-    let comments: Vec<Comment> = comment_rows.into_iter().map(Comment::from).collect();
-    Ok(Json(comments))
+    let post_row = post_row.ok_or_else(|| AppError::from(ErrorCode::AlreadyPublished))?;
+
+    let mentions = extract_mentions(&post_row.content);
+    if !mentions.is_empty() {
+        notify_mentions(&mut *tx, user_uuid, post_row.id, None, &mentions).await?;
+    }
+    tx.commit().await?;
+    app_state.cache.invalidate_post(post_uuid).await;
+
+    Ok(Json(Post::from(post_row)))
 }
 
-////////////////////////////////////////////////////////////////////////////////
-// Likes endpoints
-////////////////////////////////////////////////////////////////////////////////
+pub async fn restore_post(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(PostId(post_uuid)): Path<PostId>,
+) -> Result<Json<Post>, AppError> {
+    if !claims.is_admin {
+        return Err(AppError::from(ErrorCode::AdminRequired));
+    }
 
-pub async fn like_post(
+    let user_uuid = Uuid::parse_str(&claims.sub)
+        .map_err(|_| AppError::Coded(ErrorCode::InvalidId, "Invalid user ID".to_string()))?;
+
+    // Only matches posts that are currently deleted, by an author who isn't, and reposts
+    // not superseded by a newer repost of the same post
+    let result: Result<Option<PostRow>, sqlx::Error> = sqlx::query_as(SQL_RESTORE_POST)
+        .bind(post_uuid)
+        .bind(user_uuid)
+        .fetch_optional(&app_state.db)
+        .await;
+
+    match result {
+        Ok(Some(row)) => {
+            app_state.cache.invalidate_comments(post_uuid).await;
+            Ok(Json(Post::from(row)))
+        }
+        Ok(None) => Err(AppError::Coded(ErrorCode::PostNotFound, "Deleted post not found".to_string())),
+        Err(e) => {
+            // Reposted again while the restore ran
+            if let Some(db_err) = e.as_database_error() {
+                if db_err.code().as_deref() == Some("23505") {
+                    return Err(AppError::from(ErrorCode::AlreadyReposted)); // unique_violation
+                }
+            }
+            Err(e.into())
+        }
+    }
+}
+
+pub async fn repost_post(
     State(app_state): State<AppState>,
     Extension(claims): Extension<Claims>,
-    Path(post_id): Path<String>,
-) -> Result<StatusCode, AppError> {
+    Path(PostId(post_uuid)): Path<PostId>,
+) -> Result<(StatusCode, Json<Post>), AppError> {
     let user_uuid = Uuid::parse_str(&claims.sub)
-        .map_err(|_| AppError::BadRequest("Invalid user ID".to_string()))?;
-    let post_uuid = Uuid::parse_str(&post_id)
-        .map_err(|_| AppError::BadRequest("Invalid post ID".to_string()))?;
+        .map_err(|_| AppError::Coded(ErrorCode::InvalidId, "Invalid user ID".to_string()))?;
 
-    let result = sqlx::query(SQL_CREATE_LIKE)
+    let result: Result<Option<PostRow>, sqlx::Error> = sqlx::query_as(SQL_REPOST_POST)
         .bind(user_uuid)
         .bind(post_uuid)
-        .execute(&app_state.db)
+        .fetch_optional(&app_state.db)
         .await;
 
     match result {
-        Ok(_) => Ok(StatusCode::NO_CONTENT),
+        Ok(Some(row)) => {
+            // The original's repostCount changed
+            app_state.cache.invalidate_post(post_uuid).await;
+            Ok((StatusCode::CREATED, Json(Post::from(row))))
+        }
+        Ok(None) => Err(AppError::from(ErrorCode::PostNotFound)),
         Err(e) => {
             if let Some(db_err) = e.as_database_error() {
-                if let Some(pg_err) = db_err.try_downcast_ref::<sqlx::postgres::PgDatabaseError>() {
-                    match pg_err.code() {
-                        "23505" => return Err(AppError::Conflict("Post already liked".to_string())), // unique_violation
-                        "23503" => return Err(AppError::NotFound("Post not found".to_string())), // foreign_key_violation
-                        _ => {}
-                    }
+                if db_err.code().as_deref() == Some("23505") {
+                    return Err(AppError::from(ErrorCode::AlreadyReposted)); // unique_violation
                 }
             }
             Err(e.into())
@@ -392,25 +1487,1185 @@ pub async fn like_post(
     }
 }
 
-pub async fn unlike_post(
+////////////////////////////////////////////////////////////////////////////////
+// Comments endpoints
+////////////////////////////////////////////////////////////////////////////////
+
+async fn insert_comment<'e, E>(
+    executor: E,
+    author_id: Uuid,
+    post_id: Uuid,
+    content: &str,
+) -> Result<CommentRow, AppError>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    sqlx::query_as(SQL_CREATE_COMMENT)
+        .bind(author_id)
+        .bind(post_id)
+        .bind(content)
+        .fetch_one(executor)
+        .await
+        .map_err(|e| {
+            if let Some(db_err) = e.as_database_error() {
+                if let Some(pg_err) = db_err.try_downcast_ref::<sqlx::postgres::PgDatabaseError>() {
+                    // 23503: foreign_key_violation
+                    if pg_err.code() == "23503" {
+                        return AppError::from(ErrorCode::PostNotFound);
+                    }
+                }
+            }
+            AppError::BadRequest("Failed to create comment".to_string())
+        })
+}
+
+pub async fn create_comment(
     State(app_state): State<AppState>,
     Extension(claims): Extension<Claims>,
-    Path(post_id): Path<String>,
-) -> Result<StatusCode, AppError> {
+    Path(PostId(post_uuid)): Path<PostId>,
+    ValidatedJson(comment_data): ValidatedJson<CommentCreate>,
+) -> Result<(StatusCode, Json<Comment>), AppError> {
     let user_uuid = Uuid::parse_str(&claims.sub)
-        .map_err(|_| AppError::BadRequest("Invalid user ID".to_string()))?;
-    let post_uuid = Uuid::parse_str(&post_id)
-        .map_err(|_| AppError::BadRequest("Invalid post ID".to_string()))?;
+        .map_err(|_| AppError::Coded(ErrorCode::InvalidId, "Invalid user ID".to_string()))?;
+
+    let mentions = extract_mentions(&comment_data.content);
+
+    let comment_row = if mentions.is_empty() {
+        insert_comment(&app_state.db, user_uuid, post_uuid, &comment_data.content).await?
+    } else {
+        // Comment and mention notifications are written atomically
+        let mut tx = app_state.db.begin().await?;
+        let comment_row =
+            insert_comment(&mut *tx, user_uuid, post_uuid, &comment_data.content).await?;
+        notify_mentions(&mut *tx, user_uuid, post_uuid, Some(comment_row.id), &mentions).await?;
+        tx.commit().await?;
+        comment_row
+    };
+    app_state.cache.invalidate_comments(post_uuid).await;
 
-    let result = sqlx::query(SQL_DELETE_LIKE)
-        .bind(user_uuid)
+    Ok((StatusCode::CREATED, Json(Comment::from(comment_row))))
+}
+
+pub async fn count_post_comments(
+    State(app_state): State<AppState>,
+    OptionalClaims(viewer): OptionalClaims,
+    Path(PostId(post_uuid)): Path<PostId>,
+) -> Result<Json<Count>, AppError> {
+    // Read from the posts.comments_count counter cache
+    let count: Option<i64> = sqlx::query_scalar(SQL_COUNT_POST_COMMENTS)
         .bind(post_uuid)
-        .execute(&app_state.db)
+        .bind(viewer_id(&viewer))
+        .fetch_optional(&app_state.db)
         .await?;
 
-    if result.rows_affected() == 0 {
-        return Err(AppError::NotFound("Post or like not found".to_string()));
+    match count {
+        Some(count) => Ok(Json(Count { count })),
+        None => Err(AppError::from(ErrorCode::PostNotFound)),
     }
+}
 
-    Ok(StatusCode::NO_CONTENT)
+pub async fn list_comments(
+    State(app_state): State<AppState>,
+    Path(PostId(post_uuid)): Path<PostId>,
+    RawQuery(raw_query): RawQuery,
+    PagedQuery(query): PagedQuery<CommentListQuery>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let key = app_state
+        .cache
+        .comments_list_key(post_uuid, raw_query.as_deref().unwrap_or_default())
+        .await;
+    let comments = app_state
+        .cache
+        .get_or_load(key, query_comments(&app_state, post_uuid, &query))
+        .await?;
+    Ok(comments.conditional(&headers))
+}
+
+async fn query_comments(
+    app_state: &AppState,
+    post_uuid: Uuid,
+    query: &CommentListQuery,
+) -> Result<Listing<Comment>, AppError> {
+    let limit = query.limit.min(MAX_COMMENTS_PAGE_SIZE);
+    let (direction, comparison) = match query.order.unwrap_or(SortOrder::Asc) {
+        SortOrder::Asc => ("ASC", " > "),
+        SortOrder::Desc => ("DESC", " < "),
+    };
+    let order_by = format!("\nORDER BY c.created_at {direction}, c.id {direction}");
+    let cursor = query.cursor.as_deref().map(decode_optional_cursor).transpose()?;
+
+    let mut arguments = PgArguments::default();
+    arguments
+        .add(post_uuid)
+        .map_err(|e| AppError::InternalServerError(e.to_string()))?;
+    // $1 of the base select is the post, the page is bound after it
+    let mut builder = QueryBuilder::with_arguments(SQL_LIST_POST_COMMENTS, arguments);
+    if let Some(Some((created_at, id))) = cursor {
+        builder
+            .push(" AND (c.created_at, c.id)")
+            .push(comparison)
+            .push("(")
+            .push_bind(created_at)
+            .push(", ")
+            .push_bind(id)
+            .push(")");
+    }
+    builder.push(&order_by).push("\nLIMIT ");
+    match cursor {
+        None => builder.push_bind(limit).push(" OFFSET ").push_bind(query.offset),
+        // Fetch one extra row to know whether there is a next page
+        Some(_) => builder.push_bind(limit + 1),
+    };
+    builder.push("\n) c ON true").push(&order_by);
+
+    let rows: Vec<PostCommentRow> = builder
+        .build_query_as()
+        .fetch_all(app_state.read_db())
+        .await?;
+    if rows.is_empty() {
+        return Err(AppError::from(ErrorCode::PostNotFound));
+    }
+    let mut comment_rows: Vec<CommentRow> = rows.into_iter().filter_map(PostCommentRow::comment).collect();
+
+    if cursor.is_none() {
+        return Ok(Listing::Items(comment_rows.into_iter().map(Comment::from).collect()));
+    }
+    let next_cursor = next_cursor(&mut comment_rows, limit, |row| (row.created_at, row.id));
+
+    Ok(Listing::Cursor(CursorPage {
+        items: comment_rows.into_iter().map(Comment::from).collect(),
+        next_cursor,
+    }))
+}
+
+// Returns the comment author, 404 if the comment doesn't exist on this post
+async fn get_comment_author(
+    app_state: &AppState,
+    comment_uuid: Uuid,
+    post_uuid: Uuid,
+) -> Result<Uuid, AppError> {
+    let author_id: Option<Uuid> = sqlx::query_scalar(SQL_GET_COMMENT_AUTHOR)
+        .bind(comment_uuid)
+        .bind(post_uuid)
+        .fetch_optional(&app_state.db)
+        .await?;
+
+    author_id.ok_or_else(|| AppError::from(ErrorCode::CommentNotFound))
+}
+
+pub async fn update_comment(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path((PostId(post_uuid), CommentId(comment_uuid))): Path<(PostId, CommentId)>,
+    ValidatedJson(update_data): ValidatedJson<UpdateComment>,
+) -> Result<Json<Comment>, AppError> {
+    let user_uuid = Uuid::parse_str(&claims.sub)
+        .map_err(|_| AppError::Coded(ErrorCode::InvalidId, "Invalid user ID".to_string()))?;
+
+    let author_id = get_comment_author(&app_state, comment_uuid, post_uuid).await?;
+
+    if author_id != user_uuid && !claims.is_admin {
+        return Err(AppError::Coded(
+            ErrorCode::NotOwner,
+            "You can only update your own comments".to_string(),
+        ));
+    }
+
+    let comment_row: Option<CommentRow> = sqlx::query_as(SQL_UPDATE_COMMENT)
+        .bind(comment_uuid)
+        .bind(post_uuid)
+        .bind(&update_data.content)
+        .fetch_optional(&app_state.db)
+        .await?;
+
+    match comment_row {
+        Some(row) => {
+            app_state.cache.invalidate_comments(post_uuid).await;
+            Ok(Json(Comment::from(row)))
+        }
+        None => Err(AppError::from(ErrorCode::CommentNotFound)),
+    }
+}
+
+pub async fn delete_comment(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path((PostId(post_uuid), CommentId(comment_uuid))): Path<(PostId, CommentId)>,
+) -> Result<StatusCode, AppError> {
+    let user_uuid = Uuid::parse_str(&claims.sub)
+        .map_err(|_| AppError::Coded(ErrorCode::InvalidId, "Invalid user ID".to_string()))?;
+
+    let author_id = get_comment_author(&app_state, comment_uuid, post_uuid).await?;
+
+    if author_id != user_uuid && !claims.is_admin {
+        return Err(AppError::Coded(
+            ErrorCode::NotOwner,
+            "You can only delete your own comments".to_string(),
+        ));
+    }
+
+    sqlx::query(SQL_DELETE_COMMENT)
+        .bind(comment_uuid)
+        .bind(post_uuid)
+        .execute(&app_state.db)
+        .await?;
+    app_state.cache.invalidate_comments(post_uuid).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Likes endpoints
+////////////////////////////////////////////////////////////////////////////////
+
+pub async fn like_post(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(PostId(post_uuid)): Path<PostId>,
+) -> Result<StatusCode, AppError> {
+    let user_uuid = Uuid::parse_str(&claims.sub)
+        .map_err(|_| AppError::Coded(ErrorCode::InvalidId, "Invalid user ID".to_string()))?;
+
+    let sql = if app_state.like_count_by_app {
+        SQL_CREATE_LIKE_COUNTED
+    } else {
+        SQL_CREATE_LIKE
+    };
+    let result = sqlx::query(sql)
+        .bind(user_uuid)
+        .bind(post_uuid)
+        .execute(&app_state.db)
+        .await;
+
+    match result {
+        Ok(_) => {
+            app_state.cache.invalidate_post(post_uuid).await;
+            Ok(StatusCode::NO_CONTENT)
+        }
+        Err(e) => {
+            if let Some(db_err) = e.as_database_error() {
+                if let Some(pg_err) = db_err.try_downcast_ref::<sqlx::postgres::PgDatabaseError>() {
+                    match pg_err.code() {
+                        "23505" => return Err(AppError::from(ErrorCode::AlreadyLiked)), // unique_violation
+                        "23503" => return Err(AppError::from(ErrorCode::PostNotFound)), // foreign_key_violation
+                        _ => {}
+                    }
+                }
+            }
+            Err(e.into())
+        }
+    }
+}
+
+pub async fn unlike_post(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(PostId(post_uuid)): Path<PostId>,
+) -> Result<StatusCode, AppError> {
+    let user_uuid = Uuid::parse_str(&claims.sub)
+        .map_err(|_| AppError::Coded(ErrorCode::InvalidId, "Invalid user ID".to_string()))?;
+
+    let sql = if app_state.like_count_by_app {
+        SQL_DELETE_LIKE_COUNTED
+    } else {
+        SQL_DELETE_LIKE
+    };
+    let result = sqlx::query(sql)
+        .bind(user_uuid)
+        .bind(post_uuid)
+        .execute(&app_state.db)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::from(ErrorCode::LikeNotFound));
+    }
+    app_state.cache.invalidate_post(post_uuid).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn list_user_activity(
+    State(app_state): State<AppState>,
+    OptionalClaims(viewer): OptionalClaims,
+    Path(UserId(user_uuid)): Path<UserId>,
+    PagedQuery(query): PagedQuery<CursorQuery>,
+) -> Result<Json<CursorPage<Activity>>, AppError> {
+    let cursor = query.cursor.as_deref().map(decode_cursor).transpose()?;
+
+    // Likes follow the same privacy rule as GET /users/{userId}/likes, they're left out otherwise
+    let include_likes = app_state.liked_posts_public
+        || viewer
+            .as_ref()
+            .is_some_and(|claims| claims.is_admin || claims.sub == user_uuid.to_string());
+
+    // Fetch one extra row to know whether there is a next page
+    let mut activity_rows: Vec<ActivityRow> = sqlx::query_as(SQL_USER_ACTIVITY)
+        .bind(user_uuid)
+        .bind(cursor.map(|(created_at, _)| created_at))
+        .bind(cursor.map(|(_, id)| id))
+        .bind(query.limit + 1)
+        .bind(include_likes)
+        .fetch_all(&app_state.db)
+        .await?;
+
+    let next_cursor = next_cursor(&mut activity_rows, query.limit, |row| (row.created_at, row.id));
+
+    Ok(Json(CursorPage {
+        items: activity_rows.into_iter().map(Activity::from).collect(),
+        next_cursor,
+    }))
+}
+
+pub async fn list_liked_posts(
+    State(app_state): State<AppState>,
+    OptionalClaims(viewer): OptionalClaims,
+    Path(UserId(user_uuid)): Path<UserId>,
+    PagedQuery(pagination): PagedQuery<PaginationQuery>,
+) -> Result<Json<Vec<Post>>, AppError> {
+    // Likes are private to their owner (and admins) unless opened up globally
+    if !app_state.liked_posts_public {
+        match &viewer {
+            None => return Err(AppError::from(ErrorCode::MissingToken)),
+            Some(claims) if !claims.is_admin && claims.sub != user_uuid.to_string() => {
+                return Err(AppError::Coded(
+                    ErrorCode::NotOwner,
+                    "You can only view your own liked posts".to_string(),
+                ));
+            }
+            Some(_) => {}
+        }
+    }
+
+    let post_rows: Vec<PostRow> = sqlx::query_as(SQL_LIST_LIKED_POSTS)
+        .bind(user_uuid)
+        .bind(pagination.limit)
+        .bind(pagination.offset)
+        .bind(viewer_id(&viewer))
+        .fetch_all(&app_state.db)
+        .await?;
+
+    let posts: Vec<Post> = post_rows.into_iter().map(Post::from).collect();
+    Ok(Json(posts))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Bookmarks endpoints
+////////////////////////////////////////////////////////////////////////////////
+
+pub async fn bookmark_post(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(PostId(post_uuid)): Path<PostId>,
+) -> Result<StatusCode, AppError> {
+    let user_uuid = Uuid::parse_str(&claims.sub)
+        .map_err(|_| AppError::Coded(ErrorCode::InvalidId, "Invalid user ID".to_string()))?;
+
+    let result = sqlx::query(SQL_CREATE_BOOKMARK)
+        .bind(user_uuid)
+        .bind(post_uuid)
+        .execute(&app_state.db)
+        .await;
+
+    match result {
+        Ok(_) => Ok(StatusCode::NO_CONTENT),
+        Err(e) => {
+            if let Some(db_err) = e.as_database_error() {
+                if let Some(pg_err) = db_err.try_downcast_ref::<sqlx::postgres::PgDatabaseError>() {
+                    match pg_err.code() {
+                        "23505" => return Err(AppError::from(ErrorCode::AlreadyBookmarked)), // unique_violation
+                        "23503" => return Err(AppError::from(ErrorCode::PostNotFound)), // foreign_key_violation
+                        _ => {}
+                    }
+                }
+            }
+            Err(e.into())
+        }
+    }
+}
+
+pub async fn unbookmark_post(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(PostId(post_uuid)): Path<PostId>,
+) -> Result<StatusCode, AppError> {
+    let user_uuid = Uuid::parse_str(&claims.sub)
+        .map_err(|_| AppError::Coded(ErrorCode::InvalidId, "Invalid user ID".to_string()))?;
+
+    let result = sqlx::query(SQL_DELETE_BOOKMARK)
+        .bind(user_uuid)
+        .bind(post_uuid)
+        .execute(&app_state.db)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::from(ErrorCode::BookmarkNotFound));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn list_bookmarks(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    PagedQuery(pagination): PagedQuery<PaginationQuery>,
+) -> Result<Json<Vec<Bookmark>>, AppError> {
+    let user_uuid = Uuid::parse_str(&claims.sub)
+        .map_err(|_| AppError::Coded(ErrorCode::InvalidId, "Invalid user ID".to_string()))?;
+
+    let bookmark_rows: Vec<BookmarkRow> = sqlx::query_as(SQL_LIST_BOOKMARKS)
+        .bind(user_uuid)
+        .bind(pagination.limit)
+        .bind(pagination.offset)
+        .fetch_all(&app_state.db)
+        .await?;
+
+    let bookmarks: Vec<Bookmark> = bookmark_rows.into_iter().map(Bookmark::from).collect();
+    Ok(Json(bookmarks))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Follows endpoints
+////////////////////////////////////////////////////////////////////////////////
+
+pub async fn follow_user(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(UserId(target_uuid)): Path<UserId>,
+) -> Result<StatusCode, AppError> {
+    let user_uuid = Uuid::parse_str(&claims.sub)
+        .map_err(|_| AppError::Coded(ErrorCode::InvalidId, "Invalid user ID".to_string()))?;
+
+    if user_uuid == target_uuid {
+        return Err(AppError::from(ErrorCode::CannotFollowSelf));
+    }
+
+    let result = sqlx::query(SQL_CREATE_FOLLOW)
+        .bind(user_uuid)
+        .bind(target_uuid)
+        .execute(&app_state.db)
+        .await;
+
+    match result {
+        Ok(_) => Ok(StatusCode::NO_CONTENT),
+        Err(e) => {
+            if let Some(db_err) = e.as_database_error() {
+                if let Some(pg_err) = db_err.try_downcast_ref::<sqlx::postgres::PgDatabaseError>() {
+                    match pg_err.code() {
+                        "23505" => return Err(AppError::from(ErrorCode::AlreadyFollowing)), // unique_violation
+                        "23503" => return Err(AppError::from(ErrorCode::UserNotFound)), // foreign_key_violation
+                        _ => {}
+                    }
+                }
+            }
+            Err(e.into())
+        }
+    }
+}
+
+pub async fn unfollow_user(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(UserId(target_uuid)): Path<UserId>,
+) -> Result<StatusCode, AppError> {
+    let user_uuid = Uuid::parse_str(&claims.sub)
+        .map_err(|_| AppError::Coded(ErrorCode::InvalidId, "Invalid user ID".to_string()))?;
+
+    let result = sqlx::query(SQL_DELETE_FOLLOW)
+        .bind(user_uuid)
+        .bind(target_uuid)
+        .execute(&app_state.db)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::from(ErrorCode::FollowNotFound));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn list_followers(
+    State(app_state): State<AppState>,
+    Path(UserId(target_uuid)): Path<UserId>,
+    PagedQuery(pagination): PagedQuery<PaginationQuery>,
+) -> Result<Json<Vec<User>>, AppError> {
+    list_follow_users(&app_state, SQL_LIST_FOLLOWERS, target_uuid, &pagination).await
+}
+
+pub async fn list_following(
+    State(app_state): State<AppState>,
+    Path(UserId(target_uuid)): Path<UserId>,
+    PagedQuery(pagination): PagedQuery<PaginationQuery>,
+) -> Result<Json<Vec<User>>, AppError> {
+    list_follow_users(&app_state, SQL_LIST_FOLLOWING, target_uuid, &pagination).await
+}
+
+async fn list_follow_users(
+    app_state: &AppState,
+    query: &'static str,
+    target_uuid: Uuid,
+    pagination: &PaginationQuery,
+) -> Result<Json<Vec<User>>, AppError> {
+    let user_rows: Vec<UserRow> = sqlx::query_as(query)
+        .bind(target_uuid)
+        .bind(pagination.limit)
+        .bind(pagination.offset)
+        .fetch_all(&app_state.db)
+        .await?;
+
+    let users: Vec<User> = user_rows.into_iter().map(User::from).collect();
+    Ok(Json(users))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Feed endpoints
+////////////////////////////////////////////////////////////////////////////////
+
+pub async fn get_feed(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    PagedQuery(query): PagedQuery<CursorQuery>,
+) -> Result<Json<CursorPage<Post>>, AppError> {
+    let user_uuid = Uuid::parse_str(&claims.sub)
+        .map_err(|_| AppError::Coded(ErrorCode::InvalidId, "Invalid user ID".to_string()))?;
+    let cursor = query.cursor.as_deref().map(decode_cursor).transpose()?;
+
+    // Fetch one extra row to know whether there is a next page
+    let mut post_rows: Vec<PostRow> = sqlx::query_as(SQL_FEED)
+        .bind(user_uuid)
+        .bind(cursor.map(|(created_at, _)| created_at))
+        .bind(cursor.map(|(_, id)| id))
+        .bind(query.limit + 1)
+        .fetch_all(&app_state.db)
+        .await?;
+
+    let next_cursor = next_cursor(&mut post_rows, query.limit, |row| (row.created_at, row.id));
+
+    Ok(Json(CursorPage {
+        items: post_rows.into_iter().map(Post::from).collect(),
+        next_cursor,
+    }))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Hashtags endpoints
+////////////////////////////////////////////////////////////////////////////////
+
+pub async fn list_hashtag_posts(
+    State(app_state): State<AppState>,
+    OptionalClaims(viewer): OptionalClaims,
+    Path(tag): Path<String>,
+    PagedQuery(pagination): PagedQuery<PaginationQuery>,
+) -> Result<Json<Vec<Post>>, AppError> {
+    let tag = normalize_hashtag(&tag);
+    if tag.is_empty() {
+        return Err(AppError::BadRequest("Invalid hashtag".to_string()));
+    }
+
+    let post_rows: Vec<PostRow> = sqlx::query_as(SQL_LIST_HASHTAG_POSTS)
+        .bind(&tag)
+        .bind(pagination.limit)
+        .bind(pagination.offset)
+        .bind(viewer_id(&viewer))
+        .fetch_all(&app_state.db)
+        .await?;
+
+    let posts: Vec<Post> = post_rows.into_iter().map(Post::from).collect();
+    Ok(Json(posts))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Notifications endpoints
+////////////////////////////////////////////////////////////////////////////////
+
+// One notification per mentioned user that exists (unknown usernames are ignored)
+pub(crate) async fn notify_mentions<'e, E>(
+    executor: E,
+    actor_id: Uuid,
+    post_id: Uuid,
+    comment_id: Option<Uuid>,
+    usernames: &[String],
+) -> Result<(), AppError>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    sqlx::query(SQL_CREATE_MENTION_NOTIFICATIONS)
+        .bind(actor_id)
+        .bind(post_id)
+        .bind(comment_id)
+        .bind(usernames)
+        .execute(executor)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn list_notifications(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    PagedQuery(pagination): PagedQuery<PaginationQuery>,
+) -> Result<Json<Vec<Notification>>, AppError> {
+    let user_uuid = Uuid::parse_str(&claims.sub)
+        .map_err(|_| AppError::Coded(ErrorCode::InvalidId, "Invalid user ID".to_string()))?;
+
+    let notification_rows: Vec<NotificationRow> = sqlx::query_as(SQL_LIST_NOTIFICATIONS)
+        .bind(user_uuid)
+        .bind(pagination.limit)
+        .bind(pagination.offset)
+        .fetch_all(&app_state.db)
+        .await?;
+
+    let notifications: Vec<Notification> = notification_rows
+        .into_iter()
+        .map(Notification::from)
+        .collect();
+    Ok(Json(notifications))
+}
+
+pub async fn mark_notification_read(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(NotificationId(notification_uuid)): Path<NotificationId>,
+) -> Result<StatusCode, AppError> {
+    let user_uuid = Uuid::parse_str(&claims.sub)
+        .map_err(|_| AppError::Coded(ErrorCode::InvalidId, "Invalid user ID".to_string()))?;
+
+    // Scoped to the current user, someone else's notification is reported as missing
+    let result = sqlx::query(SQL_MARK_NOTIFICATION_READ)
+        .bind(notification_uuid)
+        .bind(user_uuid)
+        .execute(&app_state.db)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::from(ErrorCode::NotificationNotFound));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn unread_notifications_count(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<UnreadCount>, AppError> {
+    let user_uuid = Uuid::parse_str(&claims.sub)
+        .map_err(|_| AppError::Coded(ErrorCode::InvalidId, "Invalid user ID".to_string()))?;
+
+    let unread_count: i64 = sqlx::query_scalar(SQL_UNREAD_NOTIFICATIONS_COUNT)
+        .bind(user_uuid)
+        .fetch_one(&app_state.db)
+        .await?;
+
+    Ok(Json(UnreadCount { unread_count }))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Direct messages endpoints
+////////////////////////////////////////////////////////////////////////////////
+
+pub async fn create_conversation(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(conversation_data): Json<ConversationCreate>,
+) -> Result<(StatusCode, Json<Conversation>), AppError> {
+    let user_uuid = Uuid::parse_str(&claims.sub)
+        .map_err(|_| AppError::Coded(ErrorCode::InvalidId, "Invalid user ID".to_string()))?;
+
+    let too_many = || {
+        AppError::BadRequest(format!(
+            "A conversation can have at most {} participants",
+            MAX_CONVERSATION_PARTICIPANTS
+        ))
+    };
+    // Bounds the parsing and deduplication below, duplicates included
+    if conversation_data.participant_ids.len() > MAX_CONVERSATION_PARTICIPANTS {
+        return Err(too_many());
+    }
+
+    // Creator first, then the other participants without duplicates
+    let mut participant_ids = vec![user_uuid];
+    for participant_id in &conversation_data.participant_ids {
+        let participant_uuid = Uuid::parse_str(participant_id)
+            .map_err(|_| AppError::Coded(ErrorCode::InvalidId, "Invalid participant ID".to_string()))?;
+        if !participant_ids.contains(&participant_uuid) {
+            participant_ids.push(participant_uuid);
+        }
+    }
+
+    if participant_ids.len() < 2 {
+        return Err(AppError::BadRequest(
+            "A conversation needs at least one other participant".to_string(),
+        ));
+    }
+    if participant_ids.len() > MAX_CONVERSATION_PARTICIPANTS {
+        return Err(too_many());
+    }
+
+    let mut tx = app_state.db.begin().await?;
+
+    let (conversation_id, created_at): (Uuid, DateTime<Utc>) =
+        sqlx::query_as(SQL_CREATE_CONVERSATION)
+            .fetch_one(&mut *tx)
+            .await?;
+
+    sqlx::query(SQL_ADD_CONVERSATION_PARTICIPANTS)
+        .bind(conversation_id)
+        .bind(&participant_ids)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            if let Some(db_err) = e.as_database_error() {
+                if let Some(pg_err) = db_err.try_downcast_ref::<sqlx::postgres::PgDatabaseError>() {
+                    // 23503: foreign_key_violation
+                    if pg_err.code() == "23503" {
+                        return AppError::from(ErrorCode::UserNotFound);
+                    }
+                }
+            }
+            e.into()
+        })?;
+
+    tx.commit().await?;
+
+    let conversation = Conversation::from(ConversationRow {
+        id: conversation_id,
+        created_at,
+        last_message_at: None,
+        participant_ids,
+    });
+
+    Ok((StatusCode::CREATED, Json(conversation)))
+}
+
+pub async fn list_conversations(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    PagedQuery(pagination): PagedQuery<PaginationQuery>,
+) -> Result<Json<Vec<Conversation>>, AppError> {
+    let user_uuid = Uuid::parse_str(&claims.sub)
+        .map_err(|_| AppError::Coded(ErrorCode::InvalidId, "Invalid user ID".to_string()))?;
+
+    let conversation_rows: Vec<ConversationRow> = sqlx::query_as(SQL_LIST_CONVERSATIONS)
+        .bind(user_uuid)
+        .bind(pagination.limit)
+        .bind(pagination.offset)
+        .fetch_all(&app_state.db)
+        .await?;
+
+    let conversations: Vec<Conversation> = conversation_rows
+        .into_iter()
+        .map(Conversation::from)
+        .collect();
+    Ok(Json(conversations))
+}
+
+pub async fn create_message(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(ConversationId(conversation_uuid)): Path<ConversationId>,
+    ValidatedJson(message_data): ValidatedJson<MessageCreate>,
+) -> Result<(StatusCode, Json<Message>), AppError> {
+    let user_uuid = Uuid::parse_str(&claims.sub)
+        .map_err(|_| AppError::Coded(ErrorCode::InvalidId, "Invalid user ID".to_string()))?;
+
+    let message_row: Option<MessageRow> = sqlx::query_as(SQL_CREATE_MESSAGE)
+        .bind(conversation_uuid)
+        .bind(user_uuid)
+        .bind(&message_data.content)
+        .fetch_optional(&app_state.db)
+        .await?;
+
+    // Non-participants get the same answer as a missing conversation
+    match message_row {
+        Some(row) => Ok((StatusCode::CREATED, Json(Message::from(row)))),
+        None => Err(AppError::from(ErrorCode::ConversationNotFound)),
+    }
+}
+
+pub async fn list_messages(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(ConversationId(conversation_uuid)): Path<ConversationId>,
+    PagedQuery(pagination): PagedQuery<PaginationQuery>,
+) -> Result<Json<Vec<Message>>, AppError> {
+    let user_uuid = Uuid::parse_str(&claims.sub)
+        .map_err(|_| AppError::Coded(ErrorCode::InvalidId, "Invalid user ID".to_string()))?;
+
+    // Checked first: a non-participant must not make the server read the messages
+    let is_participant: bool = sqlx::query_scalar(SQL_IS_CONVERSATION_PARTICIPANT)
+        .bind(conversation_uuid)
+        .bind(user_uuid)
+        .fetch_one(&app_state.db)
+        .await?;
+
+    if !is_participant {
+        return Err(AppError::from(ErrorCode::ConversationNotFound));
+    }
+
+    let message_rows: Vec<MessageRow> = sqlx::query_as(SQL_LIST_MESSAGES)
+        .bind(conversation_uuid)
+        .bind(pagination.limit)
+        .bind(pagination.offset)
+        .fetch_all(&app_state.db)
+        .await?;
+
+    let messages: Vec<Message> = message_rows.into_iter().map(Message::from).collect();
+    Ok(Json(messages))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Admin endpoints
+////////////////////////////////////////////////////////////////////////////////
+
+pub async fn admin_stats(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<StatsQuery>,
+) -> Result<Json<AdminStats>, AppError> {
+    if !claims.is_admin {
+        return Err(AppError::from(ErrorCode::AdminRequired));
+    }
+
+    if !(1..=MAX_STATS_DAYS).contains(&query.days) {
+        return Err(AppError::BadRequest(format!(
+            "days must be between 1 and {}",
+            MAX_STATS_DAYS
+        )));
+    }
+
+    let (totals, posts_per_day) = join_queries(
+        &app_state.db,
+        sqlx::query_as::<_, AdminStatsRow>(SQL_ADMIN_STATS).fetch_one(&app_state.db),
+        sqlx::query_as::<_, DailyCountRow>(SQL_POSTS_PER_DAY)
+            .bind(query.days)
+            .fetch_all(&app_state.db),
+    )
+    .await?;
+
+    Ok(Json(AdminStats::from_rows(totals, posts_per_day)))
+}
+
+// Counters of the DB_READ_RETRIES middleware since startup
+pub async fn db_retry_stats(Extension(claims): Extension<Claims>) -> Result<Json<RetryStats>, AppError> {
+    if !claims.is_admin {
+        return Err(AppError::from(ErrorCode::AdminRequired));
+    }
+
+    Ok(Json(retry_stats()))
+}
+
+// Hits and misses of the /auth/me cache since startup
+pub async fn me_cache_stats(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<MeCacheStats>, AppError> {
+    if !claims.is_admin {
+        return Err(AppError::from(ErrorCode::AdminRequired));
+    }
+
+    Ok(Json(
+        app_state
+            .me_cache
+            .as_deref()
+            .map(MeCache::stats)
+            .unwrap_or_default(),
+    ))
+}
+
+// Generates users, posts and comments loaded with COPY, to build large datasets before a run
+pub async fn seed_database(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    ValidatedJson(payload): ValidatedJson<SeedRequest>,
+) -> Result<Json<SeedResult>, AppError> {
+    if !claims.is_admin {
+        return Err(AppError::from(ErrorCode::AdminRequired));
+    }
+
+    let password_hash = hash_password(SEED_PASSWORD, app_state.auth_config.bcrypt_cost).await?;
+    let seeded = seed(&app_state.db, &payload, &password_hash).await?;
+    app_state.cache.invalidate_post_lists().await;
+    Ok(Json(seeded))
+}
+
+// Limits of the load shedding middleware, requests in flight and shed since startup
+pub async fn load_shedding_stats(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<LoadSheddingStats>, AppError> {
+    if !claims.is_admin {
+        return Err(AppError::from(ErrorCode::AdminRequired));
+    }
+
+    Ok(Json(
+        app_state
+            .load_shedding
+            .as_ref()
+            .map(LoadShedding::stats)
+            .unwrap_or_default(),
+    ))
+}
+
+// Import rows, in input order. Malformed CSV records are kept as per-row errors,
+// a malformed JSON body is rejected as a whole.
+fn parse_user_import(headers: &HeaderMap, body: &[u8]) -> Result<Vec<Result<CreateUser, String>>, AppError> {
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("");
+
+    if content_type.starts_with("text/csv") {
+        let mut reader = csv::ReaderBuilder::new().trim(csv::Trim::All).from_reader(body);
+        Ok(reader
+            .deserialize::<CreateUser>()
+            .map(|record| record.map_err(|e| format!("Invalid CSV record: {}", e)))
+            .collect())
+    } else if content_type.starts_with("application/json") {
+        let users: Vec<CreateUser> = serde_json::from_slice(body)
+            .map_err(|e| AppError::BadRequest(format!("Invalid JSON body: {}", e)))?;
+        Ok(users.into_iter().map(Ok).collect())
+    } else {
+        Err(AppError::Coded(
+            ErrorCode::UnsupportedMediaType,
+            "Content-Type must be application/json or text/csv".to_string(),
+        ))
+    }
+}
+
+// Same rules as POST /users, flattened into the row's error message
+fn validate_import_user(user: &CreateUser) -> Result<(), String> {
+    let mut errors = FieldErrors::default();
+    user.validate(&mut errors);
+    let errors = errors.into_vec();
+    if errors.is_empty() {
+        return Ok(());
+    }
+    Err(errors
+        .iter()
+        .map(|error| format!("{} {}", error.field, error.message))
+        .collect::<Vec<_>>()
+        .join(", "))
+}
+
+pub async fn import_users(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<UserImport>, AppError> {
+    if !claims.is_admin {
+        return Err(AppError::from(ErrorCode::AdminRequired));
+    }
+
+    let rows = parse_user_import(&headers, &body)?;
+    if rows.is_empty() || rows.len() > MAX_IMPORT_USERS {
+        return Err(AppError::BadRequest(format!(
+            "An import must contain between 1 and {} users",
+            MAX_IMPORT_USERS
+        )));
+    }
+
+    // Invalid rows and rows repeating an earlier username or email fail without touching the database
+    let mut errors: Vec<Option<String>> = vec![None; rows.len()];
+    let mut usernames = HashSet::new();
+    let mut emails = HashSet::new();
+    let mut pending: Vec<(usize, CreateUser)> = Vec::new();
+    for (index, row) in rows.into_iter().enumerate() {
+        match row.and_then(|user| validate_import_user(&user).map(|_| user)) {
+            Ok(user) if usernames.contains(&user.username) || emails.contains(&user.email) => {
+                errors[index] = Some("Duplicate username or email in import".to_string());
+            }
+            Ok(user) => {
+                usernames.insert(user.username.clone());
+                emails.insert(user.email.clone());
+                pending.push((index, user));
+            }
+            Err(error) => errors[index] = Some(error),
+        }
+    }
+
+    // bcrypt is CPU bound: hash on the blocking pool, at most one password per core at a time
+    let concurrency = std::thread::available_parallelism().map_or(4, |n| n.get());
+    let semaphore = Arc::new(Semaphore::new(concurrency));
+    let cost = app_state.auth_config.bcrypt_cost;
+    let mut hashing = JoinSet::new();
+    for (position, (_, user)) in pending.iter().enumerate() {
+        let semaphore = semaphore.clone();
+        let password = user.password.clone();
+        hashing.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            (position, hash_password(&password, cost).await)
+        });
+    }
+    let mut password_hashes = vec![String::new(); pending.len()];
+    while let Some(joined) = hashing.join_next().await {
+        let (position, hash) =
+            joined.map_err(|_| AppError::InternalServerError("Task join error".to_string()))?;
+        password_hashes[position] = hash?;
+    }
+
+    // Rows taken by existing users are skipped by the insert, everything else commits together
+    let mut created: HashMap<String, UserRow> = HashMap::new();
+    let mut tx = app_state.db.begin().await?;
+    for (batch, hashes) in pending
+        .chunks(IMPORT_INSERT_BATCH_SIZE)
+        .zip(password_hashes.chunks(IMPORT_INSERT_BATCH_SIZE))
+    {
+        let usernames: Vec<&str> = batch.iter().map(|(_, user)| user.username.as_str()).collect();
+        let emails: Vec<&str> = batch.iter().map(|(_, user)| user.email.as_str()).collect();
+        let user_rows: Vec<UserRow> = sqlx::query_as(SQL_IMPORT_USERS)
+            .bind(&usernames)
+            .bind(&emails)
+            .bind(hashes)
+            .fetch_all(&mut *tx)
+            .await?;
+        created.extend(user_rows.into_iter().map(|row| (row.email.clone(), row)));
+    }
+    tx.commit().await?;
+
+    // Rows that passed validation but weren't inserted clashed with an existing user
+    let mut pending_emails: Vec<Option<String>> = vec![None; errors.len()];
+    for (index, user) in pending {
+        pending_emails[index] = Some(user.email);
+    }
+    let results: Vec<UserImportResult> = errors
+        .into_iter()
+        .zip(pending_emails)
+        .enumerate()
+        .map(|(index, (error, email))| match email.and_then(|email| created.remove(&email)) {
+            Some(row) => UserImportResult::created(index, User::from(row)),
+            None => UserImportResult::failed(
+                index,
+                error.unwrap_or_else(|| "Username or email already taken".to_string()),
+            ),
+        })
+        .collect();
+
+    Ok(Json(UserImport::from_results(results)))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Health
+////////////////////////////////////////////////////////////////////////////////
+
+// The process is up and serving, nothing else is checked
+pub async fn health_live() -> Json<Liveness> {
+    Json(Liveness { status: "alive" })
+}
+
+// 503 with the same body while the database or the pool isn't ready
+pub async fn health_ready(State(app_state): State<AppState>) -> (StatusCode, Json<Readiness>) {
+    let readiness = readiness(&app_state.db).await;
+    let status = if readiness.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(readiness))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Debug
+////////////////////////////////////////////////////////////////////////////////
+
+// Pool gauges and acquire counters, DB_POOL_METRICS
+pub async fn debug_pool(State(pools): State<Pools>) -> Json<PoolStats> {
+    Json(pools.stats())
+}
+
+// The same, for a Prometheus scrape
+pub async fn pool_metrics(State(pools): State<Pools>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")],
+        pools.stats().to_prometheus(),
+    )
+}
+
+// Runtime and memory usage of the process, DEBUG_STATS
+pub async fn debug_stats(State(shard): State<Shard>) -> Json<DebugStats> {
+    Json(DebugStats::collect(shard))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Fallbacks
+////////////////////////////////////////////////////////////////////////////////
+
+pub async fn route_not_found(method: Method, uri: Uri) -> AppError {
+    AppError::Coded(
+        ErrorCode::RouteNotFound,
+        format!("No route for {} {}", method, uri.path()),
+    )
+}
+
+// axum adds the Allow header listing the path's methods
+pub async fn method_not_allowed(method: Method, uri: Uri) -> AppError {
+    AppError::Coded(
+        ErrorCode::MethodNotAllowed,
+        format!("Method {} not allowed on {}", method, uri.path()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursor_round_trips() {
+        let created_at = DateTime::from_timestamp_micros(1_700_000_000_123_456).unwrap();
+        let id = Uuid::new_v4();
+        let cursor = encode_cursor(created_at, id);
+        assert_eq!(decode_cursor(&cursor).unwrap(), (created_at, id));
+    }
+
+    #[test]
+    fn rejects_malformed_cursors() {
+        let id = Uuid::nil();
+        for cursor in [
+            String::new(),
+            "123".to_string(),
+            format!("abc_{}", id),
+            format!("{}_{}", i64::MAX, id),
+            "123_not-a-uuid".to_string(),
+            format!("_{}", id),
+        ] {
+            assert!(decode_cursor(&cursor).is_err(), "{:?}", cursor);
+        }
+    }
+
+    #[test]
+    fn empty_cursor_is_the_first_page() {
+        assert_eq!(decode_optional_cursor("").unwrap(), None);
+    }
+
+    fn if_match(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_MATCH, value.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn expected_version_from_if_match() {
+        for (value, version) in [
+            ("3", Some(3)),
+            (" \"3\" ", Some(3)),
+            ("*", None),
+            ("W/\"7-9f86d081884c7d65\"", Some(7)),
+            ("\"7-9f86d081884c7d65\"", Some(7)),
+        ] {
+            assert_eq!(expected_version(&if_match(value), Some(1)).unwrap(), version, "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn expected_version_falls_back_to_the_body() {
+        assert_eq!(expected_version(&HeaderMap::new(), Some(4)).unwrap(), Some(4));
+        assert_eq!(expected_version(&HeaderMap::new(), None).unwrap(), None);
+    }
+
+    #[test]
+    fn rejects_invalid_if_match() {
+        for value in ["abc", "W/\"9f86d081884c7d65\"", "\"\"", "3.5"] {
+            assert!(expected_version(&if_match(value), None).is_err(), "{:?}", value);
+        }
+    }
 }
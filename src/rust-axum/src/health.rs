@@ -0,0 +1,97 @@
+use serde::Serialize;
+use sqlx::PgPool;
+use std::{
+    env,
+    sync::LazyLock,
+    time::{Duration, Instant},
+};
+
+use crate::sql::SQL_HEALTH_PING;
+
+// How long GET /health/ready waits for the database, pool checkout included
+static HEALTH_DB_TIMEOUT: LazyLock<Duration> = LazyLock::new(|| {
+    Duration::from_millis(
+        env::var("HEALTH_DB_TIMEOUT_MS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|&ms| ms > 0)
+            .unwrap_or(1000),
+    )
+});
+
+// Pool connections that must be free (idle or not opened yet) to be ready, 0 skips the check
+static HEALTH_MIN_POOL_HEADROOM: LazyLock<u32> = LazyLock::new(|| {
+    env::var("HEALTH_MIN_POOL_HEADROOM")
+        .ok()
+        .and_then(|v| v.parse::<u32>().ok())
+        .unwrap_or(1)
+});
+
+#[derive(Debug, Serialize)]
+pub struct Liveness {
+    pub status: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Readiness {
+    pub ready: bool,
+    pub database: DatabaseCheck,
+    pub pool: PoolCheck,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatabaseCheck {
+    pub ok: bool,
+    pub latency_ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolCheck {
+    pub ok: bool,
+    pub max: u32,
+    pub open: u32,
+    pub idle: u32,
+    // Connections a request can get without waiting: idle ones and the ones not opened yet
+    pub headroom: u32,
+    pub min_headroom: u32,
+}
+
+// Readiness of `pool` (the primary): a query answers within HEALTH_DB_TIMEOUT_MS and the pool
+// has HEALTH_MIN_POOL_HEADROOM connections free. Headroom is read before the query takes one.
+pub async fn readiness(pool: &PgPool) -> Readiness {
+    let max = pool.options().get_max_connections();
+    let open = pool.size();
+    let idle = pool.num_idle() as u32;
+    let headroom = max.saturating_sub(open) + idle;
+    let pool_check = PoolCheck {
+        ok: headroom >= *HEALTH_MIN_POOL_HEADROOM,
+        max,
+        open,
+        idle,
+        headroom,
+        min_headroom: *HEALTH_MIN_POOL_HEADROOM,
+    };
+
+    let started = Instant::now();
+    let error = match tokio::time::timeout(*HEALTH_DB_TIMEOUT, sqlx::query(SQL_HEALTH_PING).execute(pool)).await {
+        Ok(Ok(_)) => None,
+        Ok(Err(e)) => Some(e.to_string()),
+        Err(_) => Some(format!("no answer within {} ms", HEALTH_DB_TIMEOUT.as_millis())),
+    };
+    let database = DatabaseCheck {
+        ok: error.is_none(),
+        latency_ms: (started.elapsed().as_secs_f64() * 100_000.0).round() / 100.0,
+        error,
+    };
+
+    Readiness {
+        ready: database.ok && pool_check.ok,
+        database,
+        pool: pool_check,
+    }
+}
@@ -0,0 +1,158 @@
+use axum::{
+    body::{Body, Bytes, HttpBody},
+    extract::ConnectInfo,
+    http::Request,
+    Router,
+};
+use bytes::Buf;
+use h3::server::RequestStream;
+use quinn::crypto::rustls::QuicServerConfig;
+use rustls::pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer};
+use std::{
+    env,
+    error::Error,
+    future::poll_fn,
+    net::{Ipv4Addr, SocketAddr},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tower::ServiceExt;
+
+// QUIC endpoint serving HTTP/3 next to the TCP listener, enabled by HTTP3_PORT (UDP).
+// QUIC always runs over TLS 1.3: HTTP3_CERT_PATH and HTTP3_KEY_PATH are the PEM
+// certificate chain and private key presented to clients.
+pub fn http3_from_env() -> Result<Option<quinn::Endpoint>, Box<dyn Error>> {
+    let Some(port) = env::var("HTTP3_PORT")
+        .ok()
+        .and_then(|v| v.parse::<u16>().ok())
+    else {
+        return Ok(None);
+    };
+    let (Ok(cert_path), Ok(key_path)) = (env::var("HTTP3_CERT_PATH"), env::var("HTTP3_KEY_PATH"))
+    else {
+        return Err("HTTP3_PORT requires HTTP3_CERT_PATH and HTTP3_KEY_PATH".into());
+    };
+
+    let certs = CertificateDer::pem_file_iter(&cert_path)?.collect::<Result<Vec<_>, _>>()?;
+    let key = PrivateKeyDer::from_pem_file(&key_path)?;
+
+    // rustls is built with several crypto providers here, so pick one explicitly
+    let mut tls = rustls::ServerConfig::builder_with_provider(Arc::new(
+        rustls::crypto::ring::default_provider(),
+    ))
+    .with_protocol_versions(&[&rustls::version::TLS13])?
+    .with_no_client_auth()
+    .with_single_cert(certs, key)?;
+    tls.alpn_protocols = vec![b"h3".to_vec()];
+
+    let config = quinn::ServerConfig::with_crypto(Arc::new(QuicServerConfig::try_from(tls)?));
+    let endpoint =
+        quinn::Endpoint::server(config, SocketAddr::from((Ipv4Addr::UNSPECIFIED, port)))?;
+    tracing::info!("HTTP/3 running on https://0.0.0.0:{} (UDP)", port);
+    Ok(Some(endpoint))
+}
+
+// Accepts QUIC connections until the endpoint closes, each request is run through `app`
+pub async fn serve(endpoint: quinn::Endpoint, app: Router) {
+    while let Some(incoming) = endpoint.accept().await {
+        let app = app.clone();
+        tokio::spawn(async move {
+            let remote_addr = incoming.remote_address();
+            let connection = match incoming.await {
+                Ok(connection) => connection,
+                Err(e) => {
+                    tracing::debug!("QUIC handshake with {} failed: {}", remote_addr, e);
+                    return;
+                }
+            };
+            if let Err(e) = serve_connection(connection, remote_addr, app).await {
+                tracing::debug!("HTTP/3 connection from {} closed: {}", remote_addr, e);
+            }
+        });
+    }
+}
+
+async fn serve_connection(
+    connection: quinn::Connection,
+    remote_addr: SocketAddr,
+    app: Router,
+) -> Result<(), h3::error::ConnectionError> {
+    let mut connection = h3::server::Connection::new(h3_quinn::Connection::new(connection)).await?;
+    loop {
+        match connection.accept().await {
+            Ok(Some(resolver)) => {
+                let app = app.clone();
+                tokio::spawn(async move {
+                    let result = async {
+                        let (request, stream) = resolver.resolve_request().await?;
+                        serve_request(request, stream, remote_addr, app).await
+                    };
+                    if let Err(e) = result.await {
+                        tracing::debug!("HTTP/3 request from {} failed: {}", remote_addr, e);
+                    }
+                });
+            }
+            Ok(None) => return Ok(()),
+            Err(e) if e.is_h3_no_error() => return Ok(()),
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+// Same Router as over TCP, with the client address handlers expect from ConnectInfo
+async fn serve_request(
+    request: Request<()>,
+    stream: RequestStream<h3_quinn::BidiStream<Bytes>, Bytes>,
+    remote_addr: SocketAddr,
+    app: Router,
+) -> Result<(), h3::error::StreamError> {
+    let (mut send, recv) = stream.split();
+    let mut request = request.map(|()| Body::new(RequestBody(recv)));
+    request.extensions_mut().insert(ConnectInfo(remote_addr));
+
+    let Ok(response) = app.oneshot(request).await;
+    let (parts, mut body) = response.into_parts();
+    send.send_response(axum::http::Response::from_parts(parts, ()))
+        .await?;
+
+    while let Some(frame) = poll_fn(|cx| Pin::new(&mut body).poll_frame(cx)).await {
+        let frame = match frame {
+            Ok(frame) => frame,
+            Err(e) => {
+                tracing::warn!("Response body failed over HTTP/3: {}", e);
+                send.stop_stream(h3::error::Code::H3_INTERNAL_ERROR);
+                return Ok(());
+            }
+        };
+        match frame.into_data() {
+            Ok(data) => send.send_data(data).await?,
+            Err(frame) => {
+                if let Ok(trailers) = frame.into_trailers() {
+                    send.send_trailers(trailers).await?;
+                }
+            }
+        }
+    }
+    send.finish().await
+}
+
+// Request body read from the QUIC stream as the handler consumes it
+struct RequestBody(RequestStream<h3_quinn::RecvStream, Bytes>);
+
+impl HttpBody for RequestBody {
+    type Data = Bytes;
+    type Error = h3::error::StreamError;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<http_body::Frame<Bytes>, Self::Error>>> {
+        self.0.poll_recv_data(cx).map(|data| {
+            data.map(|data| {
+                data.map(|mut data| http_body::Frame::data(data.copy_to_bytes(data.remaining())))
+            })
+            .transpose()
+        })
+    }
+}
@@ -0,0 +1,213 @@
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Request, State},
+    http::{header, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::{env, time::Duration};
+use uuid::Uuid;
+
+use crate::{
+    auth::Claims,
+    error::{AppError, ErrorCode},
+    models::IdempotencyKeyRow,
+    request_id::spawn_in_request,
+    sql::{
+        SQL_COMPLETE_IDEMPOTENCY_KEY, SQL_GET_IDEMPOTENCY_KEY, SQL_RELEASE_IDEMPOTENCY_KEY,
+        SQL_RESERVE_IDEMPOTENCY_KEY,
+    },
+};
+
+const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+// Set on responses replayed from a previous request
+const IDEMPOTENT_REPLAYED_HEADER: &str = "idempotent-replayed";
+const MAX_KEY_LENGTH: usize = 255;
+
+// Stored responses for POST requests carrying an Idempotency-Key header, per user
+#[derive(Clone)]
+pub struct Idempotency {
+    db: PgPool,
+    ttl: Duration,
+}
+
+impl Idempotency {
+    // How long a key is remembered, later retries run the request again
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    // Stores the response so retries replay it. Server errors aren't stored,
+    // the key is released so the client can retry.
+    async fn record(&self, user_id: Uuid, key: &str, response: Response) -> Response {
+        if response.status().is_server_error() {
+            self.release(user_id, key).await;
+            return response;
+        }
+
+        let (parts, body) = response.into_parts();
+        let body = match to_bytes(body, usize::MAX).await {
+            Ok(body) => body,
+            Err(e) => {
+                self.release(user_id, key).await;
+                return AppError::InternalServerError(format!("Failed to read response body: {}", e)).into_response();
+            }
+        };
+        let content_type = parts
+            .headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok());
+
+        // The request went through whatever happens here: the client still gets its response,
+        // and retries get IDEMPOTENCY_KEY_IN_PROGRESS rather than running it again
+        if let Err(e) = sqlx::query(SQL_COMPLETE_IDEMPOTENCY_KEY)
+            .bind(user_id)
+            .bind(key)
+            .bind(parts.status.as_u16() as i16)
+            .bind(content_type)
+            .bind(body.as_ref())
+            .execute(&self.db)
+            .await
+        {
+            tracing::error!("Failed to record the response for Idempotency-Key {}: {:?}", key, e);
+        }
+
+        Response::from_parts(parts, Body::from(body))
+    }
+
+    // Lets the client retry with the same key. Left reserved until it expires if this fails
+    async fn release(&self, user_id: Uuid, key: &str) {
+        if let Err(e) = sqlx::query(SQL_RELEASE_IDEMPOTENCY_KEY)
+            .bind(user_id)
+            .bind(key)
+            .execute(&self.db)
+            .await
+        {
+            tracing::error!("Failed to release Idempotency-Key {}: {:?}", key, e);
+        }
+    }
+}
+
+// IDEMPOTENCY_KEY_TTL_SECS defaults to 24 hours
+pub fn idempotency_from_env(db: PgPool) -> Idempotency {
+    let ttl_secs = env::var("IDEMPOTENCY_KEY_TTL_SECS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(24 * 60 * 60);
+
+    Idempotency {
+        db,
+        ttl: Duration::from_secs(ttl_secs),
+    }
+}
+
+// Runs after auth_middleware: keys are scoped to the authenticated user
+pub async fn idempotency_middleware(
+    State(idempotency): State<Idempotency>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    if request.method() != Method::POST {
+        return Ok(next.run(request).await);
+    }
+    let Some(key) = request.headers().get(IDEMPOTENCY_KEY_HEADER) else {
+        return Ok(next.run(request).await);
+    };
+
+    let key = key
+        .to_str()
+        .ok()
+        .filter(|key| !key.is_empty() && key.len() <= MAX_KEY_LENGTH)
+        .ok_or_else(|| AppError::BadRequest("Invalid Idempotency-Key header".to_string()))?
+        .to_string();
+    let claims = request
+        .extensions()
+        .get::<Claims>()
+        .ok_or_else(|| AppError::Unauthorized("Not authenticated".to_string()))?;
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| AppError::Coded(ErrorCode::InvalidId, "Invalid user ID".to_string()))?;
+
+    // Already capped by the body limit middleware wrapping these routes
+    let (parts, body) = request.into_parts();
+    let body = to_bytes(body, usize::MAX)
+        .await
+        .map_err(|_| AppError::BadRequest("Failed to read request body".to_string()))?;
+
+    // A retry must send the same request, reusing a key for another one is a client bug
+    let request_hash = Sha256::new()
+        .chain_update(parts.method.as_str())
+        .chain_update(b" ")
+        .chain_update(parts.uri.path_and_query().map_or("", |pq| pq.as_str()))
+        .chain_update(b"\n")
+        .chain_update(&body)
+        .finalize();
+
+    let reserved: Option<Uuid> = sqlx::query_scalar(SQL_RESERVE_IDEMPOTENCY_KEY)
+        .bind(user_id)
+        .bind(&key)
+        .bind(request_hash.as_slice())
+        .bind(idempotency.ttl.as_secs_f64())
+        .fetch_optional(&idempotency.db)
+        .await?;
+
+    if reserved.is_none() {
+        return replay(&idempotency, user_id, &key, &request_hash).await;
+    }
+
+    // Handled on its own task so the response is still recorded when the client
+    // gives up (e.g. a timed out load test request that is about to be retried)
+    let request = Request::from_parts(parts, Body::from(body));
+    let task = spawn_in_request({
+        let idempotency = idempotency.clone();
+        let key = key.clone();
+        async move {
+            let response = next.run(request).await;
+            idempotency.record(user_id, &key, response).await
+        }
+    });
+    match task.await {
+        Ok(response) => Ok(response),
+        // The handler panicked: nothing was recorded, the client may retry
+        Err(e) => {
+            idempotency.release(user_id, &key).await;
+            Err(AppError::InternalServerError(format!("Request handler failed: {}", e)))
+        }
+    }
+}
+
+async fn replay(
+    idempotency: &Idempotency,
+    user_id: Uuid,
+    key: &str,
+    request_hash: &[u8],
+) -> Result<Response, AppError> {
+    let row: Option<IdempotencyKeyRow> = sqlx::query_as(SQL_GET_IDEMPOTENCY_KEY)
+        .bind(user_id)
+        .bind(key)
+        .fetch_optional(&idempotency.db)
+        .await?;
+
+    match row {
+        Some(row) if row.request_hash != request_hash => Err(ErrorCode::IdempotencyKeyReused.into()),
+        Some(IdempotencyKeyRow {
+            status_code: Some(status_code),
+            content_type,
+            response_body,
+            ..
+        }) => {
+            let status = StatusCode::from_u16(status_code as u16)
+                .map_err(|e| AppError::InternalServerError(e.to_string()))?;
+            let mut response = (status, response_body.unwrap_or_default()).into_response();
+            let headers = response.headers_mut();
+            if let Some(content_type) = content_type.and_then(|value| HeaderValue::from_str(&value).ok()) {
+                headers.insert(header::CONTENT_TYPE, content_type);
+            }
+            headers.insert(IDEMPOTENT_REPLAYED_HEADER, HeaderValue::from_static("true"));
+            Ok(response)
+        }
+        // Still being handled, or released after a server error in the meantime
+        _ => Err(ErrorCode::IdempotencyKeyInProgress.into()),
+    }
+}
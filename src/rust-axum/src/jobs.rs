@@ -0,0 +1,81 @@
+use sqlx::PgPool;
+use std::time::Duration;
+use tokio::time::{interval, MissedTickBehavior};
+
+use crate::{
+    cache::Cache,
+    content::extract_mentions,
+    error::AppError,
+    handlers::notify_mentions,
+    models::ScheduledPostRow,
+    sql::{SQL_PUBLISH_SCHEDULED_POSTS, SQL_PURGE_IDEMPOTENCY_KEYS},
+};
+
+// Due posts published per transaction, larger backlogs are drained in several batches
+const PUBLISH_BATCH_SIZE: i64 = 100;
+
+const IDEMPOTENCY_PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+// Background job publishing scheduled drafts once their publish_at is due.
+// Runs for the lifetime of the process, failures are logged and retried on the next tick.
+pub fn spawn_scheduled_publisher(db: PgPool, cache: Cache, period: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = interval(period);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = publish_due_posts(&db, &cache).await {
+                tracing::error!("Scheduled publishing failed: {}", e);
+            }
+        }
+    });
+}
+
+async fn publish_due_posts(db: &PgPool, cache: &Cache) -> Result<(), AppError> {
+    loop {
+        // Mention notifications are sent with the publish, as for POST /posts/{post_id}/publish
+        let mut tx = db.begin().await?;
+        let published: Vec<ScheduledPostRow> = sqlx::query_as(SQL_PUBLISH_SCHEDULED_POSTS)
+            .bind(PUBLISH_BATCH_SIZE)
+            .fetch_all(&mut *tx)
+            .await?;
+        for post in &published {
+            let mentions = extract_mentions(&post.content);
+            if !mentions.is_empty() {
+                notify_mentions(&mut *tx, post.author_id, post.id, None, &mentions).await?;
+            }
+        }
+        tx.commit().await?;
+
+        if !published.is_empty() {
+            tracing::info!("Published {} scheduled posts", published.len());
+            cache.invalidate_post_lists().await;
+        }
+        if (published.len() as i64) < PUBLISH_BATCH_SIZE {
+            return Ok(());
+        }
+    }
+}
+
+// Background job deleting idempotency keys older than `ttl`.
+// Expired keys are already ignored by the middleware, this only keeps the table small.
+pub fn spawn_idempotency_key_purge(db: PgPool, ttl: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = interval(IDEMPOTENCY_PURGE_INTERVAL);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            match sqlx::query(SQL_PURGE_IDEMPOTENCY_KEYS)
+                .bind(ttl.as_secs_f64())
+                .execute(&db)
+                .await
+            {
+                Ok(result) if result.rows_affected() > 0 => {
+                    tracing::info!("Purged {} expired idempotency keys", result.rows_affected());
+                }
+                Ok(_) => {}
+                Err(e) => tracing::error!("Idempotency key purge failed: {}", e),
+            }
+        }
+    });
+}
@@ -0,0 +1,155 @@
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    env,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::error::{AppError, ErrorCode};
+
+// In-flight request limits: past them requests are rejected at once with a 503 OVERLOADED
+// and Retry-After, rather than queueing for pool connections until every latency collapses.
+// - MAX_IN_FLIGHT_REQUESTS: requests handled at once, all routes together
+// - ROUTE_IN_FLIGHT_LIMITS: per-route limits, e.g. "GET /posts=200,POST /posts=50",
+//   routes written as in the router ("GET /posts/{post_id}")
+#[derive(Clone)]
+pub struct LoadShedding {
+    global: Option<Arc<InFlightLimit>>,
+    routes: Arc<HashMap<String, InFlightLimit>>,
+}
+
+struct InFlightLimit {
+    max: usize,
+    permits: Arc<Semaphore>,
+    shed: AtomicU64,
+}
+
+impl InFlightLimit {
+    fn new(max: usize) -> Self {
+        Self {
+            max,
+            permits: Arc::new(Semaphore::new(max)),
+            shed: AtomicU64::new(0),
+        }
+    }
+
+    fn try_acquire(&self) -> Option<OwnedSemaphorePermit> {
+        let permit = self.permits.clone().try_acquire_owned().ok();
+        if permit.is_none() {
+            self.shed.fetch_add(1, Ordering::Relaxed);
+        }
+        permit
+    }
+
+    fn stats(&self) -> InFlightStats {
+        InFlightStats {
+            limit: self.max,
+            in_flight: self.max - self.permits.available_permits(),
+            shed: self.shed.load(Ordering::Relaxed),
+        }
+    }
+}
+
+pub fn load_shedding_from_env() -> Option<LoadShedding> {
+    let global = env::var("MAX_IN_FLIGHT_REQUESTS")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .filter(|&max| max > 0);
+
+    let mut routes = HashMap::new();
+    let raw_routes = env::var("ROUTE_IN_FLIGHT_LIMITS").unwrap_or_default();
+    for entry in raw_routes.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let parsed = entry.rsplit_once('=').and_then(|(route, max)| {
+            let (method, path) = route.trim().split_once(' ')?;
+            let max = max.trim().parse::<usize>().ok().filter(|&max| max > 0)?;
+            Some((format!("{} {}", method.to_uppercase(), path.trim()), max))
+        });
+        match parsed {
+            Some((route, max)) => {
+                routes.insert(route, InFlightLimit::new(max));
+            }
+            None => tracing::warn!("Invalid ROUTE_IN_FLIGHT_LIMITS entry {:?}, ignored", entry),
+        }
+    }
+
+    if global.is_none() && routes.is_empty() {
+        return None;
+    }
+    tracing::info!(
+        "Load shedding enabled: {} in flight overall, {} route limit(s)",
+        global.map_or_else(|| "unlimited".to_string(), |max| max.to_string()),
+        routes.len()
+    );
+    Some(LoadShedding {
+        global: global.map(|max| Arc::new(InFlightLimit::new(max))),
+        routes: Arc::new(routes),
+    })
+}
+
+#[derive(Debug, Serialize)]
+pub struct InFlightStats {
+    pub limit: usize,
+    pub in_flight: usize,
+    // Requests rejected since startup
+    pub shed: u64,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct LoadSheddingStats {
+    pub global: Option<InFlightStats>,
+    pub routes: BTreeMap<String, InFlightStats>,
+}
+
+impl LoadShedding {
+    // Served by GET /admin/load-shedding
+    pub fn stats(&self) -> LoadSheddingStats {
+        LoadSheddingStats {
+            global: self.global.as_ref().map(|limit| limit.stats()),
+            routes: self
+                .routes
+                .iter()
+                .map(|(route, limit)| (route.clone(), limit.stats()))
+                .collect(),
+        }
+    }
+}
+
+fn overloaded() -> Response {
+    AppError::from(ErrorCode::Overloaded).into_response()
+}
+
+// The route's permit first, so a request shed by its route doesn't hold a global one.
+// Permits are released once the handler has returned its response.
+pub async fn load_shed_middleware(
+    State(shedding): State<LoadShedding>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let route_limit = request
+        .extensions()
+        .get::<MatchedPath>()
+        .filter(|_| !shedding.routes.is_empty())
+        .and_then(|path| {
+            shedding
+                .routes
+                .get(&format!("{} {}", request.method(), path.as_str()))
+        });
+    let _route_permit = match route_limit.map(InFlightLimit::try_acquire) {
+        Some(None) => return overloaded(),
+        permit => permit.flatten(),
+    };
+    let _global_permit = match shedding.global.as_deref().map(InFlightLimit::try_acquire) {
+        Some(None) => return overloaded(),
+        permit => permit.flatten(),
+    };
+    next.run(request).await
+}
@@ -0,0 +1,27 @@
+use std::{env, sync::LazyLock};
+
+// Format of the logs written to stdout, LOG_FORMAT:
+// - `text`: tracing's human-readable lines (default)
+// - `json`: one JSON object per line for log pipelines (Loki, Elastic), with the request span's
+//   fields (request_id, method, route) and, unless ACCESS_LOG=false, the access log
+pub static JSON_LOGS: LazyLock<bool> = LazyLock::new(|| match env::var("LOG_FORMAT").as_deref() {
+    Ok("json") => true,
+    Ok("text") | Err(_) => false,
+    Ok(other) => {
+        // Read while logging is being set up
+        eprintln!("Unknown LOG_FORMAT {:?}, using text", other);
+        false
+    }
+});
+
+// A `request completed` line per request (access_log.rs), ACCESS_LOG=true / false,
+// on by default with JSON logs only. Disabled, the middleware isn't even in the stack.
+pub static ACCESS_LOG: LazyLock<bool> = LazyLock::new(|| match env::var("ACCESS_LOG").as_deref() {
+    Ok("true" | "1") => true,
+    Ok("false" | "0") => false,
+    Err(_) => *JSON_LOGS,
+    Ok(other) => {
+        eprintln!("Unknown ACCESS_LOG {:?}, using {}", other, *JSON_LOGS);
+        *JSON_LOGS
+    }
+});
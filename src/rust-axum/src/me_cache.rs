@@ -0,0 +1,93 @@
+use lru::LruCache;
+use serde::Serialize;
+use std::{
+    env,
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+use uuid::Uuid;
+
+use crate::models::User;
+
+// Short-lived cache of the GET /auth/me answers read from the database (AUTH_ME_FROM_DB, or
+// tokens without a profile), keyed by user id and enabled by AUTH_ME_CACHE_TTL_MS.
+// Profile changes made through this instance drop their user's entry, changes made
+// elsewhere show up after the TTL, or at once with CACHE_NOTIFY.
+pub struct MeCache {
+    ttl: Duration,
+    entries: Mutex<LruCache<Uuid, (User, Instant)>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+pub fn me_cache_from_env() -> Option<MeCache> {
+    let ttl_ms = env::var("AUTH_ME_CACHE_TTL_MS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .filter(|&ms| ms > 0)?;
+    let size = env::var("AUTH_ME_CACHE_SIZE")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .and_then(NonZeroUsize::new)
+        .unwrap_or(NonZeroUsize::new(10_000).expect("non-zero"));
+
+    tracing::info!("/auth/me cache enabled ({} entries, TTL {} ms)", size, ttl_ms);
+    Some(MeCache {
+        ttl: Duration::from_millis(ttl_ms),
+        entries: Mutex::new(LruCache::new(size)),
+        hits: AtomicU64::new(0),
+        misses: AtomicU64::new(0),
+    })
+}
+
+// Served by GET /admin/me-cache, counters since startup
+#[derive(Debug, Default, Serialize)]
+pub struct MeCacheStats {
+    pub enabled: bool,
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl MeCache {
+    pub fn get(&self, user_id: Uuid) -> Option<User> {
+        let cached = {
+            let mut entries = self.entries.lock().expect("me cache lock poisoned");
+            entries
+                .get(&user_id)
+                .filter(|(_, fetched_at)| fetched_at.elapsed() < self.ttl)
+                .map(|(user, _)| user.clone())
+        };
+        let counter = if cached.is_some() { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+        cached
+    }
+
+    pub fn put(&self, user: &User) {
+        self.entries
+            .lock()
+            .expect("me cache lock poisoned")
+            .put(user.id, (user.clone(), Instant::now()));
+    }
+
+    pub fn invalidate(&self, user_id: Uuid) {
+        self.entries.lock().expect("me cache lock poisoned").pop(&user_id);
+    }
+
+    pub fn clear(&self) {
+        self.entries.lock().expect("me cache lock poisoned").clear();
+    }
+
+    pub fn stats(&self) -> MeCacheStats {
+        MeCacheStats {
+            enabled: true,
+            entries: self.entries.lock().expect("me cache lock poisoned").len(),
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+}
@@ -1,6 +1,11 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
+
+use crate::{
+    auth::{bio_hash, TokenProfile},
+    timestamp,
+};
 
 // Request Models
 #[derive(Debug, Deserialize)]
@@ -9,6 +14,11 @@ pub struct LoginCredentials {
     pub password: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct RotateSecret {
+    pub secret: String,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct CreateUser {
     pub username: String,
@@ -19,11 +29,70 @@ pub struct CreateUser {
 #[derive(Debug, Deserialize)]
 pub struct UpdateUser {
     pub bio: Option<String>,
+    // Optimistic concurrency check, same as an If-Match header
+    pub version: Option<i32>,
 }
 
+// PUT /users/me, omitted fields are left unchanged
 #[derive(Debug, Deserialize)]
+pub struct UpdateProfile {
+    pub username: Option<String>,
+    pub bio: Option<String>,
+    pub version: Option<i32>,
+}
+
+// Drafts are only visible to their author until published
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PostStatus {
+    Draft,
+    #[default]
+    Published,
+}
+
+impl PostStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PostStatus::Draft => "draft",
+            PostStatus::Published => "published",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct PostCreate {
     pub content: String,
+    #[serde(default)]
+    pub status: PostStatus,
+    // Saves the post as a draft that the scheduled publishing job publishes at this time
+    pub publish_at: Option<DateTime<Utc>>,
+}
+
+impl PostCreate {
+    pub fn effective_status(&self) -> PostStatus {
+        if self.publish_at.is_some() {
+            PostStatus::Draft
+        } else {
+            self.status
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PostBatchCreate {
+    pub posts: Vec<PostCreate>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PostLookup {
+    pub ids: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdatePost {
+    pub content: String,
+    pub version: Option<i32>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -31,46 +100,294 @@ pub struct CommentCreate {
     pub content: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct UpdateComment {
+    pub content: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversationCreate {
+    pub participant_ids: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MessageCreate {
+    pub content: String,
+}
+
 // Response Models
 #[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct LoginResponse {
-    #[serde(rename = "accessToken")]
     pub access_token: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct User {
-    pub id: String,
+    pub id: Uuid,
     pub username: String,
     pub email: String,
     pub bio: Option<String>,
-    #[serde(rename = "createdAt")]
+    pub avatar_url: Option<String>,
+    #[serde(serialize_with = "timestamp::serialize")]
     pub created_at: DateTime<Utc>,
+    // Incremented on every update, see If-Match
+    pub version: i32,
 }
 
+// Search result, email is only disclosed to admins
 #[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserSearchResult {
+    pub id: Uuid,
+    pub username: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    pub bio: Option<String>,
+    pub avatar_url: Option<String>,
+    #[serde(serialize_with = "timestamp::serialize")]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Post {
-    pub id: String,
-    #[serde(rename = "authorId")]
-    pub author_id: String,
+    pub id: Uuid,
+    pub author_id: Uuid,
     pub content: String,
-    #[serde(rename = "likeCount")]
+    pub status: String,
+    // Set on drafts waiting for the scheduled publishing job
+    #[serde(serialize_with = "timestamp::serialize_option")]
+    pub publish_at: Option<DateTime<Utc>>,
     pub like_count: i64,
-    #[serde(rename = "createdAt")]
+    pub comment_count: i64,
+    #[serde(serialize_with = "timestamp::serialize")]
     pub created_at: DateTime<Utc>,
+    #[serde(serialize_with = "timestamp::serialize_option")]
+    pub updated_at: Option<DateTime<Utc>>,
+    pub version: i32,
+    pub repost_count: i64,
+    pub repost_of: Option<Box<Post>>,
+    // Only present when the request is authenticated
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub liked_by_me: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Bookmark {
+    #[serde(serialize_with = "timestamp::serialize")]
+    pub bookmarked_at: DateTime<Utc>,
+    pub post: Post,
+}
+
+// Entry of a user's activity timeline, discriminated by `type`
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase", rename_all_fields = "camelCase")]
+pub enum Activity {
+    Post {
+        id: Uuid,
+        content: String,
+        #[serde(serialize_with = "timestamp::serialize")]
+        created_at: DateTime<Utc>,
+    },
+    Repost {
+        id: Uuid,
+        post_id: Uuid,
+        #[serde(serialize_with = "timestamp::serialize")]
+        created_at: DateTime<Utc>,
+    },
+    Comment {
+        id: Uuid,
+        post_id: Uuid,
+        content: String,
+        #[serde(serialize_with = "timestamp::serialize")]
+        created_at: DateTime<Utc>,
+    },
+    Like {
+        post_id: Uuid,
+        #[serde(serialize_with = "timestamp::serialize")]
+        created_at: DateTime<Utc>,
+    },
 }
 
 #[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Comment {
-    pub id: String,
-    #[serde(rename = "authorId")]
-    pub author_id: String,
-    pub post_id: String,
+    pub id: Uuid,
+    pub author_id: Uuid,
+    pub post_id: Uuid,
     pub content: String,
-    #[serde(rename = "createdAt")]
+    #[serde(serialize_with = "timestamp::serialize")]
     pub created_at: DateTime<Utc>,
 }
 
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Notification {
+    pub id: Uuid,
+    pub kind: String,
+    pub actor_id: Uuid,
+    pub post_id: Option<Uuid>,
+    pub comment_id: Option<Uuid>,
+    pub read: bool,
+    #[serde(serialize_with = "timestamp::serialize")]
+    pub created_at: DateTime<Utc>,
+}
+
+// Outcome of one row of POST /admin/users/import, `index` is its position in the input
+#[derive(Debug, Serialize)]
+pub struct UserImportResult {
+    pub index: usize,
+    pub status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<User>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl UserImportResult {
+    pub fn created(index: usize, user: User) -> Self {
+        Self {
+            index,
+            status: "created",
+            user: Some(user),
+            error: None,
+        }
+    }
+
+    pub fn failed(index: usize, error: String) -> Self {
+        Self {
+            index,
+            status: "failed",
+            user: None,
+            error: Some(error),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct UserImport {
+    pub created: usize,
+    pub failed: usize,
+    pub results: Vec<UserImportResult>,
+}
+
+impl UserImport {
+    pub fn from_results(results: Vec<UserImportResult>) -> Self {
+        let created = results.iter().filter(|result| result.user.is_some()).count();
+        Self {
+            created,
+            failed: results.len() - created,
+            results,
+        }
+    }
+}
+
+// POST /admin/seed: rows generated of each kind
+#[derive(Debug, Deserialize)]
+pub struct SeedRequest {
+    #[serde(default)]
+    pub users: u32,
+    #[serde(default)]
+    pub posts: u32,
+    #[serde(default)]
+    pub comments: u32,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SeedResult {
+    pub users: u64,
+    pub posts: u64,
+    pub comments: u64,
+    // Shared password of the generated users
+    pub password: &'static str,
+    pub elapsed_ms: u128,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminStats {
+    pub total_users: i64,
+    pub total_posts: i64,
+    pub total_comments: i64,
+    pub total_likes: i64,
+    pub total_follows: i64,
+    pub total_bookmarks: i64,
+    pub total_messages: i64,
+    pub posts_per_day: Vec<DailyCount>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DailyCount {
+    pub date: NaiveDate,
+    pub count: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Count {
+    pub count: i64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnreadCount {
+    pub unread_count: i64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Conversation {
+    pub id: Uuid,
+    pub participant_ids: Vec<Uuid>,
+    #[serde(serialize_with = "timestamp::serialize")]
+    pub created_at: DateTime<Utc>,
+    #[serde(serialize_with = "timestamp::serialize_option")]
+    pub last_message_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Message {
+    pub id: Uuid,
+    pub conversation_id: Uuid,
+    pub sender_id: Uuid,
+    pub content: String,
+    #[serde(serialize_with = "timestamp::serialize")]
+    pub created_at: DateTime<Utc>,
+}
+
+// Keyset-paginated listing, `next_cursor` is None on the last page
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CursorPage<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+// Offset-paginated listing with its total, `next_cursor` continues after it in keyset mode (None on the last page)
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Page<T> {
+    pub data: Vec<T>,
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+    pub next_cursor: Option<String>,
+}
+
+// Bare array by default, envelope when the client asks for one, cursor page in keyset mode
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum Listing<T> {
+    Items(Vec<T>),
+    Page(Page<T>),
+    Cursor(CursorPage<T>),
+}
+
 // Database row structs
 #[derive(Debug, sqlx::FromRow)]
 pub struct UserRow {
@@ -78,7 +395,9 @@ pub struct UserRow {
     pub username: String,
     pub email: String,
     pub bio: Option<String>,
+    pub avatar_url: Option<String>,
     pub created_at: DateTime<Utc>,
+    pub version: i32,
 }
 
 #[derive(Debug, sqlx::FromRow)]
@@ -87,7 +406,39 @@ pub struct PostRow {
     pub author_id: Uuid,
     pub content: String,
     pub created_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub status: String,
+    pub publish_at: Option<DateTime<Utc>>,
+    pub version: i32,
     pub like_count: Option<i64>,
+    pub comment_count: Option<i64>,
+    pub repost_count: Option<i64>,
+    pub reposted_post_id: Option<Uuid>,
+    pub original_author_id: Option<Uuid>,
+    pub original_content: Option<String>,
+    pub original_created_at: Option<DateTime<Utc>>,
+    pub original_updated_at: Option<DateTime<Utc>>,
+    pub original_version: Option<i32>,
+    pub original_like_count: Option<i64>,
+    pub original_comment_count: Option<i64>,
+    pub original_repost_count: Option<i64>,
+    pub liked_by_me: Option<bool>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct ActivityRow {
+    pub kind: String,
+    pub id: Uuid,
+    pub post_id: Uuid,
+    pub content: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct BookmarkRow {
+    pub bookmarked_at: DateTime<Utc>,
+    #[sqlx(flatten)]
+    pub post: PostRow,
 }
 
 #[derive(Debug, sqlx::FromRow)]
@@ -95,7 +446,26 @@ pub struct PostCreateRow {
     pub id: Uuid,
     pub author_id: Uuid,
     pub content: String,
+    pub status: String,
+    pub publish_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
+    pub version: i32,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct IdempotencyKeyRow {
+    pub request_hash: Vec<u8>,
+    pub status_code: Option<i16>,
+    pub content_type: Option<String>,
+    pub response_body: Option<Vec<u8>>,
+}
+
+// Post published by the scheduled publishing job
+#[derive(Debug, sqlx::FromRow)]
+pub struct ScheduledPostRow {
+    pub id: Uuid,
+    pub author_id: Uuid,
+    pub content: String,
 }
 
 #[derive(Debug, sqlx::FromRow)]
@@ -107,21 +477,147 @@ pub struct CommentRow {
     pub created_at: DateTime<Utc>,
 }
 
+// Row of a comment listing joined to its post, the comment columns are NULL when the post has none
+#[derive(Debug, sqlx::FromRow)]
+pub struct PostCommentRow {
+    pub id: Option<Uuid>,
+    pub author_id: Option<Uuid>,
+    pub post_id: Option<Uuid>,
+    pub content: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+impl PostCommentRow {
+    pub fn comment(self) -> Option<CommentRow> {
+        Some(CommentRow {
+            id: self.id?,
+            author_id: self.author_id?,
+            post_id: self.post_id?,
+            content: self.content?,
+            created_at: self.created_at?,
+        })
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct NotificationRow {
+    pub id: Uuid,
+    pub kind: String,
+    pub actor_id: Uuid,
+    pub post_id: Option<Uuid>,
+    pub comment_id: Option<Uuid>,
+    pub read: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct ConversationRow {
+    pub id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub last_message_at: Option<DateTime<Utc>>,
+    pub participant_ids: Vec<Uuid>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct MessageRow {
+    pub id: Uuid,
+    pub conversation_id: Uuid,
+    pub sender_id: Uuid,
+    pub content: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct AdminStatsRow {
+    pub total_users: i64,
+    pub total_posts: i64,
+    pub total_comments: i64,
+    pub total_likes: i64,
+    pub total_follows: i64,
+    pub total_bookmarks: i64,
+    pub total_messages: i64,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct DailyCountRow {
+    pub day: NaiveDate,
+    pub post_count: i64,
+}
+
 #[derive(Debug, sqlx::FromRow)]
 pub struct LoginRow {
     pub id: Uuid,
+    pub username: String,
+    pub email: String,
+    pub bio: Option<String>,
+    pub avatar_url: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub version: i32,
     pub password_hash: String,
     pub is_admin: bool,
 }
 
+#[derive(Debug, sqlx::FromRow)]
+pub struct ImpersonationRow {
+    pub id: Uuid,
+    pub username: String,
+    pub email: String,
+    pub bio: Option<String>,
+    pub avatar_url: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub version: i32,
+    pub is_admin: bool,
+}
+
 // Conversion implementations
+impl From<&LoginRow> for TokenProfile {
+    fn from(row: &LoginRow) -> Self {
+        Self {
+            username: row.username.clone(),
+            email: row.email.clone(),
+            bio_hash: row.bio.as_deref().map(bio_hash),
+            avatar_url: row.avatar_url.clone(),
+            created_at: row.created_at,
+            version: row.version,
+        }
+    }
+}
+
+impl From<&ImpersonationRow> for TokenProfile {
+    fn from(row: &ImpersonationRow) -> Self {
+        Self {
+            username: row.username.clone(),
+            email: row.email.clone(),
+            bio_hash: row.bio.as_deref().map(bio_hash),
+            avatar_url: row.avatar_url.clone(),
+            created_at: row.created_at,
+            version: row.version,
+        }
+    }
+}
+
 impl From<UserRow> for User {
     fn from(row: UserRow) -> Self {
         Self {
-            id: row.id.to_string(),
+            id: row.id,
             username: row.username,
             email: row.email,
             bio: row.bio,
+            avatar_url: row.avatar_url,
+            created_at: row.created_at,
+            version: row.version,
+        }
+    }
+}
+
+impl UserSearchResult {
+    pub fn from_row(row: UserRow, include_email: bool) -> Self {
+        Self {
+            id: row.id,
+            username: row.username,
+            email: include_email.then_some(row.email),
+            bio: row.bio,
+            avatar_url: row.avatar_url,
             created_at: row.created_at,
         }
     }
@@ -129,12 +625,45 @@ impl From<UserRow> for User {
 
 impl From<PostRow> for Post {
     fn from(row: PostRow) -> Self {
+        // The original post is embedded one level deep: reposts always point at an original
+        let repost_of = match (
+            row.reposted_post_id,
+            row.original_author_id,
+            row.original_content,
+            row.original_created_at,
+        ) {
+            (Some(id), Some(author_id), Some(content), Some(created_at)) => Some(Box::new(Post {
+                id,
+                author_id,
+                content,
+                // Only published posts can be reposted
+                status: PostStatus::Published.as_str().to_string(),
+                publish_at: None,
+                like_count: row.original_like_count.unwrap_or(0),
+                comment_count: row.original_comment_count.unwrap_or(0),
+                created_at,
+                updated_at: row.original_updated_at,
+                version: row.original_version.unwrap_or(1),
+                repost_count: row.original_repost_count.unwrap_or(0),
+                repost_of: None,
+                liked_by_me: None,
+            })),
+            _ => None,
+        };
         Self {
-            id: row.id.to_string(),
-            author_id: row.author_id.to_string(),
+            id: row.id,
+            author_id: row.author_id,
             content: row.content,
+            status: row.status,
+            publish_at: row.publish_at,
             like_count: row.like_count.unwrap_or(0),
+            comment_count: row.comment_count.unwrap_or(0),
             created_at: row.created_at,
+            updated_at: row.updated_at,
+            version: row.version,
+            repost_count: row.repost_count.unwrap_or(0),
+            repost_of,
+            liked_by_me: row.liked_by_me,
         }
     }
 }
@@ -142,11 +671,56 @@ impl From<PostRow> for Post {
 impl From<PostCreateRow> for Post {
     fn from(row: PostCreateRow) -> Self {
         Self {
-            id: row.id.to_string(),
-            author_id: row.author_id.to_string(),
+            id: row.id,
+            author_id: row.author_id,
             content: row.content,
+            status: row.status,
+            publish_at: row.publish_at,
             like_count: 0, // New posts always have 0 likes
+            comment_count: 0,
             created_at: row.created_at,
+            updated_at: None,
+            version: row.version,
+            repost_count: 0,
+            repost_of: None,
+            liked_by_me: Some(false),
+        }
+    }
+}
+
+impl From<ActivityRow> for Activity {
+    fn from(row: ActivityRow) -> Self {
+        // kind is one of the literals selected by users/activity.sql
+        match row.kind.as_str() {
+            "post" => Activity::Post {
+                id: row.id,
+                content: row.content.unwrap_or_default(),
+                created_at: row.created_at,
+            },
+            "repost" => Activity::Repost {
+                id: row.id,
+                post_id: row.post_id,
+                created_at: row.created_at,
+            },
+            "comment" => Activity::Comment {
+                id: row.id,
+                post_id: row.post_id,
+                content: row.content.unwrap_or_default(),
+                created_at: row.created_at,
+            },
+            _ => Activity::Like {
+                post_id: row.post_id,
+                created_at: row.created_at,
+            },
+        }
+    }
+}
+
+impl From<BookmarkRow> for Bookmark {
+    fn from(row: BookmarkRow) -> Self {
+        Self {
+            bookmarked_at: row.bookmarked_at,
+            post: Post::from(row.post),
         }
     }
 }
@@ -154,11 +728,69 @@ impl From<PostCreateRow> for Post {
 impl From<CommentRow> for Comment {
     fn from(row: CommentRow) -> Self {
         Self {
-            id: row.id.to_string(),
-            author_id: row.author_id.to_string(),
-            post_id: row.post_id.to_string(),
+            id: row.id,
+            author_id: row.author_id,
+            post_id: row.post_id,
             content: row.content,
             created_at: row.created_at,
         }
     }
 }
+
+impl From<NotificationRow> for Notification {
+    fn from(row: NotificationRow) -> Self {
+        Self {
+            id: row.id,
+            kind: row.kind,
+            actor_id: row.actor_id,
+            post_id: row.post_id,
+            comment_id: row.comment_id,
+            read: row.read,
+            created_at: row.created_at,
+        }
+    }
+}
+
+impl From<ConversationRow> for Conversation {
+    fn from(row: ConversationRow) -> Self {
+        Self {
+            id: row.id,
+            participant_ids: row.participant_ids,
+            created_at: row.created_at,
+            last_message_at: row.last_message_at,
+        }
+    }
+}
+
+impl From<MessageRow> for Message {
+    fn from(row: MessageRow) -> Self {
+        Self {
+            id: row.id,
+            conversation_id: row.conversation_id,
+            sender_id: row.sender_id,
+            content: row.content,
+            created_at: row.created_at,
+        }
+    }
+}
+
+impl AdminStats {
+    pub fn from_rows(row: AdminStatsRow, posts_per_day: Vec<DailyCountRow>) -> Self {
+        Self {
+            total_users: row.total_users,
+            total_posts: row.total_posts,
+            total_comments: row.total_comments,
+            total_likes: row.total_likes,
+            total_follows: row.total_follows,
+            total_bookmarks: row.total_bookmarks,
+            total_messages: row.total_messages,
+            posts_per_day: posts_per_day
+                .into_iter()
+                .map(|day| DailyCount {
+                    date: day.day,
+                    count: day.post_count,
+                })
+                .collect(),
+        }
+    }
+}
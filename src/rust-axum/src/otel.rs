@@ -0,0 +1,169 @@
+use opentelemetry::{
+    trace::{Span as _, SpanKind, Tracer as _, TracerProvider as _},
+    Context as OtelContext, KeyValue,
+};
+use opentelemetry_otlp::SpanExporter;
+use opentelemetry_sdk::{
+    trace::{SdkTracer, SdkTracerProvider},
+    Resource,
+};
+use std::{
+    collections::HashMap,
+    env,
+    fmt::Debug,
+    time::{Duration, SystemTime},
+};
+use tracing::{
+    field::{Field, Visit},
+    level_filters::LevelFilter,
+    Event, Subscriber,
+};
+use tracing_subscriber::{
+    filter::Targets,
+    layer::{Context, Layer},
+    registry::LookupSpan,
+};
+
+use crate::sql::STATEMENT_NAMES;
+
+// OTLP export (HTTP, protobuf) of the request spans and of a span per database statement,
+// enabled in otel builds by OTEL_EXPORTER_OTLP_ENDPOINT or OTEL_EXPORTER_OTLP_TRACES_ENDPOINT.
+// The exporter, sampler (OTEL_TRACES_SAMPLER), batching (OTEL_BSP_*) and resource
+// (OTEL_SERVICE_NAME, OTEL_RESOURCE_ATTRIBUTES) read the other standard variables.
+pub fn otel_from_env<S>() -> Option<impl Layer<S>>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    let disabled = env::var("OTEL_SDK_DISABLED").is_ok_and(|v| v == "true");
+    let endpoint = env::var("OTEL_EXPORTER_OTLP_TRACES_ENDPOINT")
+        .or_else(|_| env::var("OTEL_EXPORTER_OTLP_ENDPOINT"))
+        .ok()
+        .filter(|_| !disabled)?;
+
+    // Logging isn't set up yet
+    let exporter = match SpanExporter::builder().with_http().build() {
+        Ok(exporter) => exporter,
+        Err(e) => {
+            eprintln!("OpenTelemetry export to {} disabled: {}", endpoint, e);
+            return None;
+        }
+    };
+    let mut resource = Resource::builder();
+    if env::var_os("OTEL_SERVICE_NAME").is_none() {
+        resource = resource.with_service_name("rust-axum");
+    }
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(resource.build())
+        .build();
+    let tracer = provider.tracer("rust-axum-api");
+    eprintln!("OpenTelemetry spans exported to {}", endpoint);
+
+    // Request spans come from this crate, statements are logged by sqlx at debug level
+    let spans = Targets::new().with_target("rust_axum_api", LevelFilter::INFO);
+    let statements = Targets::new().with_target("sqlx::query", LevelFilter::DEBUG);
+    Some(
+        tracing_opentelemetry::layer()
+            .with_tracer(tracer.clone())
+            .with_filter(spans)
+            .and_then(StatementSpans::new(tracer).with_filter(statements)),
+    )
+}
+
+// Turns the event sqlx logs once a statement completes into a client span, ending now and
+// started its duration earlier, under the span the statement ran in (whose context the
+// OpenTelemetry layer makes current while it is entered). Statements of sql.rs
+// are named after their constant (SQL_SELECT_POSTS -> select_posts), queries built on one
+// of them after the longest, others after sqlx's summary (first words of the query).
+struct StatementSpans {
+    tracer: SdkTracer,
+    names: HashMap<&'static str, String>,
+}
+
+impl StatementSpans {
+    fn new(tracer: SdkTracer) -> Self {
+        let names = STATEMENT_NAMES
+            .iter()
+            .map(|(name, sql)| (sql.trim(), name.trim_start_matches("SQL_").to_lowercase()))
+            .collect();
+        Self { tracer, names }
+    }
+
+    fn name(&self, sql: &str) -> Option<&str> {
+        if let Some(name) = self.names.get(sql) {
+            return Some(name);
+        }
+        self.names
+            .iter()
+            .filter(|(base, _)| sql.starts_with(*base))
+            .max_by_key(|(base, _)| base.len())
+            .map(|(_, name)| name.as_str())
+    }
+}
+
+// Fields of sqlx's statement event
+#[derive(Default)]
+struct Statement {
+    summary: String,
+    // Empty when the summary is the whole statement
+    sql: String,
+    elapsed_secs: f64,
+    rows_returned: u64,
+    rows_affected: u64,
+}
+
+impl Visit for Statement {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "summary" => self.summary = value.trim_end_matches(" …").to_string(),
+            "db.statement" => self.sql = value.trim().to_string(),
+            _ => {}
+        }
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        if field.name() == "elapsed_secs" {
+            self.elapsed_secs = value;
+        }
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        match field.name() {
+            "rows_returned" => self.rows_returned = value,
+            "rows_affected" => self.rows_affected = value,
+            _ => {}
+        }
+    }
+
+    fn record_debug(&mut self, _field: &Field, _value: &dyn Debug) {}
+}
+
+impl<S> Layer<S> for StatementSpans
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut statement = Statement::default();
+        event.record(&mut statement);
+        if statement.sql.is_empty() {
+            statement.sql = statement.summary.clone();
+        }
+
+        let name = self.name(&statement.sql).unwrap_or(&statement.summary).to_string();
+        let end = SystemTime::now();
+        let start = end - Duration::from_secs_f64(statement.elapsed_secs);
+        let mut span = self
+            .tracer
+            .span_builder(name)
+            .with_kind(SpanKind::Client)
+            .with_start_time(start)
+            .with_attributes([
+                KeyValue::new("db.system.name", "postgresql"),
+                KeyValue::new("db.query.text", statement.sql),
+                KeyValue::new("db.response.returned_rows", statement.rows_returned as i64),
+                KeyValue::new("db.response.affected_rows", statement.rows_affected as i64),
+            ])
+            .start_with_context(&self.tracer, &OtelContext::current());
+        span.end_with_timestamp(end);
+    }
+}
@@ -0,0 +1,19 @@
+use sqlx::{Error, PgPool};
+use std::future::Future;
+
+// Runs two independent queries of a handler, concurrently on separate connections of `pool`
+// when two can be had without waiting, one after the other otherwise: under load a request
+// then holds a single connection at a time instead of queueing for a second one.
+// Both queries must run against `pool`; sqlx can't pipeline them on a single connection.
+pub async fn join_queries<A, B>(
+    pool: &PgPool,
+    first: impl Future<Output = Result<A, Error>>,
+    second: impl Future<Output = Result<B, Error>>,
+) -> Result<(A, B), Error> {
+    let unopened = pool.options().get_max_connections().saturating_sub(pool.size());
+    if pool.num_idle() as u32 + unopened >= 2 {
+        tokio::try_join!(first, second)
+    } else {
+        Ok((first.await?, second.await?))
+    }
+}
@@ -0,0 +1,266 @@
+use serde::Serialize;
+use sqlx::PgPool;
+use std::{
+    env,
+    fmt::Write,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, LazyLock,
+    },
+    time::Duration,
+};
+use tokio::time::{interval, MissedTickBehavior};
+#[cfg(not(feature = "no-tracing"))]
+use tracing::{
+    field::{Field, Visit},
+    level_filters::LevelFilter,
+    Event, Subscriber,
+};
+#[cfg(not(feature = "no-tracing"))]
+use tracing_subscriber::{
+    filter::Targets,
+    layer::{Context, Layer},
+    registry::LookupSpan,
+};
+
+// Pool metrics, enabled by DB_POOL_METRICS: open and idle connections of each pool, the time
+// requests wait for a connection and the acquire timeouts, served by GET /debug/pool (JSON)
+// and GET /metrics (Prometheus), with a warning logged while a pool is saturated.
+// Wait times come from the event sqlx logs after each acquire, compiled out by no-tracing.
+pub static DB_POOL_METRICS: LazyLock<bool> = LazyLock::new(|| {
+    env::var("DB_POOL_METRICS")
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(false)
+});
+
+// Share of a pool's connections in use from which it is reported saturated, 0 disables alerts
+static DB_POOL_ALERT_PERCENT: LazyLock<u32> = LazyLock::new(|| {
+    env::var("DB_POOL_ALERT_PERCENT")
+        .ok()
+        .and_then(|v| v.parse::<u32>().ok())
+        .unwrap_or(90)
+});
+
+const SATURATION_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+// Upper bounds of the acquire wait buckets in microseconds, the last bucket being unbounded
+const WAIT_BUCKETS_MICROS: [u64; 12] = [
+    500, 1_000, 2_500, 5_000, 10_000, 25_000, 50_000, 100_000, 250_000, 500_000, 1_000_000, 5_000_000,
+];
+
+// Process-wide counters, shards included
+static WAIT_COUNTS: [AtomicU64; WAIT_BUCKETS_MICROS.len() + 1] = [const { AtomicU64::new(0) }; WAIT_BUCKETS_MICROS.len() + 1];
+static WAIT_SUM_MICROS: AtomicU64 = AtomicU64::new(0);
+static ACQUIRE_TIMEOUTS: AtomicU64 = AtomicU64::new(0);
+static SATURATION_ALERTS: AtomicU64 = AtomicU64::new(0);
+
+#[cfg(not(feature = "no-tracing"))]
+fn record_wait(secs: f64) {
+    let micros = (secs * 1_000_000.0) as u64;
+    let bucket = WAIT_BUCKETS_MICROS
+        .iter()
+        .position(|&bound| micros <= bound)
+        .unwrap_or(WAIT_BUCKETS_MICROS.len());
+    WAIT_COUNTS[bucket].fetch_add(1, Ordering::Relaxed);
+    WAIT_SUM_MICROS.fetch_add(micros, Ordering::Relaxed);
+}
+
+// Counted whatever DB_POOL_METRICS is set to, when the error reaches a response
+pub fn record_timeout() {
+    ACQUIRE_TIMEOUTS.fetch_add(1, Ordering::Relaxed);
+}
+
+// Records the wait sqlx logs with every acquire once the pools get `acquire_time_level`.
+// Acquires past the slow threshold are logged at warn level instead, with the same field.
+#[cfg(not(feature = "no-tracing"))]
+pub fn acquire_wait_layer<S>() -> Option<impl Layer<S>>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    let acquires = Targets::new().with_target("sqlx::pool::acquire", LevelFilter::TRACE);
+    DB_POOL_METRICS.then(|| AcquireWaits.with_filter(acquires))
+}
+
+#[cfg(not(feature = "no-tracing"))]
+struct AcquireWaits;
+
+#[cfg(not(feature = "no-tracing"))]
+impl Visit for AcquireWaits {
+    // Spelled this way by sqlx
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        if field.name() == "aquired_after_secs" {
+            record_wait(value);
+        }
+    }
+
+    fn record_debug(&mut self, _field: &Field, _value: &dyn std::fmt::Debug) {}
+}
+
+#[cfg(not(feature = "no-tracing"))]
+impl<S: Subscriber> Layer<S> for AcquireWaits {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        event.record(&mut AcquireWaits);
+    }
+}
+
+// The pools of a shard by name: primary, write (when writes get their own) and replica
+#[derive(Clone)]
+pub struct Pools(Arc<Vec<(&'static str, PgPool)>>);
+
+impl Pools {
+    pub fn new(pools: Vec<(&'static str, PgPool)>) -> Self {
+        Self(Arc::new(pools))
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        let mut cumulative = 0;
+        let acquire_wait_buckets = WAIT_COUNTS
+            .iter()
+            .enumerate()
+            .map(|(i, count)| {
+                cumulative += count.load(Ordering::Relaxed);
+                WaitBucket {
+                    le_ms: WAIT_BUCKETS_MICROS.get(i).map(|&micros| micros as f64 / 1000.0),
+                    count: cumulative,
+                }
+            })
+            .collect();
+        PoolStats {
+            pools: self.0.iter().map(|(name, pool)| PoolGauges::of(name, pool)).collect(),
+            acquires: cumulative,
+            acquire_wait_sum_ms: WAIT_SUM_MICROS.load(Ordering::Relaxed) as f64 / 1000.0,
+            acquire_wait_buckets,
+            acquire_timeouts: ACQUIRE_TIMEOUTS.load(Ordering::Relaxed),
+            saturation_alerts: SATURATION_ALERTS.load(Ordering::Relaxed),
+        }
+    }
+}
+
+// Served by GET /debug/pool. The gauges are the shard's, the counters the process'
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolStats {
+    pub pools: Vec<PoolGauges>,
+    pub acquires: u64,
+    pub acquire_wait_sum_ms: f64,
+    // Cumulative, as Prometheus buckets: acquires that waited at most leMs (null: any time)
+    pub acquire_wait_buckets: Vec<WaitBucket>,
+    pub acquire_timeouts: u64,
+    pub saturation_alerts: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolGauges {
+    pub name: &'static str,
+    pub max: u32,
+    pub open: u32,
+    pub idle: u32,
+    pub in_use: u32,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WaitBucket {
+    pub le_ms: Option<f64>,
+    pub count: u64,
+}
+
+impl PoolGauges {
+    fn of(name: &'static str, pool: &PgPool) -> Self {
+        let open = pool.size();
+        let idle = (pool.num_idle() as u32).min(open);
+        PoolGauges {
+            name,
+            max: pool.options().get_max_connections(),
+            open,
+            idle,
+            in_use: open - idle,
+        }
+    }
+}
+
+// Name, help and value of a pool's gauge
+type Gauge = (&'static str, &'static str, fn(&PoolGauges) -> u32);
+
+impl PoolStats {
+    // Prometheus text exposition format, served by GET /metrics
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::with_capacity(2048);
+        let gauges: [Gauge; 4] = [
+            ("apibench_db_pool_max_connections", "Connections the pool may open", |p| p.max),
+            ("apibench_db_pool_open_connections", "Connections currently open", |p| p.open),
+            ("apibench_db_pool_idle_connections", "Open connections waiting for a request", |p| p.idle),
+            ("apibench_db_pool_in_use_connections", "Connections checked out by requests", |p| p.in_use),
+        ];
+        for (name, help, value) in gauges {
+            let _ = writeln!(out, "# HELP {} {}\n# TYPE {} gauge", name, help, name);
+            for pool in &self.pools {
+                let _ = writeln!(out, "{}{{pool=\"{}\"}} {}", name, pool.name, value(pool));
+            }
+        }
+
+        let name = "apibench_db_pool_acquire_wait_seconds";
+        let _ = writeln!(out, "# HELP {} Time spent waiting for a connection\n# TYPE {} histogram", name, name);
+        for bucket in &self.acquire_wait_buckets {
+            let le = bucket.le_ms.map_or("+Inf".to_string(), |ms| (ms / 1000.0).to_string());
+            let _ = writeln!(out, "{}_bucket{{le=\"{}\"}} {}", name, le, bucket.count);
+        }
+        let _ = writeln!(out, "{}_sum {}", name, self.acquire_wait_sum_ms / 1000.0);
+        let _ = writeln!(out, "{}_count {}", name, self.acquires);
+
+        let counters = [
+            ("apibench_db_pool_acquire_timeouts_total", "Requests that failed waiting for a connection", self.acquire_timeouts),
+            ("apibench_db_pool_saturation_alerts_total", "Times a pool was reported saturated", self.saturation_alerts),
+        ];
+        for (name, help, value) in counters {
+            let _ = writeln!(out, "# HELP {} {}\n# TYPE {} counter\n{} {}", name, help, name, name, value);
+        }
+        out
+    }
+}
+
+// Checks every SATURATION_CHECK_INTERVAL whether a pool has DB_POOL_ALERT_PERCENT of its
+// connections in use, warning once when it gets there and logging when it recovers.
+// Acquire timeouts are process-wide, `report_timeouts` is set on one shard only.
+pub fn spawn_saturation_monitor(pools: Pools, report_timeouts: bool) {
+    let alert_percent = *DB_POOL_ALERT_PERCENT;
+    if alert_percent == 0 {
+        return;
+    }
+    tokio::spawn(async move {
+        let mut ticker = interval(SATURATION_CHECK_INTERVAL);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut saturated = vec![false; pools.0.len()];
+        let mut timeouts = ACQUIRE_TIMEOUTS.load(Ordering::Relaxed);
+        loop {
+            ticker.tick().await;
+            for ((name, pool), saturated) in pools.0.iter().zip(&mut saturated) {
+                let gauges = PoolGauges::of(name, pool);
+                let now_saturated = gauges.in_use * 100 >= gauges.max * alert_percent;
+                if now_saturated && !*saturated {
+                    SATURATION_ALERTS.fetch_add(1, Ordering::Relaxed);
+                    tracing::warn!(
+                        "DB pool {} saturated: {}/{} connections in use, requests may wait for one",
+                        name,
+                        gauges.in_use,
+                        gauges.max
+                    );
+                } else if !now_saturated && *saturated {
+                    tracing::info!("DB pool {} recovered: {}/{} connections in use", name, gauges.in_use, gauges.max);
+                }
+                *saturated = now_saturated;
+            }
+
+            let total = ACQUIRE_TIMEOUTS.load(Ordering::Relaxed);
+            if report_timeouts && total > timeouts {
+                tracing::warn!(
+                    "{} request(s) timed out waiting for a DB connection in the last {:?}",
+                    total - timeouts,
+                    SATURATION_CHECK_INTERVAL
+                );
+            }
+            timeouts = total;
+        }
+    });
+}
@@ -0,0 +1,105 @@
+use std::{env, sync::LazyLock};
+
+use crate::{
+    casing::{snake_case, JsonCasing, JSON_CASING},
+    models::PostStatus,
+};
+
+// Serialization of plain post listings (GET /posts without cursor nor envelope):
+// - `app`: rows are decoded into PostRow and serialized by the app
+// - `postgres`: the listing is wrapped into a json_agg(...) the app passes through verbatim,
+//   to compare both sides. Same document, only the whitespace differs ({"id" : ...}).
+pub static POSTS_JSON_BY_DB: LazyLock<bool> = LazyLock::new(|| match env::var("POSTS_JSON_MODE").as_deref() {
+    Ok("postgres") => {
+        tracing::info!("Post listings serialized by Postgres");
+        true
+    }
+    Ok("app") | Err(_) => false,
+    Ok(other) => {
+        tracing::warn!("Unknown POSTS_JSON_MODE {:?}, using app", other);
+        false
+    }
+});
+
+// Objects of a listing without and with a viewer (likedByMe)
+static POST_OBJECTS: LazyLock<[String; 2]> = LazyLock::new(|| [post_object(false), post_object(true)]);
+
+// Closes the listing opened by `posts_json_select`
+pub const POSTS_JSON_END: &str = "\n) l";
+
+// Opens the aggregate around the listing query pushed after it, aliased `l`. json_agg follows
+// `order_by` (on the columns of `l`), not the subquery's order Postgres doesn't guarantee.
+pub fn posts_json_select(viewer: bool, order_by: &str) -> String {
+    format!(
+        "SELECT coalesce(json_agg({} ORDER BY {}), '[]')::text FROM (\n",
+        POST_OBJECTS[viewer as usize], order_by
+    )
+}
+
+// Key of the configured casing, models being declared camelCase
+fn key(name: &str) -> String {
+    let name = match *JSON_CASING {
+        JsonCasing::Camel => name.to_string(),
+        JsonCasing::Snake => snake_case(name),
+    };
+    format!("'{}'", name)
+}
+
+// Formatted as timestamp::serialize does: UTC, fraction digits only when needed, by groups of 3
+fn timestamp(column: &str) -> String {
+    let utc = format!("{} AT TIME ZONE 'UTC'", column);
+    let micros = format!("(extract(microseconds FROM {})::bigint % 1000000)", column);
+    format!(
+        "to_char({utc}, 'YYYY-MM-DD\"T\"HH24:MI:SS') || CASE WHEN {micros} = 0 THEN '' \
+         WHEN {micros} % 1000 = 0 THEN to_char({utc}, '.MS') ELSE to_char({utc}, '.US') END || 'Z'"
+    )
+}
+
+// json_build_object of a row of the listing, fields in the order of models::Post.
+// json (not jsonb) keeps that order; likedByMe is omitted without a viewer, as in Post.
+fn post_object(viewer: bool) -> String {
+    let repost_of = format!(
+        "CASE WHEN l.reposted_post_id IS NOT NULL AND l.original_author_id IS NOT NULL \
+         AND l.original_content IS NOT NULL AND l.original_created_at IS NOT NULL \
+         THEN json_build_object({}, l.reposted_post_id, {}, l.original_author_id, {}, l.original_content, \
+         {}, '{}', {}, NULL, {}, coalesce(l.original_like_count, 0), {}, coalesce(l.original_comment_count, 0), \
+         {}, {}, {}, {}, {}, coalesce(l.original_version, 1), {}, coalesce(l.original_repost_count, 0), {}, NULL) END",
+        key("id"),
+        key("authorId"),
+        key("content"),
+        key("status"),
+        PostStatus::Published.as_str(),
+        key("publishAt"),
+        key("likeCount"),
+        key("commentCount"),
+        key("createdAt"),
+        timestamp("l.original_created_at"),
+        key("updatedAt"),
+        timestamp("l.original_updated_at"),
+        key("version"),
+        key("repostCount"),
+        key("repostOf"),
+    );
+    let mut fields = vec![
+        (key("id"), "l.id".to_string()),
+        (key("authorId"), "l.author_id".to_string()),
+        (key("content"), "l.content".to_string()),
+        (key("status"), "l.status".to_string()),
+        (key("publishAt"), timestamp("l.publish_at")),
+        (key("likeCount"), "coalesce(l.like_count, 0)".to_string()),
+        (key("commentCount"), "coalesce(l.comment_count, 0)".to_string()),
+        (key("createdAt"), timestamp("l.created_at")),
+        (key("updatedAt"), timestamp("l.updated_at")),
+        (key("version"), "l.version".to_string()),
+        (key("repostCount"), "coalesce(l.repost_count, 0)".to_string()),
+        (key("repostOf"), repost_of),
+    ];
+    if viewer {
+        fields.push((key("likedByMe"), "l.liked_by_me".to_string()));
+    }
+    let fields: Vec<String> = fields
+        .into_iter()
+        .map(|(key, value)| format!("{}, {}", key, value))
+        .collect();
+    format!("json_build_object({})", fields.join(", "))
+}
@@ -0,0 +1,69 @@
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use governor::{DefaultKeyedRateLimiter, Quota, RateLimiter};
+use std::{
+    env,
+    net::{IpAddr, SocketAddr},
+    num::NonZeroU32,
+    sync::Arc,
+    time::Duration,
+};
+
+use crate::error::AppError;
+
+pub type IpRateLimiter = Arc<DefaultKeyedRateLimiter<IpAddr>>;
+
+// How often idle per-IP buckets are dropped from the limiter
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60);
+
+// Build the /auth/login limiter from the environment, shared by the shards of the sharded runtime.
+// Disabled unless LOGIN_RATE_LIMIT_PER_SECOND is set to a positive value.
+pub fn login_rate_limiter_from_env() -> Option<IpRateLimiter> {
+    let per_second = env::var("LOGIN_RATE_LIMIT_PER_SECOND")
+        .ok()
+        .and_then(|v| v.parse::<u32>().ok())
+        .and_then(NonZeroU32::new)?;
+    let burst = env::var("LOGIN_RATE_LIMIT_BURST")
+        .ok()
+        .and_then(|v| v.parse::<u32>().ok())
+        .and_then(NonZeroU32::new)
+        .unwrap_or(per_second);
+
+    tracing::info!(
+        "Login rate limiting enabled: {} req/s per IP, burst {}",
+        per_second,
+        burst
+    );
+
+    Some(Arc::new(RateLimiter::keyed(
+        Quota::per_second(per_second).allow_burst(burst),
+    )))
+}
+
+// Keyed limiters keep one bucket per IP, periodically forget the idle ones.
+// Spawned once per limiter, by the first shard with the sharded runtime.
+pub fn spawn_rate_limiter_cleanup(limiter: IpRateLimiter) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(CLEANUP_INTERVAL);
+        loop {
+            interval.tick().await;
+            limiter.retain_recent();
+        }
+    });
+}
+
+pub async fn rate_limit_middleware(
+    State(limiter): State<IpRateLimiter>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    if limiter.check_key(&addr.ip()).is_err() {
+        return Err(AppError::TooManyRequests("Too many requests".to_string()));
+    }
+
+    Ok(next.run(request).await)
+}
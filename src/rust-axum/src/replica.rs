@@ -0,0 +1,132 @@
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::Response,
+};
+use sqlx::{
+    postgres::{PgConnectOptions, PgPoolOptions},
+    PgPool,
+};
+use std::{
+    env,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+// Read replica serving the read-only routes (GET /posts, GET /posts/{post_id},
+// GET /posts/{post_id}/comments, GET /auth/me) while it is available.
+// Once a request fails on it with a 503 it is marked down and reads go to the primary,
+// until a probe every DB_REPLICA_RETRY_SECS finds it reachable again.
+#[derive(Clone)]
+pub struct ReadReplica {
+    pool: PgPool,
+    available: Arc<AtomicBool>,
+    retry_interval: Duration,
+}
+
+// Enabled by DATABASE_REPLICA_URL, `configure` applies the primary's connection settings.
+// The pool connects lazily, an unreachable replica doesn't prevent startup.
+pub async fn replica_from_env(
+    pool_options: PgPoolOptions,
+    configure: impl FnOnce(PgConnectOptions) -> PgConnectOptions,
+) -> Result<Option<ReadReplica>, sqlx::Error> {
+    let url = match env::var("DATABASE_REPLICA_URL") {
+        Ok(url) if !url.is_empty() => url,
+        _ => return Ok(None),
+    };
+    let retry_secs = env::var("DB_REPLICA_RETRY_SECS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .filter(|&secs| secs > 0)
+        .unwrap_or(5);
+    // Short, so reads don't wait long on an unreachable replica before falling back
+    let acquire_timeout_ms = env::var("DB_REPLICA_ACQUIRE_TIMEOUT_MS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(1000);
+
+    let replica = ReadReplica {
+        pool: pool_options
+            .acquire_timeout(Duration::from_millis(acquire_timeout_ms))
+            .connect_lazy_with(configure(url.parse()?)),
+        available: Arc::new(AtomicBool::new(true)),
+        retry_interval: Duration::from_secs(retry_secs),
+    };
+    tracing::info!("Read replica enabled for read-only routes");
+    if let Err(e) = replica.ping().await {
+        tracing::warn!("Read replica unreachable at startup: {}", e);
+        replica.mark_unavailable();
+    }
+    Ok(Some(replica))
+}
+
+impl ReadReplica {
+    // The replica pool while it is available
+    pub fn pool(&self) -> Option<&PgPool> {
+        self.available.load(Ordering::Relaxed).then_some(&self.pool)
+    }
+
+    // The replica pool whether it is available or not, for its metrics
+    pub fn raw_pool(&self) -> &PgPool {
+        &self.pool
+    }
+
+    async fn ping(&self) -> Result<(), sqlx::Error> {
+        sqlx::query("SELECT 1").execute(&self.pool).await.map(|_| ())
+    }
+
+    // Sends reads to the primary and probes the replica until it answers again
+    fn mark_unavailable(&self) {
+        if !self.available.swap(false, Ordering::Relaxed) {
+            return;
+        }
+        let replica = self.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(replica.retry_interval).await;
+                match replica.ping().await {
+                    Ok(()) => {
+                        replica.available.store(true, Ordering::Relaxed);
+                        tracing::info!("Read replica reachable again, serving reads");
+                        return;
+                    }
+                    Err(e) => tracing::debug!("Read replica still unreachable: {}", e),
+                }
+            }
+        });
+    }
+}
+
+// Runs a read on the replica, then once more on the primary if the replica answered 503
+// (unreachable, saturated, or a cancelled statement). Read handlers ignore the body.
+pub async fn replica_fallback_middleware(
+    State(replica): State<ReadReplica>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if replica.pool().is_none() {
+        return next.run(request).await;
+    }
+
+    let (parts, _) = request.into_parts();
+    let response = next
+        .clone()
+        .run(Request::from_parts(parts.clone(), Body::empty()))
+        .await;
+    if response.status() != StatusCode::SERVICE_UNAVAILABLE {
+        return response;
+    }
+
+    tracing::warn!(
+        "Read replica failed {} {}, falling back to the primary",
+        parts.method,
+        parts.uri.path()
+    );
+    replica.mark_unavailable();
+    next.run(Request::from_parts(parts, Body::empty())).await
+}
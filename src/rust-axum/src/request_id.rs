@@ -0,0 +1,40 @@
+use axum::{extract::Request, http::HeaderValue, middleware::Next, response::Response};
+use std::future::Future;
+use tokio::task::JoinHandle;
+use tower_http::request_id::RequestId;
+
+tokio::task_local! {
+    // x-request-id of the request being served
+    static REQUEST_ID: HeaderValue;
+}
+
+// Serves the request with its ID (the client's x-request-id, or the one SetRequestIdLayer
+// generated) in scope, so error responses built below, by handlers and middleware alike,
+// carry it in their body, where logs and failed benchmark requests can be matched on it
+pub async fn request_id_middleware(request: Request, next: Next) -> Response {
+    match request.extensions().get::<RequestId>() {
+        Some(id) => {
+            let id = id.header_value().clone();
+            REQUEST_ID.scope(id, next.run(request)).await
+        }
+        None => next.run(request).await,
+    }
+}
+
+// None outside a request (health checks, background jobs)
+pub fn current_request_id() -> Option<HeaderValue> {
+    REQUEST_ID.try_with(HeaderValue::clone).ok()
+}
+
+// tokio::spawn for work of the current request: spawned tasks don't inherit task locals,
+// the request ID is put back in scope so errors built there still carry it
+pub fn spawn_in_request<F>(future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    match current_request_id() {
+        Some(id) => tokio::spawn(REQUEST_ID.scope(id, future)),
+        None => tokio::spawn(future),
+    }
+}
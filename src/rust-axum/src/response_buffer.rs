@@ -0,0 +1,54 @@
+use bytes::{Bytes, BytesMut};
+use std::{cell::RefCell, mem};
+
+// Capacity of a new buffer, a page of posts fits in it
+const BUFFER_BYTES: usize = 16 * 1024;
+
+// Buffers grown past this (by a large page) are freed instead of going back to the pool
+const MAX_POOLED_BYTES: usize = 256 * 1024;
+
+// Buffers kept per thread
+const MAX_POOLED_BUFFERS: usize = 64;
+
+// Response buffers of the `pooled-buffers` feature, per thread so taking and returning one
+// takes no lock. A buffer serialized into on one thread may come back on another, both pools
+// stay bounded.
+thread_local! {
+    static POOL: RefCell<Vec<BytesMut>> = const { RefCell::new(Vec::new()) };
+}
+
+// A body owning its buffer, handed back to the pool when hyper drops it after sending
+struct PooledBuffer(BytesMut);
+
+impl AsRef<[u8]> for PooledBuffer {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Drop for PooledBuffer {
+    fn drop(&mut self) {
+        if self.0.capacity() > MAX_POOLED_BYTES {
+            return;
+        }
+        let mut buffer = mem::take(&mut self.0);
+        buffer.clear();
+        // The pool of an exiting thread may be gone already
+        let _ = POOL.try_with(|pool| {
+            let mut pool = pool.borrow_mut();
+            if pool.len() < MAX_POOLED_BUFFERS {
+                pool.push(buffer);
+            }
+        });
+    }
+}
+
+// Runs `write` on a buffer of the pool (a new one when it's empty), the body it returns
+// gives the buffer back once dropped. Bodies kept around (cached) keep their buffer out of the pool.
+pub fn pooled_body<E>(write: impl FnOnce(&mut BytesMut) -> Result<(), E>) -> Result<Bytes, E> {
+    let mut buffer = POOL
+        .with_borrow_mut(Vec::pop)
+        .unwrap_or_else(|| BytesMut::with_capacity(BUFFER_BYTES));
+    write(&mut buffer)?;
+    Ok(Bytes::from_owner(PooledBuffer(buffer)))
+}
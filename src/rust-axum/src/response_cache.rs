@@ -0,0 +1,128 @@
+use axum::{
+    body::{to_bytes, Body, Bytes},
+    extract::{Request, State},
+    http::{header, HeaderMap, Method, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use futures_util::stream;
+use lru::LruCache;
+use std::{
+    env,
+    num::NonZeroUsize,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::sync::OnceCell;
+
+// In-process cache of whole responses of the public routes it is layered on, enabled by
+// RESPONSE_CACHE_TTL_MS. Unlike the Redis cache nothing invalidates it: entries are served
+// until the TTL, so keep it short. Only anonymous GETs are cached, keyed by path and query.
+// Concurrent misses on one key wait for the first request instead of all hitting the database.
+#[derive(Clone)]
+pub struct ResponseCache {
+    entries: Arc<Mutex<LruCache<String, Arc<OnceCell<CachedResponse>>>>>,
+    ttl: Duration,
+}
+
+struct CachedResponse {
+    headers: HeaderMap,
+    body: Bytes,
+    expires_at: Instant,
+}
+
+pub fn response_cache_from_env() -> Option<ResponseCache> {
+    let ttl_ms = env::var("RESPONSE_CACHE_TTL_MS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .filter(|&ms| ms > 0)?;
+    let size = env::var("RESPONSE_CACHE_SIZE")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .and_then(NonZeroUsize::new)
+        .unwrap_or(NonZeroUsize::new(1000).expect("non-zero"));
+
+    tracing::info!("Response cache enabled ({} entries, TTL {} ms)", size, ttl_ms);
+    Some(ResponseCache {
+        entries: Arc::new(Mutex::new(LruCache::new(size))),
+        ttl: Duration::from_millis(ttl_ms),
+    })
+}
+
+impl ResponseCache {
+    // The entry of `key`, a fresh one when missing or expired
+    fn entry(&self, key: String) -> Arc<OnceCell<CachedResponse>> {
+        let mut entries = self.entries.lock().expect("response cache lock poisoned");
+        let now = Instant::now();
+        match entries.get(&key) {
+            Some(entry) if entry.get().is_none_or(|cached| cached.expires_at > now) => entry.clone(),
+            _ => {
+                let entry = Arc::new(OnceCell::new());
+                entries.put(key, entry.clone());
+                entry
+            }
+        }
+    }
+}
+
+// Requests carrying credentials get per-user responses (likedByMe, drafts), and conditional
+// ones are left to the handler's ETag check
+fn cacheable(request: &Request) -> bool {
+    let headers = request.headers();
+    request.method() == Method::GET
+        && !headers.contains_key(header::AUTHORIZATION)
+        && !headers.contains_key(header::COOKIE)
+        && !headers.contains_key(header::IF_NONE_MATCH)
+}
+
+// Serves a cached 200 when there is one, otherwise runs the request and caches its 200.
+// Other responses are returned as they are, the next waiting request then runs its own.
+pub async fn response_cache_middleware(
+    State(cache): State<ResponseCache>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !cacheable(&request) {
+        return next.run(request).await;
+    }
+
+    let key = request
+        .uri()
+        .path_and_query()
+        .map_or_else(|| request.uri().path().to_string(), |pq| pq.as_str().to_string());
+    let entry = cache.entry(key);
+    let cached = entry
+        .get_or_try_init(|| async {
+            let response = next.run(request).await;
+            if response.status() != StatusCode::OK {
+                return Err(response);
+            }
+            let (parts, body) = response.into_parts();
+            match to_bytes(body, usize::MAX).await {
+                Ok(body) => Ok(CachedResponse {
+                    headers: parts.headers,
+                    // Copied out of the pooled buffer it may have been serialized into
+                    #[cfg(feature = "pooled-buffers")]
+                    body: Bytes::copy_from_slice(&body),
+                    #[cfg(not(feature = "pooled-buffers"))]
+                    body,
+                    expires_at: Instant::now() + cache.ttl,
+                }),
+                // A streamed body failing midway, the client gets the same aborted response
+                Err(e) => {
+                    let body = stream::once(async move { Err::<Bytes, _>(e) });
+                    Err(Response::from_parts(parts, Body::from_stream(body)))
+                }
+            }
+        })
+        .await;
+
+    match cached {
+        Ok(cached) => {
+            let mut response = Response::new(Body::from(cached.body.clone()));
+            *response.headers_mut() = cached.headers.clone();
+            response
+        }
+        Err(response) => response,
+    }
+}
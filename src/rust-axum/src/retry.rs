@@ -0,0 +1,123 @@
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::Method,
+    middleware::Next,
+    response::Response,
+};
+use serde::Serialize;
+use std::{
+    env,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use crate::error::RetryableDbError;
+
+// Reads retried after a serialization failure or a reset connection.
+// GET / HEAD handlers only read, so the whole request is simply run again.
+#[derive(Debug, Clone, Copy)]
+pub struct ReadRetry {
+    max_retries: u32,
+    base_delay: Duration,
+}
+
+// Build the read retry policy from the environment.
+// Disabled unless DB_READ_RETRIES is set to a positive value.
+pub fn read_retry_from_env() -> Option<ReadRetry> {
+    let max_retries = env::var("DB_READ_RETRIES")
+        .ok()
+        .and_then(|v| v.parse::<u32>().ok())
+        .filter(|&retries| retries > 0)?;
+    let base_delay_ms = env::var("DB_READ_RETRY_BASE_MS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(20);
+
+    tracing::info!(
+        "Read retries enabled: up to {} per request, backoff from {} ms",
+        max_retries,
+        base_delay_ms
+    );
+
+    Some(ReadRetry {
+        max_retries,
+        base_delay: Duration::from_millis(base_delay_ms),
+    })
+}
+
+impl ReadRetry {
+    // Exponential backoff with full jitter: a random delay up to base * 2^(attempt - 1)
+    fn delay(&self, attempt: u32) -> Duration {
+        let ceiling = self.base_delay.saturating_mul(1 << (attempt - 1).min(10));
+        ceiling.mul_f64(rand::random::<f64>())
+    }
+}
+
+// Process-wide retry counters, served by GET /admin/db-retries
+static RETRIES: AtomicU64 = AtomicU64::new(0);
+static RECOVERED: AtomicU64 = AtomicU64::new(0);
+static EXHAUSTED: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Serialize)]
+pub struct RetryStats {
+    // Extra attempts made
+    pub retries: u64,
+    // Requests that succeeded after at least one retry
+    pub recovered: u64,
+    // Requests still failing once out of retries
+    pub exhausted: u64,
+}
+
+pub fn retry_stats() -> RetryStats {
+    RetryStats {
+        retries: RETRIES.load(Ordering::Relaxed),
+        recovered: RECOVERED.load(Ordering::Relaxed),
+        exhausted: EXHAUSTED.load(Ordering::Relaxed),
+    }
+}
+
+pub async fn read_retry_middleware(
+    State(policy): State<ReadRetry>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !matches!(*request.method(), Method::GET | Method::HEAD) {
+        return next.run(request).await;
+    }
+
+    // Read handlers ignore the body, replays are sent without one
+    let (parts, _) = request.into_parts();
+    let mut attempt = 0;
+    loop {
+        let response = next
+            .clone()
+            .run(Request::from_parts(parts.clone(), Body::empty()))
+            .await;
+        let retryable = response.extensions().get::<RetryableDbError>().is_some();
+
+        if !retryable {
+            if attempt > 0 {
+                RECOVERED.fetch_add(1, Ordering::Relaxed);
+            }
+            return response;
+        }
+        if attempt == policy.max_retries {
+            EXHAUSTED.fetch_add(1, Ordering::Relaxed);
+            return response;
+        }
+
+        attempt += 1;
+        RETRIES.fetch_add(1, Ordering::Relaxed);
+        let delay = policy.delay(attempt);
+        tracing::warn!(
+            "Retrying {} {} after a transient database error (attempt {}/{}, in {:?})",
+            parts.method,
+            parts.uri.path(),
+            attempt,
+            policy.max_retries,
+            delay
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
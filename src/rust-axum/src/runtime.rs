@@ -0,0 +1,132 @@
+use std::{env, error::Error, future::Future, io, sync::mpsc, thread};
+use tokio::runtime::{Builder, Runtime};
+use tracing::Instrument;
+
+fn env_usize(name: &str) -> Option<usize> {
+    env::var(name)
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .filter(|&value| value > 0)
+}
+
+// Multi-threaded runtime sized from the environment, tokio's defaults otherwise:
+// - TOKIO_WORKER_THREADS: async worker threads (one per core)
+// - TOKIO_MAX_BLOCKING_THREADS: spawn_blocking pool, where bcrypt runs (512)
+// - TOKIO_THREAD_STACK_SIZE: stack size of every runtime thread in bytes (2 MiB)
+pub fn runtime_from_env() -> io::Result<Runtime> {
+    let mut builder = Builder::new_multi_thread();
+    builder.enable_all();
+
+    let worker_threads = env_usize("TOKIO_WORKER_THREADS");
+    if let Some(threads) = worker_threads {
+        builder.worker_threads(threads);
+    }
+    let max_blocking_threads = env_usize("TOKIO_MAX_BLOCKING_THREADS");
+    if let Some(threads) = max_blocking_threads {
+        builder.max_blocking_threads(threads);
+    }
+    let thread_stack_size = env_usize("TOKIO_THREAD_STACK_SIZE");
+    if let Some(size) = thread_stack_size {
+        builder.thread_stack_size(size);
+    }
+
+    let runtime = builder.build()?;
+    tracing::info!(
+        "Tokio runtime: {} worker threads, up to {} blocking threads, {} stack",
+        runtime.metrics().num_workers(),
+        max_blocking_threads.map_or_else(|| "512 (default)".to_string(), |threads| threads.to_string()),
+        thread_stack_size.map_or_else(|| "default".to_string(), |size| format!("{} byte", size))
+    );
+    Ok(runtime)
+}
+
+// Server mode, RUNTIME_MODE:
+// - `multi-thread`: the runtime above, its workers steal each other's connections (default)
+// - `sharded`: RUNTIME_SHARDS threads (one per core) each running a single-threaded runtime
+//   with its own SO_REUSEPORT listener, pools and in-process state. The kernel spreads
+//   connections across the shards, a connection's requests never leave its thread.
+pub fn shards_from_env() -> Option<usize> {
+    match env::var("RUNTIME_MODE").as_deref() {
+        Ok("sharded") => Some(
+            env_usize("RUNTIME_SHARDS")
+                .unwrap_or_else(|| thread::available_parallelism().map_or(1, |cores| cores.get())),
+        ),
+        Ok("multi-thread") | Err(_) => None,
+        Ok(other) => {
+            tracing::warn!("Unknown RUNTIME_MODE {:?}, using multi-thread", other);
+            None
+        }
+    }
+}
+
+// Part of the server run by one runtime, the only one outside the sharded mode
+#[derive(Debug, Clone, Copy)]
+pub struct Shard {
+    pub index: usize,
+    pub count: usize,
+}
+
+impl Shard {
+    pub const SINGLE: Shard = Shard { index: 0, count: 1 };
+
+    pub fn is_first(self) -> bool {
+        self.index == 0
+    }
+
+    // Share of `total` (pool connections) of this shard, the first ones get the remainder
+    pub fn slice(self, total: u32) -> u32 {
+        let count = self.count as u32;
+        total / count + u32::from((self.index as u32) < total % count)
+    }
+}
+
+// Runs `serve` on every shard, each on a thread of its own, until one of them fails.
+// Startup logs of a shard are prefixed by its index.
+pub fn run_sharded<F, Fut>(shards: usize, serve: F) -> Result<(), Box<dyn Error>>
+where
+    F: Fn(Shard) -> Fut + Clone + Send + 'static,
+    Fut: Future<Output = Result<(), Box<dyn Error>>>,
+{
+    let thread_stack_size = env_usize("TOKIO_THREAD_STACK_SIZE");
+    tracing::info!(
+        "Sharded runtime: {} single-threaded runtimes, up to {} blocking threads each",
+        shards,
+        env_usize("TOKIO_MAX_BLOCKING_THREADS").map_or_else(|| "512 (default)".to_string(), |threads| threads.to_string())
+    );
+
+    let (done, finished) = mpsc::channel();
+    for index in 0..shards {
+        let shard = Shard { index, count: shards };
+        let done = done.clone();
+        let serve = serve.clone();
+        let mut builder = thread::Builder::new().name(format!("shard-{}", index));
+        if let Some(size) = thread_stack_size {
+            builder = builder.stack_size(size);
+        }
+        builder.spawn(move || {
+            let result = match shard_runtime() {
+                Ok(runtime) => runtime
+                    .block_on(serve(shard).instrument(tracing::info_span!("shard", index)))
+                    .map_err(|e| e.to_string()),
+                Err(e) => Err(e.to_string()),
+            };
+            let _ = done.send((index, result));
+        })?;
+    }
+
+    let (index, result) = finished.recv()?;
+    result.map_err(|e| format!("shard {}: {}", index, e).into())
+}
+
+// Runs on the shard's thread, spawn_blocking still gets a pool of its own
+fn shard_runtime() -> io::Result<Runtime> {
+    let mut builder = Builder::new_current_thread();
+    builder.enable_all();
+    if let Some(threads) = env_usize("TOKIO_MAX_BLOCKING_THREADS") {
+        builder.max_blocking_threads(threads);
+    }
+    if let Some(size) = env_usize("TOKIO_THREAD_STACK_SIZE") {
+        builder.thread_stack_size(size);
+    }
+    builder.build()
+}
@@ -0,0 +1,170 @@
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use sqlx::{postgres::PgCopyIn, PgConnection, PgPool};
+use std::{io::Write, time::Instant};
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    models::{SeedRequest, SeedResult},
+    sql::*,
+};
+
+// Password of every seeded user, hashed once
+pub const SEED_PASSWORD: &str = "password";
+
+// COPY data sent to Postgres at once
+const COPY_CHUNK_BYTES: usize = 1024 * 1024;
+
+// Rows are spread over this many days before now
+const SEED_DAYS: i64 = 30;
+
+const POST_TEXTS: &[&str] = &[
+    "Benchmarking APIs one request at a time",
+    "Connection pools are a shared resource, size them accordingly",
+    "Latency percentiles tell more than averages",
+    "Indexes first, caches second",
+    "Another day, another flame graph",
+];
+
+const COMMENT_TEXTS: &[&str] = &["Agreed", "Interesting, thanks for sharing", "Not so sure about that", "+1"];
+
+// Generated rows in COPY text format, flushed to Postgres by chunks
+struct CopyWriter<'c> {
+    copy: PgCopyIn<&'c mut PgConnection>,
+    buffer: Vec<u8>,
+}
+
+impl<'c> CopyWriter<'c> {
+    async fn begin(connection: &'c mut PgConnection, statement: &str) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            copy: connection.copy_in_raw(statement).await?,
+            buffer: Vec::with_capacity(COPY_CHUNK_BYTES + 1024),
+        })
+    }
+
+    // `row` writes tab-separated fields, free of tabs, newlines and backslashes
+    async fn row(&mut self, row: impl FnOnce(&mut Vec<u8>) -> std::io::Result<()>) -> Result<(), AppError> {
+        row(&mut self.buffer).map_err(|e| AppError::InternalServerError(e.to_string()))?;
+        self.buffer.push(b'\n');
+        if self.buffer.len() >= COPY_CHUNK_BYTES {
+            self.copy.send(self.buffer.as_slice()).await?;
+            self.buffer.clear();
+        }
+        Ok(())
+    }
+
+    async fn finish(mut self) -> Result<u64, sqlx::Error> {
+        if !self.buffer.is_empty() {
+            self.copy.send(self.buffer.as_slice()).await?;
+        }
+        self.copy.finish().await
+    }
+}
+
+fn timestamp(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+// Generates the requested users, posts and comments and loads them with COPY in one
+// transaction. Usernames and emails get a random prefix per run, so seeding can be repeated.
+// Comment counts are copied with the posts when the trigger can skip them (migration 028),
+// otherwise the trigger counts each comment, which is much slower.
+pub async fn seed(pool: &PgPool, request: &SeedRequest, password_hash: &str) -> Result<SeedResult, AppError> {
+    let started = Instant::now();
+    let mut rng = StdRng::from_os_rng();
+    let run: u32 = rng.random();
+    let now = Utc::now();
+    let since = now - Duration::days(SEED_DAYS);
+    let span_micros = (now - since).num_microseconds().unwrap_or(1).max(1);
+
+    let counts_copied: bool = sqlx::query_scalar(SQL_COMMENT_COUNTER_TRIGGER_SKIPPABLE)
+        .fetch_one(pool)
+        .await?;
+    let mut tx = pool.begin().await?;
+    sqlx::query(SQL_SEED_SETTINGS).execute(&mut *tx).await?;
+
+    let user_ids: Vec<Uuid> = (0..request.users).map(|_| Uuid::new_v4()).collect();
+    let mut users = CopyWriter::begin(&mut tx, SQL_SEED_COPY_USERS).await?;
+    for (i, id) in user_ids.iter().enumerate() {
+        let created_at = since + Duration::microseconds(rng.random_range(0..span_micros));
+        users
+            .row(|buffer| {
+                write!(
+                    buffer,
+                    "{id}\tseed_{run:08x}_{i}\tseed_{run:08x}_{i}@seed.local\t{password_hash}\t{}",
+                    timestamp(created_at)
+                )
+            })
+            .await?;
+    }
+    let users = users.finish().await?;
+
+    // Comments are assigned to their posts first, whose counts are copied with them
+    let comment_posts: Vec<u32> = (0..request.comments)
+        .map(|_| rng.random_range(0..request.posts.max(1)))
+        .collect();
+    let mut comment_counts = vec![0u32; request.posts as usize];
+    if counts_copied {
+        for &post in &comment_posts {
+            comment_counts[post as usize] += 1;
+        }
+    }
+
+    let mut posts_created: Vec<(Uuid, DateTime<Utc>)> = Vec::with_capacity(request.posts as usize);
+    let mut posts = CopyWriter::begin(&mut tx, SQL_SEED_COPY_POSTS).await?;
+    for (i, comments) in comment_counts.iter().enumerate() {
+        let id = Uuid::new_v4();
+        let author_id = user_ids[rng.random_range(0..user_ids.len())];
+        let text = POST_TEXTS[rng.random_range(0..POST_TEXTS.len())];
+        let created_at = since + Duration::microseconds(rng.random_range(0..span_micros));
+        posts
+            .row(|buffer| {
+                write!(
+                    buffer,
+                    "{id}\t{author_id}\tSeed post {i}: {text}\t{}\t{comments}",
+                    timestamp(created_at)
+                )
+            })
+            .await?;
+        posts_created.push((id, created_at));
+    }
+    let posts = posts.finish().await?;
+
+    let mut comments = CopyWriter::begin(&mut tx, SQL_SEED_COPY_COMMENTS).await?;
+    for post in comment_posts {
+        let (post_id, post_created_at) = posts_created[post as usize];
+        let author_id = user_ids[rng.random_range(0..user_ids.len())];
+        let text = COMMENT_TEXTS[rng.random_range(0..COMMENT_TEXTS.len())];
+        let after_post = (now - post_created_at).num_microseconds().unwrap_or(1).max(1);
+        let created_at = post_created_at + Duration::microseconds(rng.random_range(0..after_post));
+        comments
+            .row(|buffer| {
+                write!(
+                    buffer,
+                    "{}\t{author_id}\t{post_id}\t{text}\t{}",
+                    Uuid::new_v4(),
+                    timestamp(created_at)
+                )
+            })
+            .await?;
+    }
+    let comments = comments.finish().await?;
+    tx.commit().await?;
+
+    sqlx::query(SQL_SEED_ANALYZE).execute(pool).await?;
+    tracing::info!(
+        "Seeded {} users, {} posts and {} comments in {:?}",
+        users,
+        posts,
+        comments,
+        started.elapsed()
+    );
+    Ok(SeedResult {
+        users,
+        posts,
+        comments,
+        password: SEED_PASSWORD,
+        elapsed_ms: started.elapsed().as_millis(),
+    })
+}
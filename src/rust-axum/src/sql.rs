@@ -1,27 +1,280 @@
 // SQL query constants - loaded at compile time for better performance
 
 // Auth
-pub const SQL_LOGIN: &str = include_str!("../../../database/queries/auth/login.sql");
-pub const SQL_ME: &str = include_str!("../../../database/queries/auth/me.sql");
+pub const SQL_LOGIN: &str = include_str!("../../../database/queries/auth/login_profile.sql");
+pub const SQL_ME: &str = include_str!("../../../database/queries/auth/me_detailed.sql");
 
 // Users
-pub const SQL_CREATE_USER: &str = include_str!("../../../database/queries/users/create.sql");
-pub const SQL_GET_USER: &str = include_str!("../../../database/queries/users/get.sql");
-pub const SQL_LIST_USERS: &str = include_str!("../../../database/queries/users/list.sql");
-pub const SQL_UPDATE_USER: &str = include_str!("../../../database/queries/users/update.sql");
-pub const SQL_DELETE_USER: &str = include_str!("../../../database/queries/users/delete.sql");
+pub const SQL_USER_STATUS: &str = include_str!("../../../database/queries/user_status.sql");
+pub const SQL_CREATE_USER: &str = include_str!("../../../database/queries/users/create_detailed.sql");
+pub const SQL_GET_USER: &str = include_str!("../../../database/queries/users/get_detailed.sql");
+pub const SQL_LIST_USERS: &str = include_str!("../../../database/queries/users/list_detailed.sql");
+pub const SQL_LIST_USERS_KEYSET: &str = include_str!("../../../database/queries/users/list_keyset.sql");
+pub const SQL_COUNT_USERS: &str = include_str!("../../../database/queries/users/count.sql");
+pub const SQL_UPDATE_USER: &str = include_str!("../../../database/queries/users/update_detailed.sql");
+pub const SQL_DELETE_USER: &str = include_str!("../../../database/queries/users/soft_delete.sql");
+pub const SQL_SEARCH_USERS: &str = include_str!("../../../database/queries/users/search.sql");
+pub const SQL_IMPERSONATE_USER: &str = include_str!("../../../database/queries/users/impersonate.sql");
+pub const SQL_IMPORT_USERS: &str = include_str!("../../../database/queries/users/import_batch.sql");
+pub const SQL_UPDATE_PROFILE: &str = include_str!("../../../database/queries/users/update_profile.sql");
+pub const SQL_USER_ACTIVITY: &str = include_str!("../../../database/queries/users/activity.sql");
+pub const SQL_UPDATE_USER_AVATAR: &str = include_str!("../../../database/queries/users/update_avatar.sql");
 
 // Posts
-pub const SQL_CREATE_POST: &str = include_str!("../../../database/queries/posts/create.sql");
-pub const SQL_LIST_POSTS: &str = include_str!("../../../database/queries/posts/list.sql");
-pub const SQL_GET_POST: &str = include_str!("../../../database/queries/posts/get.sql");
-pub const SQL_GET_POST_AUTHOR: &str = include_str!("../../../database/queries/posts/get_author.sql");
-pub const SQL_DELETE_POST: &str = include_str!("../../../database/queries/posts/delete.sql");
+pub const SQL_CREATE_POST: &str = include_str!("../../../database/queries/posts/create_with_status.sql");
+pub const SQL_CREATE_POSTS_BATCH: &str = include_str!("../../../database/queries/posts/create_batch.sql");
+pub const SQL_SELECT_POSTS: &str = include_str!("../../../database/queries/posts/select_detailed.sql");
+pub const SQL_COUNT_PUBLISHED_POSTS: &str = include_str!("../../../database/queries/posts/count_published.sql");
+pub const SQL_COUNT_POSTS: &str = include_str!("../../../database/queries/posts/count.sql");
+pub const SQL_LIST_POSTS_BY_AUTHOR: &str = include_str!("../../../database/queries/posts/list_by_author.sql");
+pub const SQL_TRENDING_POSTS: &str = include_str!("../../../database/queries/posts/trending.sql");
+pub const SQL_GET_POST: &str = include_str!("../../../database/queries/posts/get_detailed.sql");
+pub const SQL_LOOKUP_POSTS: &str = include_str!("../../../database/queries/posts/lookup.sql");
+pub const SQL_GET_POST_AUTHOR: &str = include_str!("../../../database/queries/posts/get_author_active.sql");
+pub const SQL_UPDATE_POST: &str = include_str!("../../../database/queries/posts/update.sql");
+pub const SQL_DELETE_POST: &str = include_str!("../../../database/queries/posts/soft_delete.sql");
+pub const SQL_PUBLISH_POST: &str = include_str!("../../../database/queries/posts/publish.sql");
+pub const SQL_PUBLISH_SCHEDULED_POSTS: &str = include_str!("../../../database/queries/posts/publish_scheduled.sql");
+pub const SQL_RESTORE_POST: &str = include_str!("../../../database/queries/posts/restore.sql");
+pub const SQL_REPOST_POST: &str = include_str!("../../../database/queries/posts/repost.sql");
 
 // Comments
-pub const SQL_CREATE_COMMENT: &str = include_str!("../../../database/queries/comments/create.sql");
-pub const SQL_LIST_COMMENTS: &str = include_str!("../../../database/queries/comments/list.sql");
+pub const SQL_CREATE_COMMENT: &str = include_str!("../../../database/queries/comments/create_with_notification.sql");
+pub const SQL_LIST_POST_COMMENTS: &str = include_str!("../../../database/queries/comments/list_for_post.sql");
+pub const SQL_COUNT_POST_COMMENTS: &str = include_str!("../../../database/queries/posts/comment_count.sql");
+pub const SQL_GET_COMMENT_AUTHOR: &str = include_str!("../../../database/queries/comments/get_author.sql");
+pub const SQL_UPDATE_COMMENT: &str = include_str!("../../../database/queries/comments/update.sql");
+pub const SQL_DELETE_COMMENT: &str = include_str!("../../../database/queries/comments/delete.sql");
 
 // Likes
-pub const SQL_CREATE_LIKE: &str = include_str!("../../../database/queries/likes/create.sql");
+pub const SQL_CREATE_LIKE: &str = include_str!("../../../database/queries/likes/create_with_notification.sql");
+pub const SQL_LIST_LIKED_POSTS: &str = include_str!("../../../database/queries/likes/list_by_user.sql");
 pub const SQL_DELETE_LIKE: &str = include_str!("../../../database/queries/likes/delete.sql");
+// LIKE_COUNT_MODE=app: the statements maintain posts.likes_count instead of the triggers
+pub const SQL_CREATE_LIKE_COUNTED: &str = include_str!("../../../database/queries/likes/create_counted.sql");
+pub const SQL_DELETE_LIKE_COUNTED: &str = include_str!("../../../database/queries/likes/delete_counted.sql");
+pub const SQL_LIKE_COUNTER_TRIGGERS_SKIPPABLE: &str =
+    include_str!("../../../database/queries/likes/counter_triggers_skippable.sql");
+
+// Bookmarks
+pub const SQL_CREATE_BOOKMARK: &str = include_str!("../../../database/queries/bookmarks/create.sql");
+pub const SQL_DELETE_BOOKMARK: &str = include_str!("../../../database/queries/bookmarks/delete.sql");
+pub const SQL_LIST_BOOKMARKS: &str = include_str!("../../../database/queries/bookmarks/list.sql");
+
+// Follows
+pub const SQL_CREATE_FOLLOW: &str = include_str!("../../../database/queries/follows/create.sql");
+pub const SQL_DELETE_FOLLOW: &str = include_str!("../../../database/queries/follows/delete.sql");
+pub const SQL_LIST_FOLLOWERS: &str = include_str!("../../../database/queries/follows/list_followers.sql");
+pub const SQL_LIST_FOLLOWING: &str = include_str!("../../../database/queries/follows/list_following.sql");
+
+// Feed
+pub const SQL_FEED: &str = include_str!("../../../database/queries/feed/list.sql");
+
+// Hashtags
+pub const SQL_LINK_POST_HASHTAGS: &str = include_str!("../../../database/queries/hashtags/link_post.sql");
+pub const SQL_UNLINK_POST_HASHTAGS: &str = include_str!("../../../database/queries/hashtags/unlink_post.sql");
+pub const SQL_LIST_HASHTAG_POSTS: &str = include_str!("../../../database/queries/hashtags/list_posts.sql");
+
+// Notifications
+pub const SQL_CREATE_MENTION_NOTIFICATIONS: &str = include_str!("../../../database/queries/notifications/create_mentions.sql");
+pub const SQL_LIST_NOTIFICATIONS: &str = include_str!("../../../database/queries/notifications/list.sql");
+pub const SQL_MARK_NOTIFICATION_READ: &str = include_str!("../../../database/queries/notifications/mark_read.sql");
+pub const SQL_UNREAD_NOTIFICATIONS_COUNT: &str = include_str!("../../../database/queries/notifications/unread_count.sql");
+
+// Conversations
+pub const SQL_CREATE_CONVERSATION: &str = include_str!("../../../database/queries/conversations/create.sql");
+pub const SQL_ADD_CONVERSATION_PARTICIPANTS: &str = include_str!("../../../database/queries/conversations/add_participants.sql");
+pub const SQL_LIST_CONVERSATIONS: &str = include_str!("../../../database/queries/conversations/list.sql");
+pub const SQL_IS_CONVERSATION_PARTICIPANT: &str = include_str!("../../../database/queries/conversations/is_participant.sql");
+pub const SQL_CREATE_MESSAGE: &str = include_str!("../../../database/queries/conversations/create_message.sql");
+pub const SQL_LIST_MESSAGES: &str = include_str!("../../../database/queries/conversations/list_messages.sql");
+
+// Admin
+pub const SQL_ADMIN_STATS: &str = include_str!("../../../database/queries/admin/stats.sql");
+pub const SQL_POSTS_PER_DAY: &str = include_str!("../../../database/queries/admin/posts_per_day.sql");
+pub const SQL_SEED_SETTINGS: &str = include_str!("../../../database/queries/admin/seed_settings.sql");
+pub const SQL_SEED_COPY_USERS: &str = include_str!("../../../database/queries/admin/seed_copy_users.sql");
+pub const SQL_SEED_COPY_POSTS: &str = include_str!("../../../database/queries/admin/seed_copy_posts.sql");
+pub const SQL_SEED_COPY_COMMENTS: &str = include_str!("../../../database/queries/admin/seed_copy_comments.sql");
+pub const SQL_SEED_ANALYZE: &str = include_str!("../../../database/queries/admin/seed_analyze.sql");
+pub const SQL_COMMENT_COUNTER_TRIGGER_SKIPPABLE: &str =
+    include_str!("../../../database/queries/comments/counter_trigger_skippable.sql");
+
+// Health checks
+pub const SQL_HEALTH_PING: &str = include_str!("../../../database/queries/health/ping.sql");
+
+// Cache invalidation
+pub const SQL_NOTIFY_CACHE: &str = include_str!("../../../database/queries/cache/notify.sql");
+
+// Idempotency keys
+pub const SQL_RESERVE_IDEMPOTENCY_KEY: &str = include_str!("../../../database/queries/idempotency/reserve.sql");
+pub const SQL_GET_IDEMPOTENCY_KEY: &str = include_str!("../../../database/queries/idempotency/get.sql");
+pub const SQL_COMPLETE_IDEMPOTENCY_KEY: &str = include_str!("../../../database/queries/idempotency/complete.sql");
+pub const SQL_RELEASE_IDEMPOTENCY_KEY: &str = include_str!("../../../database/queries/idempotency/release.sql");
+pub const SQL_PURGE_IDEMPOTENCY_KEYS: &str = include_str!("../../../database/queries/idempotency/purge_expired.sql");
+
+// Statements run as they are above, prepared on each connection by the startup warmup.
+// Not the bases the handlers append filters to (posts listing and count, post comments),
+// nor the startup-only like counter check.
+pub const PREPARED_QUERIES: &[&str] = &[
+    SQL_LOGIN,
+    SQL_ME,
+    SQL_USER_STATUS,
+    SQL_CREATE_USER,
+    SQL_GET_USER,
+    SQL_LIST_USERS,
+    SQL_LIST_USERS_KEYSET,
+    SQL_COUNT_USERS,
+    SQL_UPDATE_USER,
+    SQL_DELETE_USER,
+    SQL_SEARCH_USERS,
+    SQL_IMPERSONATE_USER,
+    SQL_IMPORT_USERS,
+    SQL_UPDATE_PROFILE,
+    SQL_USER_ACTIVITY,
+    SQL_UPDATE_USER_AVATAR,
+    SQL_CREATE_POST,
+    SQL_CREATE_POSTS_BATCH,
+    SQL_COUNT_PUBLISHED_POSTS,
+    SQL_LIST_POSTS_BY_AUTHOR,
+    SQL_TRENDING_POSTS,
+    SQL_GET_POST,
+    SQL_LOOKUP_POSTS,
+    SQL_GET_POST_AUTHOR,
+    SQL_UPDATE_POST,
+    SQL_DELETE_POST,
+    SQL_PUBLISH_POST,
+    SQL_PUBLISH_SCHEDULED_POSTS,
+    SQL_RESTORE_POST,
+    SQL_REPOST_POST,
+    SQL_CREATE_COMMENT,
+    SQL_COUNT_POST_COMMENTS,
+    SQL_GET_COMMENT_AUTHOR,
+    SQL_UPDATE_COMMENT,
+    SQL_DELETE_COMMENT,
+    SQL_CREATE_LIKE,
+    SQL_LIST_LIKED_POSTS,
+    SQL_DELETE_LIKE,
+    SQL_CREATE_LIKE_COUNTED,
+    SQL_DELETE_LIKE_COUNTED,
+    SQL_CREATE_BOOKMARK,
+    SQL_DELETE_BOOKMARK,
+    SQL_LIST_BOOKMARKS,
+    SQL_CREATE_FOLLOW,
+    SQL_DELETE_FOLLOW,
+    SQL_LIST_FOLLOWERS,
+    SQL_LIST_FOLLOWING,
+    SQL_FEED,
+    SQL_LINK_POST_HASHTAGS,
+    SQL_UNLINK_POST_HASHTAGS,
+    SQL_LIST_HASHTAG_POSTS,
+    SQL_CREATE_MENTION_NOTIFICATIONS,
+    SQL_LIST_NOTIFICATIONS,
+    SQL_MARK_NOTIFICATION_READ,
+    SQL_UNREAD_NOTIFICATIONS_COUNT,
+    SQL_CREATE_CONVERSATION,
+    SQL_ADD_CONVERSATION_PARTICIPANTS,
+    SQL_LIST_CONVERSATIONS,
+    SQL_IS_CONVERSATION_PARTICIPANT,
+    SQL_CREATE_MESSAGE,
+    SQL_LIST_MESSAGES,
+    SQL_ADMIN_STATS,
+    SQL_POSTS_PER_DAY,
+    SQL_RESERVE_IDEMPOTENCY_KEY,
+    SQL_GET_IDEMPOTENCY_KEY,
+    SQL_COMPLETE_IDEMPOTENCY_KEY,
+    SQL_RELEASE_IDEMPOTENCY_KEY,
+    SQL_PURGE_IDEMPOTENCY_KEYS,
+];
+
+// Name of each statement above, for the database spans of otel builds
+#[cfg(feature = "otel")]
+macro_rules! named {
+    ($($name:ident,)*) => {
+        &[$((stringify!($name), $name)),*]
+    };
+}
+
+#[cfg(feature = "otel")]
+pub const STATEMENT_NAMES: &[(&str, &str)] = named!(
+    SQL_LOGIN,
+    SQL_ME,
+    SQL_USER_STATUS,
+    SQL_CREATE_USER,
+    SQL_GET_USER,
+    SQL_LIST_USERS,
+    SQL_LIST_USERS_KEYSET,
+    SQL_COUNT_USERS,
+    SQL_UPDATE_USER,
+    SQL_DELETE_USER,
+    SQL_SEARCH_USERS,
+    SQL_IMPERSONATE_USER,
+    SQL_IMPORT_USERS,
+    SQL_UPDATE_PROFILE,
+    SQL_USER_ACTIVITY,
+    SQL_UPDATE_USER_AVATAR,
+    SQL_CREATE_POST,
+    SQL_CREATE_POSTS_BATCH,
+    SQL_SELECT_POSTS,
+    SQL_COUNT_PUBLISHED_POSTS,
+    SQL_COUNT_POSTS,
+    SQL_LIST_POSTS_BY_AUTHOR,
+    SQL_TRENDING_POSTS,
+    SQL_GET_POST,
+    SQL_LOOKUP_POSTS,
+    SQL_GET_POST_AUTHOR,
+    SQL_UPDATE_POST,
+    SQL_DELETE_POST,
+    SQL_PUBLISH_POST,
+    SQL_PUBLISH_SCHEDULED_POSTS,
+    SQL_RESTORE_POST,
+    SQL_REPOST_POST,
+    SQL_CREATE_COMMENT,
+    SQL_LIST_POST_COMMENTS,
+    SQL_COUNT_POST_COMMENTS,
+    SQL_GET_COMMENT_AUTHOR,
+    SQL_UPDATE_COMMENT,
+    SQL_DELETE_COMMENT,
+    SQL_CREATE_LIKE,
+    SQL_LIST_LIKED_POSTS,
+    SQL_DELETE_LIKE,
+    SQL_CREATE_LIKE_COUNTED,
+    SQL_DELETE_LIKE_COUNTED,
+    SQL_LIKE_COUNTER_TRIGGERS_SKIPPABLE,
+    SQL_CREATE_BOOKMARK,
+    SQL_DELETE_BOOKMARK,
+    SQL_LIST_BOOKMARKS,
+    SQL_CREATE_FOLLOW,
+    SQL_DELETE_FOLLOW,
+    SQL_LIST_FOLLOWERS,
+    SQL_LIST_FOLLOWING,
+    SQL_FEED,
+    SQL_LINK_POST_HASHTAGS,
+    SQL_UNLINK_POST_HASHTAGS,
+    SQL_LIST_HASHTAG_POSTS,
+    SQL_CREATE_MENTION_NOTIFICATIONS,
+    SQL_LIST_NOTIFICATIONS,
+    SQL_MARK_NOTIFICATION_READ,
+    SQL_UNREAD_NOTIFICATIONS_COUNT,
+    SQL_CREATE_CONVERSATION,
+    SQL_ADD_CONVERSATION_PARTICIPANTS,
+    SQL_LIST_CONVERSATIONS,
+    SQL_IS_CONVERSATION_PARTICIPANT,
+    SQL_CREATE_MESSAGE,
+    SQL_LIST_MESSAGES,
+    SQL_ADMIN_STATS,
+    SQL_POSTS_PER_DAY,
+    SQL_SEED_SETTINGS,
+    SQL_SEED_COPY_USERS,
+    SQL_SEED_COPY_POSTS,
+    SQL_SEED_COPY_COMMENTS,
+    SQL_SEED_ANALYZE,
+    SQL_COMMENT_COUNTER_TRIGGER_SKIPPABLE,
+    SQL_HEALTH_PING,
+    SQL_NOTIFY_CACHE,
+    SQL_RESERVE_IDEMPOTENCY_KEY,
+    SQL_GET_IDEMPOTENCY_KEY,
+    SQL_COMPLETE_IDEMPOTENCY_KEY,
+    SQL_RELEASE_IDEMPOTENCY_KEY,
+    SQL_PURGE_IDEMPOTENCY_KEYS,
+);
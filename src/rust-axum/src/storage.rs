@@ -0,0 +1,133 @@
+use bytes::Bytes;
+use object_store::{
+    aws::{AmazonS3, AmazonS3Builder},
+    path::Path as ObjectPath,
+    Attribute, Attributes, ObjectStore, PutOptions, PutPayload,
+};
+use std::{
+    env,
+    future::Future,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::Arc,
+};
+
+use crate::error::AppError;
+
+// Route the local backend's files are served from
+pub const LOCAL_UPLOADS_ROUTE: &str = "/uploads";
+
+pub type StorageFuture<'a> = Pin<Box<dyn Future<Output = Result<(), AppError>> + Send + 'a>>;
+
+// Where uploaded files (avatars) are written.
+// Keys are relative paths such as "avatars/<user_id>/<file>.png".
+pub trait StorageBackend: Send + Sync {
+    fn put<'a>(&'a self, key: &'a str, content_type: &'a str, data: Bytes) -> StorageFuture<'a>;
+
+    // Public URL a stored key is reachable at
+    fn url(&self, key: &str) -> String;
+
+    // Directory to serve under LOCAL_UPLOADS_ROUTE, only for backends writing to disk
+    fn local_dir(&self) -> Option<&Path> {
+        None
+    }
+}
+
+pub type Storage = Arc<dyn StorageBackend>;
+
+fn storage_error(e: impl std::fmt::Display) -> AppError {
+    tracing::error!("Storage error: {}", e);
+    AppError::InternalServerError("Failed to store file".to_string())
+}
+
+pub struct LocalStorage {
+    root: PathBuf,
+    base_url: String,
+}
+
+impl StorageBackend for LocalStorage {
+    fn put<'a>(&'a self, key: &'a str, _content_type: &'a str, data: Bytes) -> StorageFuture<'a> {
+        Box::pin(async move {
+            let path = self.root.join(key);
+            if let Some(parent) = path.parent() {
+                tokio::fs::create_dir_all(parent).await.map_err(storage_error)?;
+            }
+            tokio::fs::write(&path, &data).await.map_err(storage_error)
+        })
+    }
+
+    fn url(&self, key: &str) -> String {
+        format!("{}/{}", self.base_url, key)
+    }
+
+    fn local_dir(&self) -> Option<&Path> {
+        Some(&self.root)
+    }
+}
+
+// Any S3-compatible object store (AWS, MinIO, R2...)
+pub struct S3Storage {
+    store: AmazonS3,
+    base_url: String,
+}
+
+impl StorageBackend for S3Storage {
+    fn put<'a>(&'a self, key: &'a str, content_type: &'a str, data: Bytes) -> StorageFuture<'a> {
+        Box::pin(async move {
+            let options = PutOptions {
+                attributes: Attributes::from_iter([(Attribute::ContentType, content_type.to_string())]),
+                ..Default::default()
+            };
+            self.store
+                .put_opts(&ObjectPath::from(key), PutPayload::from(data), options)
+                .await
+                .map_err(storage_error)?;
+            Ok(())
+        })
+    }
+
+    fn url(&self, key: &str) -> String {
+        format!("{}/{}", self.base_url, key)
+    }
+}
+
+// Build the upload storage from the environment.
+// STORAGE_BACKEND is "local" (default) or "s3"; S3 credentials come from the usual AWS_* variables.
+pub fn storage_from_env() -> Result<Storage, Box<dyn std::error::Error>> {
+    let public_url = env::var("STORAGE_PUBLIC_URL")
+        .ok()
+        .map(|url| url.trim_end_matches('/').to_string());
+
+    match env::var("STORAGE_BACKEND").as_deref() {
+        Ok("s3") => {
+            let bucket = env::var("S3_BUCKET").map_err(|_| "S3_BUCKET is required when STORAGE_BACKEND=s3")?;
+            let endpoint = env::var("S3_ENDPOINT").ok();
+
+            let mut builder = AmazonS3Builder::from_env().with_bucket_name(&bucket);
+            if let Ok(region) = env::var("S3_REGION") {
+                builder = builder.with_region(region);
+            }
+            if let Some(endpoint) = &endpoint {
+                builder = builder
+                    .with_endpoint(endpoint)
+                    .with_allow_http(endpoint.starts_with("http://"));
+            }
+
+            // Path-style URLs work for both AWS and self-hosted endpoints
+            let base_url = public_url.unwrap_or_else(|| match &endpoint {
+                Some(endpoint) => format!("{}/{}", endpoint.trim_end_matches('/'), bucket),
+                None => format!("https://{}.s3.amazonaws.com", bucket),
+            });
+
+            Ok(Arc::new(S3Storage {
+                store: builder.build()?,
+                base_url,
+            }))
+        }
+        Ok("local") | Err(_) => Ok(Arc::new(LocalStorage {
+            root: PathBuf::from(env::var("STORAGE_LOCAL_DIR").unwrap_or_else(|_| "./uploads".to_string())),
+            base_url: public_url.unwrap_or_else(|| LOCAL_UPLOADS_ROUTE.to_string()),
+        })),
+        Ok(other) => Err(format!("Unknown STORAGE_BACKEND: {}", other).into()),
+    }
+}
@@ -0,0 +1,88 @@
+use axum::{
+    body::{Body, Bytes},
+    http::{header, HeaderValue},
+    response::{IntoResponse, Response},
+};
+use futures_util::{stream, StreamExt, TryStreamExt};
+use serde::Serialize;
+use sqlx::{postgres::PgRow, FromRow, PgPool, Postgres, QueryBuilder};
+use std::{env, mem, sync::LazyLock};
+use tokio::sync::mpsc;
+
+use crate::{casing::to_json_bytes, error::AppError, request_id::spawn_in_request};
+
+// Pages of at least this many rows are streamed, smaller ones are faster serialized at once.
+// Above the default MAX_PAGE_SIZE: streaming only starts once larger pages are allowed.
+pub static STREAM_MIN_PAGE_SIZE: LazyLock<i64> = LazyLock::new(|| {
+    env::var("STREAM_MIN_PAGE_SIZE")
+        .ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .filter(|&size| size > 0)
+        .unwrap_or(200)
+});
+
+// Body bytes sent at once, and chunks buffered ahead of a slow client
+const CHUNK_BYTES: usize = 16 * 1024;
+const BUFFERED_CHUNKS: usize = 4;
+
+// Runs `query` and streams its rows as a JSON array, serialized as they are fetched, so
+// memory stays bounded by a few chunks whatever the page size. A failure before the first
+// chunk is a regular error response; later ones can only abort the response.
+pub async fn stream_json_array<R, T>(
+    pool: PgPool,
+    mut query: QueryBuilder<'static, Postgres>,
+    to_item: fn(R) -> T,
+) -> Result<Response, AppError>
+where
+    R: for<'r> FromRow<'r, PgRow> + Send + Unpin + 'static,
+    T: Serialize + 'static,
+{
+    let (sender, mut receiver) = mpsc::channel::<Result<Bytes, AppError>>(BUFFERED_CHUNKS);
+    spawn_in_request(async move {
+        let mut rows = query.build_query_as::<R>().fetch(&pool);
+        let mut chunk = Vec::with_capacity(CHUNK_BYTES);
+        chunk.push(b'[');
+        let mut empty = true;
+        let mut streaming = false;
+        let result = loop {
+            let row = match rows.try_next().await {
+                Ok(Some(row)) => row,
+                Ok(None) => {
+                    chunk.push(b']');
+                    break Ok(Bytes::from(chunk));
+                }
+                Err(e) => break Err(AppError::from(e)),
+            };
+            if !empty {
+                chunk.push(b',');
+            }
+            empty = false;
+            match to_json_bytes(&to_item(row)) {
+                Ok(item) => chunk.extend_from_slice(&item),
+                Err(e) => break Err(e),
+            }
+            if chunk.len() >= CHUNK_BYTES {
+                // Stops fetching once the client is gone
+                let full = mem::replace(&mut chunk, Vec::with_capacity(CHUNK_BYTES));
+                if sender.send(Ok(Bytes::from(full))).await.is_err() {
+                    return;
+                }
+                streaming = true;
+            }
+        };
+        if let (true, Err(e)) = (streaming, &result) {
+            tracing::error!("Streamed response aborted: {}", e);
+        }
+        let _ = sender.send(result).await;
+    });
+
+    let first = receiver.recv().await.unwrap_or_else(|| {
+        Err(AppError::InternalServerError("Streamed response ended early".to_string()))
+    })?;
+    let rest = stream::poll_fn(move |cx| receiver.poll_recv(cx));
+    Ok((
+        [(header::CONTENT_TYPE, HeaderValue::from_static("application/json"))],
+        Body::from_stream(stream::once(async { Ok(first) }).chain(rest)),
+    )
+        .into_response())
+}
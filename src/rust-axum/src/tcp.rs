@@ -0,0 +1,71 @@
+use axum::serve::{ListenerExt, TapIo};
+use socket2::{SockRef, TcpKeepalive};
+use std::{
+    env, io,
+    net::{Ipv4Addr, SocketAddr},
+    time::Duration,
+};
+use tokio::net::{TcpListener, TcpSocket, TcpStream};
+
+fn env_u64(name: &str) -> Option<u64> {
+    env::var(name).ok().and_then(|v| v.parse::<u64>().ok())
+}
+
+// Listening socket tuned from the environment:
+// - TCP_NODELAY: disable Nagle's algorithm on accepted connections, so small responses
+//   aren't held back waiting for the client's ACK (default: true)
+// - TCP_LISTEN_BACKLOG: pending connections queued before accept (default: 1024)
+// - TCP_KEEPALIVE_SECS / TCP_KEEPALIVE_INTERVAL_SECS: idle time before keep-alive probes
+//   are sent on accepted connections, and between probes (default: unset, no probes)
+// With `reuse_port` (sharded runtime) every shard binds a socket of its own to the port and
+// the kernel balances incoming connections across them.
+pub fn listener_from_env(
+    port: u16,
+    reuse_port: bool,
+) -> io::Result<TapIo<TcpListener, impl FnMut(&mut TcpStream) + Send + 'static>> {
+    let nodelay = env::var("TCP_NODELAY")
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(true);
+    let backlog = env_u64("TCP_LISTEN_BACKLOG")
+        .and_then(|backlog| u32::try_from(backlog).ok())
+        .filter(|&backlog| backlog > 0)
+        .unwrap_or(1024);
+    let keepalive_secs = env_u64("TCP_KEEPALIVE_SECS").filter(|&secs| secs > 0);
+    let keepalive = keepalive_secs.map(|secs| {
+        let keepalive = TcpKeepalive::new().with_time(Duration::from_secs(secs));
+        match env_u64("TCP_KEEPALIVE_INTERVAL_SECS").filter(|&secs| secs > 0) {
+            Some(interval) => keepalive.with_interval(Duration::from_secs(interval)),
+            None => keepalive,
+        }
+    });
+
+    let socket = TcpSocket::new_v4()?;
+    socket.set_reuseaddr(true)?;
+    #[cfg(unix)]
+    socket.set_reuseport(reuse_port)?;
+    #[cfg(not(unix))]
+    if reuse_port {
+        return Err(io::Error::new(io::ErrorKind::Unsupported, "SO_REUSEPORT needs a unix platform"));
+    }
+    socket.bind(SocketAddr::from((Ipv4Addr::UNSPECIFIED, port)))?;
+    let listener = socket.listen(backlog)?;
+
+    tracing::info!(
+        "TCP: nodelay {}, backlog {}, keep-alive {}",
+        nodelay,
+        backlog,
+        keepalive_secs.map_or_else(|| "off".to_string(), |secs| format!("after {} s idle", secs))
+    );
+
+    Ok(listener.tap_io(move |stream| {
+        let socket = SockRef::from(&*stream);
+        if let Err(e) = socket.set_tcp_nodelay(nodelay) {
+            tracing::debug!("Failed to set TCP_NODELAY: {}", e);
+        }
+        if let Some(keepalive) = &keepalive {
+            if let Err(e) = socket.set_tcp_keepalive(keepalive) {
+                tracing::debug!("Failed to enable TCP keep-alive: {}", e);
+            }
+        }
+    }))
+}
@@ -0,0 +1,77 @@
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::Method,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::{env, time::Duration};
+
+use crate::error::{AppError, ErrorCode};
+
+// Time budgets of requests, past which the handler is dropped and a 504 TIMEOUT returned.
+// Login gets its own budget (bcrypt), other requests the read one (GET, HEAD) or the write one.
+// A budget only covers the handler up to its response headers, not a streamed body.
+#[derive(Debug, Clone, Copy)]
+pub struct RequestTimeouts {
+    read: Option<Duration>,
+    write: Option<Duration>,
+    login: Option<Duration>,
+}
+
+fn timeout_ms(name: &str) -> Option<Duration> {
+    env::var(name)
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .filter(|&ms| ms > 0)
+        .map(Duration::from_millis)
+}
+
+// REQUEST_TIMEOUT_READ_MS, REQUEST_TIMEOUT_WRITE_MS, REQUEST_TIMEOUT_LOGIN_MS, each unset by default
+pub fn request_timeouts_from_env() -> Option<RequestTimeouts> {
+    let timeouts = RequestTimeouts {
+        read: timeout_ms("REQUEST_TIMEOUT_READ_MS"),
+        write: timeout_ms("REQUEST_TIMEOUT_WRITE_MS"),
+        login: timeout_ms("REQUEST_TIMEOUT_LOGIN_MS"),
+    };
+    if timeouts.read.is_none() && timeouts.write.is_none() && timeouts.login.is_none() {
+        return None;
+    }
+    tracing::info!(
+        "Request timeouts: reads {:?}, writes {:?}, login {:?}",
+        timeouts.read,
+        timeouts.write,
+        timeouts.login
+    );
+    Some(timeouts)
+}
+
+impl RequestTimeouts {
+    fn budget(&self, request: &Request) -> Option<Duration> {
+        let path = request.extensions().get::<MatchedPath>().map(MatchedPath::as_str);
+        if path == Some("/auth/login") {
+            return self.login;
+        }
+        match *request.method() {
+            Method::GET | Method::HEAD => self.read,
+            _ => self.write,
+        }
+    }
+}
+
+pub async fn request_timeout_middleware(
+    State(timeouts): State<RequestTimeouts>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(budget) = timeouts.budget(&request) else {
+        return next.run(request).await;
+    };
+    match tokio::time::timeout(budget, next.run(request)).await {
+        Ok(response) => response,
+        Err(_) => AppError::Coded(
+            ErrorCode::Timeout,
+            format!("Request did not complete within {} ms", budget.as_millis()),
+        )
+        .into_response(),
+    }
+}
@@ -0,0 +1,73 @@
+use chrono::{DateTime, Datelike, SecondsFormat, Timelike, Utc};
+use serde::Serializer;
+
+// Longest timestamp written here: "2024-01-02T03:04:05.123456789Z"
+const MAX_LEN: usize = 30;
+
+// Response timestamps, identical to chrono's own serialization ("2024-01-02T03:04:05.123456Z",
+// fraction digits in groups of 3 and only when needed) but formatted into a stack buffer,
+// without going through fmt::Write for every field of every row.
+pub fn serialize<S: Serializer>(timestamp: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error> {
+    let mut buffer = [0u8; MAX_LEN];
+    match format(timestamp, &mut buffer) {
+        Some(len) => serializer.serialize_str(std::str::from_utf8(&buffer[..len]).map_err(serde::ser::Error::custom)?),
+        // Years outside 0000-9999 and leap seconds, which Postgres doesn't return anyway
+        None => serializer.serialize_str(&timestamp.to_rfc3339_opts(SecondsFormat::AutoSi, true)),
+    }
+}
+
+pub fn serialize_option<S: Serializer>(
+    timestamp: &Option<DateTime<Utc>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match timestamp {
+        Some(timestamp) => serialize(timestamp, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+fn format(timestamp: &DateTime<Utc>, buffer: &mut [u8; MAX_LEN]) -> Option<usize> {
+    let year = timestamp.year();
+    let nanos = timestamp.nanosecond();
+    if !(0..=9999).contains(&year) || nanos >= 1_000_000_000 {
+        return None;
+    }
+
+    write_digits(&mut buffer[0..4], year as u32);
+    buffer[4] = b'-';
+    write_digits(&mut buffer[5..7], timestamp.month());
+    buffer[7] = b'-';
+    write_digits(&mut buffer[8..10], timestamp.day());
+    buffer[10] = b'T';
+    write_digits(&mut buffer[11..13], timestamp.hour());
+    buffer[13] = b':';
+    write_digits(&mut buffer[14..16], timestamp.minute());
+    buffer[16] = b':';
+    write_digits(&mut buffer[17..19], timestamp.second());
+
+    let (fraction, digits) = if nanos == 0 {
+        (0, 0)
+    } else if nanos.is_multiple_of(1_000_000) {
+        (nanos / 1_000_000, 3)
+    } else if nanos.is_multiple_of(1_000) {
+        (nanos / 1_000, 6)
+    } else {
+        (nanos, 9)
+    };
+    let mut len = 19;
+    if digits > 0 {
+        buffer[19] = b'.';
+        write_digits(&mut buffer[20..20 + digits], fraction);
+        len = 20 + digits;
+    }
+    buffer[len] = b'Z';
+    Some(len + 1)
+}
+
+// Zero-padded to the slice length
+fn write_digits(out: &mut [u8], mut value: u32) {
+    for byte in out.iter_mut().rev() {
+        *byte = b'0' + (value % 10) as u8;
+        value /= 10;
+    }
+}
@@ -0,0 +1,380 @@
+use axum::{
+    extract::{FromRequest, FromRequestParts, Request},
+    http::request::Parts,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{env, sync::LazyLock};
+use unicode_normalization::{is_nfc, UnicodeNormalization};
+
+use crate::{
+    error::{AppError, ErrorCode},
+    extract::{Json, Query},
+    models::*,
+};
+
+// Post and comment lengths, MAX_POST_LENGTH / MAX_COMMENT_LENGTH override api/openapi.yaml's 280
+struct ContentLimits {
+    post: usize,
+    comment: usize,
+}
+
+static CONTENT_LIMITS: LazyLock<ContentLimits> = LazyLock::new(|| {
+    let limit = |name: &str| {
+        env::var(name)
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(280)
+    };
+    ContentLimits {
+        post: limit("MAX_POST_LENGTH"),
+        comment: limit("MAX_COMMENT_LENGTH"),
+    }
+});
+
+// Largest page a listing returns, MAX_PAGE_SIZE overrides it
+static MAX_PAGE_SIZE: LazyLock<i64> = LazyLock::new(|| {
+    env::var("MAX_PAGE_SIZE")
+        .ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .filter(|&size| size > 0)
+        .unwrap_or(100)
+});
+
+// Largest offset accepted, deeper OFFSET scans are rejected (default: unlimited,
+// KEYSET_MODE_MAX_OFFSET with PAGINATION_MODE=keyset for the listings without a cursor)
+static MAX_OFFSET: LazyLock<Option<i64>> = LazyLock::new(|| {
+    env::var("MAX_OFFSET")
+        .ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .filter(|&offset| offset >= 0)
+        .or_else(|| KEYSET_ONLY.then_some(KEYSET_MODE_MAX_OFFSET))
+});
+
+const KEYSET_MODE_MAX_OFFSET: i64 = 1000;
+
+// PAGINATION_MODE=keyset: listings supporting a cursor always page by keyset, a request
+// without one getting the first page, so no OFFSET scan is left on them
+static KEYSET_ONLY: LazyLock<bool> = LazyLock::new(|| match env::var("PAGINATION_MODE").as_deref() {
+    Ok("keyset") => {
+        tracing::info!("Keyset-only pagination");
+        true
+    }
+    Ok("offset") | Err(_) => false,
+    Ok(other) => {
+        tracing::warn!("Unknown PAGINATION_MODE {:?}, using offset", other);
+        false
+    }
+});
+
+// Other limits from api/openapi.yaml where it defines them, otherwise the users table columns
+const MAX_MESSAGE_LENGTH: usize = 2000;
+const MAX_BIO_LENGTH: usize = 500;
+const MAX_USERNAME_LENGTH: usize = 50;
+const MAX_EMAIL_LENGTH: usize = 255;
+const MIN_PASSWORD_LENGTH: usize = 8;
+// bcrypt ignores anything past 72 bytes
+const MAX_PASSWORD_BYTES: usize = 72;
+// Most rows of each kind generated by a single POST /admin/seed
+const MAX_SEED_ROWS: u32 = 10_000_000;
+
+#[derive(Debug, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+// Every rule violation of a request body, reported together as a 422
+#[derive(Debug, Default)]
+pub struct FieldErrors(Vec<FieldError>);
+
+impl FieldErrors {
+    pub fn add(&mut self, field: impl Into<String>, message: impl Into<String>) {
+        self.0.push(FieldError {
+            field: field.into(),
+            message: message.into(),
+        });
+    }
+
+    // Validates a nested value, its fields are reported as `<prefix>.<field>`
+    pub fn nested(&mut self, prefix: &str, value: &impl Validate) {
+        let mut nested = FieldErrors::default();
+        value.validate(&mut nested);
+        self.0.extend(nested.0.into_iter().map(|error| FieldError {
+            field: format!("{}.{}", prefix, error.field),
+            message: error.message,
+        }));
+    }
+
+    pub fn into_result(self) -> Result<(), AppError> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(AppError::Validation(self.0))
+        }
+    }
+
+    pub fn into_vec(self) -> Vec<FieldError> {
+        self.0
+    }
+}
+
+pub trait Validate {
+    // Cleans up the value before its rules are checked
+    fn sanitize(&mut self) {}
+
+    fn validate(&self, errors: &mut FieldErrors);
+}
+
+// Json<T> that also sanitizes T and runs its validation rules.
+// Malformed bodies keep Json's rejection, rule violations are a 422 listing the fields.
+pub struct ValidatedJson<T>(pub T);
+
+impl<T, S> FromRequest<S> for ValidatedJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(mut value) = Json::<T>::from_request(req, state).await?;
+        value.sanitize();
+
+        let mut errors = FieldErrors::default();
+        value.validate(&mut errors);
+        errors.into_result()?;
+
+        Ok(Self(value))
+    }
+}
+
+// Query strings with `limit` / `offset` pagination
+pub trait Paginated {
+    fn limit_mut(&mut self) -> &mut i64;
+
+    // Keyset-only listings have no offset
+    fn offset(&self) -> i64 {
+        0
+    }
+
+    // Listings also paging by keyset, once given a `cursor`
+    fn cursor_mut(&mut self) -> Option<&mut Option<String>> {
+        None
+    }
+}
+
+// Query<T> with its page bounds checked: a limit above MAX_PAGE_SIZE is capped,
+// a limit below 1, a negative offset or one above MAX_OFFSET is a 422.
+// With PAGINATION_MODE=keyset a missing cursor is the first page and offsets are a 422.
+pub struct PagedQuery<T>(pub T);
+
+impl<T, S> FromRequestParts<S> for PagedQuery<T>
+where
+    T: DeserializeOwned + Paginated,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(mut value) = Query::<T>::from_request_parts(parts, state).await?;
+
+        let offset = value.offset();
+        if offset < 0 {
+            return Err(AppError::Coded(
+                ErrorCode::InvalidPagination,
+                "offset must not be negative".to_string(),
+            ));
+        }
+        if *KEYSET_ONLY {
+            if let Some(cursor) = value.cursor_mut() {
+                if offset > 0 {
+                    return Err(AppError::Coded(
+                        ErrorCode::InvalidPagination,
+                        "offset is not supported with keyset pagination, follow nextCursor".to_string(),
+                    ));
+                }
+                cursor.get_or_insert_with(String::new);
+            }
+        }
+        if let Some(max_offset) = *MAX_OFFSET {
+            if offset > max_offset {
+                return Err(AppError::Coded(
+                    ErrorCode::InvalidPagination,
+                    format!("offset must be at most {}, page with a cursor where supported", max_offset),
+                ));
+            }
+        }
+        let limit = value.limit_mut();
+        if *limit < 1 {
+            return Err(AppError::Coded(
+                ErrorCode::InvalidPagination,
+                "limit must be at least 1".to_string(),
+            ));
+        }
+        *limit = (*limit).min(*MAX_PAGE_SIZE);
+
+        Ok(Self(value))
+    }
+}
+
+// Drops control characters other than newlines and tabs ("\r\n" becomes "\n") and normalizes to NFC
+fn sanitize_text(text: &mut String) {
+    let keep = |c: char| !c.is_control() || c == '\n' || c == '\t';
+    if !text.chars().all(keep) || !is_nfc(text) {
+        *text = text.chars().filter(|&c| keep(c)).nfc().collect();
+    }
+}
+
+// Lengths are counted in characters, not bytes
+fn check_text(errors: &mut FieldErrors, field: &str, value: &str, max: usize) {
+    if value.trim().is_empty() {
+        errors.add(field, "must not be empty");
+    } else if value.chars().count() > max {
+        errors.add(field, format!("must be at most {} characters", max));
+    }
+}
+
+fn check_bio(errors: &mut FieldErrors, bio: Option<&str>) {
+    if bio.is_some_and(|bio| bio.chars().count() > MAX_BIO_LENGTH) {
+        errors.add("bio", format!("must be at most {} characters", MAX_BIO_LENGTH));
+    }
+}
+
+fn check_username(errors: &mut FieldErrors, username: &str) {
+    let length = username.chars().count();
+    if length == 0 || length > MAX_USERNAME_LENGTH {
+        errors.add(
+            "username",
+            format!("must be between 1 and {} characters", MAX_USERNAME_LENGTH),
+        );
+    } else if !username
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+    {
+        errors.add("username", "may only contain letters, digits, '_', '.' and '-'");
+    }
+}
+
+// Deliberately loose: one '@' with a non-empty local part and a dotted domain
+fn check_email(errors: &mut FieldErrors, email: &str) {
+    let valid = email.len() <= MAX_EMAIL_LENGTH
+        && !email.chars().any(char::is_whitespace)
+        && email.split_once('@').is_some_and(|(local, domain)| {
+            !local.is_empty()
+                && !domain.contains('@')
+                && domain.split('.').count() >= 2
+                && domain.split('.').all(|label| !label.is_empty())
+        });
+    if !valid {
+        errors.add("email", "must be a valid email address");
+    }
+}
+
+fn check_password(errors: &mut FieldErrors, password: &str) {
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
+        errors.add(
+            "password",
+            format!("must be at least {} characters", MIN_PASSWORD_LENGTH),
+        );
+    } else if password.len() > MAX_PASSWORD_BYTES {
+        errors.add("password", format!("must be at most {} bytes", MAX_PASSWORD_BYTES));
+    }
+}
+
+impl Validate for CreateUser {
+    fn validate(&self, errors: &mut FieldErrors) {
+        check_username(errors, &self.username);
+        check_email(errors, &self.email);
+        check_password(errors, &self.password);
+    }
+}
+
+// Posts are written by seeded users, comments on seeded posts
+impl Validate for SeedRequest {
+    fn validate(&self, errors: &mut FieldErrors) {
+        for (field, rows) in [("users", self.users), ("posts", self.posts), ("comments", self.comments)] {
+            if rows > MAX_SEED_ROWS {
+                errors.add(field, format!("must be at most {}", MAX_SEED_ROWS));
+            }
+        }
+        if self.users == 0 {
+            errors.add("users", "must be at least 1");
+        }
+        if self.comments > 0 && self.posts == 0 {
+            errors.add("posts", "must be at least 1 to seed comments");
+        }
+    }
+}
+
+impl Validate for UpdateUser {
+    fn validate(&self, errors: &mut FieldErrors) {
+        check_bio(errors, self.bio.as_deref());
+    }
+}
+
+impl Validate for UpdateProfile {
+    fn validate(&self, errors: &mut FieldErrors) {
+        if let Some(username) = &self.username {
+            check_username(errors, username);
+        }
+        check_bio(errors, self.bio.as_deref());
+    }
+}
+
+impl Validate for PostCreate {
+    fn sanitize(&mut self) {
+        sanitize_text(&mut self.content);
+    }
+
+    fn validate(&self, errors: &mut FieldErrors) {
+        check_text(errors, "content", &self.content, CONTENT_LIMITS.post);
+    }
+}
+
+impl Validate for PostBatchCreate {
+    fn sanitize(&mut self) {
+        self.posts.iter_mut().for_each(Validate::sanitize);
+    }
+
+    fn validate(&self, errors: &mut FieldErrors) {
+        for (index, post) in self.posts.iter().enumerate() {
+            errors.nested(&format!("posts[{}]", index), post);
+        }
+    }
+}
+
+impl Validate for UpdatePost {
+    fn sanitize(&mut self) {
+        sanitize_text(&mut self.content);
+    }
+
+    fn validate(&self, errors: &mut FieldErrors) {
+        check_text(errors, "content", &self.content, CONTENT_LIMITS.post);
+    }
+}
+
+impl Validate for CommentCreate {
+    fn sanitize(&mut self) {
+        sanitize_text(&mut self.content);
+    }
+
+    fn validate(&self, errors: &mut FieldErrors) {
+        check_text(errors, "content", &self.content, CONTENT_LIMITS.comment);
+    }
+}
+
+impl Validate for UpdateComment {
+    fn sanitize(&mut self) {
+        sanitize_text(&mut self.content);
+    }
+
+    fn validate(&self, errors: &mut FieldErrors) {
+        check_text(errors, "content", &self.content, CONTENT_LIMITS.comment);
+    }
+}
+
+impl Validate for MessageCreate {
+    fn validate(&self, errors: &mut FieldErrors) {
+        check_text(errors, "content", &self.content, MAX_MESSAGE_LENGTH);
+    }
+}
@@ -0,0 +1,67 @@
+use axum::{
+    body::{to_bytes, Body},
+    extract::ConnectInfo,
+    http::Request,
+    Router,
+};
+use futures_util::future::try_join_all;
+use sqlx::{Executor, PgPool};
+use std::{
+    env,
+    net::{Ipv4Addr, SocketAddr},
+    time::Instant,
+};
+use tower::ServiceExt;
+
+use crate::sql::PREPARED_QUERIES;
+
+// Anonymous reads run through the Router once the pools are warm
+const WARMUP_REQUESTS: &[&str] = &["/posts", "/posts/count", "/posts/trending"];
+
+// Startup warmup before the listener is bound, enabled by WARMUP, so the first seconds of a
+// benchmark don't pay for opening connections and preparing statements.
+pub fn warmup_enabled() -> bool {
+    env::var("WARMUP")
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(false)
+}
+
+// Opens the pool's DB_POOL_MIN connections at once and prepares every statement of
+// PREPARED_QUERIES on each of them, into the statement cache the handlers then hit.
+// Parameter types are inferred by Postgres there, binds of other types would fail.
+pub async fn warm_pool(pool: &PgPool, name: &str) -> Result<(), sqlx::Error> {
+    let started = Instant::now();
+    let connections = pool.options().get_min_connections().max(1);
+    let mut held = try_join_all((0..connections).map(|_| pool.acquire())).await?;
+    for connection in &mut held {
+        for sql in PREPARED_QUERIES {
+            connection.prepare(sql).await?;
+        }
+    }
+    tracing::info!(
+        "Warmup: {} {} connection(s) opened, {} statements prepared on each in {:?}",
+        held.len(),
+        name,
+        PREPARED_QUERIES.len(),
+        started.elapsed()
+    );
+    Ok(())
+}
+
+// Runs WARMUP_REQUESTS through `app`, a failing one is logged but doesn't stop startup
+pub async fn warm_routes(app: &Router) {
+    let started = Instant::now();
+    for path in WARMUP_REQUESTS {
+        let mut request = Request::get(*path).body(Body::empty()).expect("valid request");
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(SocketAddr::from((Ipv4Addr::LOCALHOST, 0))));
+        let Ok(response) = app.clone().oneshot(request).await;
+        let status = response.status();
+        let _ = to_bytes(response.into_body(), usize::MAX).await;
+        if !status.is_success() {
+            tracing::warn!("Warmup request GET {} answered {}", path, status);
+        }
+    }
+    tracing::info!("Warmup: {} requests served in {:?}", WARMUP_REQUESTS.len(), started.elapsed());
+}